@@ -0,0 +1,439 @@
+use std::{
+    ffi::c_void,
+    mem::size_of,
+    slice::{from_raw_parts, from_raw_parts_mut},
+};
+
+use super::{AudioFormat, Error, RawFrameBuffer, RawFrameBufferMut, Result, I24};
+
+/**
+ * The buffer of interleaved samples for reading which format is known at runtime only
+ *
+ * It is used with [`Dynamic`](crate::Dynamic) frame type,
+ * so the same code can process whatever format the device negotiated.
+ * The number of channels can be queried from the stream.
+ */
+#[derive(Debug)]
+pub enum AudioBuffer<'a> {
+    /**
+     * Signed 16-bit integer samples
+     */
+    I16(&'a [i16]),
+
+    /**
+     * Packed signed 24-bit integer samples
+     */
+    I24(&'a [I24]),
+
+    /**
+     * Signed 32-bit integer samples
+     */
+    I32(&'a [i32]),
+
+    /**
+     * Single precision floating point samples
+     */
+    F32(&'a [f32]),
+}
+
+impl<'a> AudioBuffer<'a> {
+    /**
+     * Get the format of samples
+     */
+    pub fn get_format(&self) -> AudioFormat {
+        match self {
+            AudioBuffer::I16(_) => AudioFormat::I16,
+            AudioBuffer::I24(_) => AudioFormat::I24,
+            AudioBuffer::I32(_) => AudioFormat::I32,
+            AudioBuffer::F32(_) => AudioFormat::F32,
+        }
+    }
+
+    /**
+     * Get the number of samples
+     */
+    pub fn len(&self) -> usize {
+        match self {
+            AudioBuffer::I16(data) => data.len(),
+            AudioBuffer::I24(data) => data.len(),
+            AudioBuffer::I32(data) => data.len(),
+            AudioBuffer::F32(data) => data.len(),
+        }
+    }
+
+    /**
+     * Check that buffer has no samples
+     */
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /**
+     * Convert samples to floating points in range `-1.0..1.0`
+     *
+     * Returns the number of converted samples which is minimal length of buffers.
+     */
+    pub fn copy_to_f32(&self, output: &mut [f32]) -> usize {
+        match self {
+            AudioBuffer::I16(data) => convert(data, output, |s| i16_to_f32(*s)),
+            AudioBuffer::I24(data) => convert(data, output, |s| s.to_f32()),
+            AudioBuffer::I32(data) => convert(data, output, |s| i32_to_f32(*s)),
+            AudioBuffer::F32(data) => convert(data, output, |s| *s),
+        }
+    }
+}
+
+impl<'a> RawFrameBuffer for AudioBuffer<'a> {
+    unsafe fn _wrap_raw(
+        data: *const c_void,
+        frames: usize,
+        channels: usize,
+        format: AudioFormat,
+    ) -> Result<Self> {
+        let samples = frames * channels;
+        Ok(match format {
+            AudioFormat::I16 => AudioBuffer::I16(from_raw_parts(data as _, samples)),
+            AudioFormat::I24 => AudioBuffer::I24(from_raw_parts(data as _, samples)),
+            AudioFormat::I32 => AudioBuffer::I32(from_raw_parts(data as _, samples)),
+            AudioFormat::F32 => AudioBuffer::F32(from_raw_parts(data as _, samples)),
+            // The opened stream always has a known PCM format
+            _ => return Err(Error::InvalidFormat),
+        })
+    }
+
+    fn _raw_frames(&self, channels: usize, format: AudioFormat) -> Result<(*const c_void, usize)> {
+        let data = match self {
+            AudioBuffer::I16(data) => data.as_ptr() as *const c_void,
+            AudioBuffer::I24(data) => data.as_ptr() as *const c_void,
+            AudioBuffer::I32(data) => data.as_ptr() as *const c_void,
+            AudioBuffer::F32(data) => data.as_ptr() as *const c_void,
+        };
+        Ok((
+            data,
+            frames_of(self.get_format(), self.len(), channels, format)?,
+        ))
+    }
+
+    fn _bytes_per_frame(&self, channels: usize) -> usize {
+        channels * bytes_per_sample(self.get_format())
+    }
+}
+
+/**
+ * The buffer of interleaved samples for writing which format is known at runtime only
+ *
+ * It is used with [`Dynamic`](crate::Dynamic) frame type,
+ * so the same code can render whatever format the device negotiated.
+ * The number of channels can be queried from the stream.
+ */
+#[derive(Debug)]
+pub enum AudioBufferMut<'a> {
+    /**
+     * Signed 16-bit integer samples
+     */
+    I16(&'a mut [i16]),
+
+    /**
+     * Packed signed 24-bit integer samples
+     */
+    I24(&'a mut [I24]),
+
+    /**
+     * Signed 32-bit integer samples
+     */
+    I32(&'a mut [i32]),
+
+    /**
+     * Single precision floating point samples
+     */
+    F32(&'a mut [f32]),
+}
+
+impl<'a> AudioBufferMut<'a> {
+    /**
+     * Get the format of samples
+     */
+    pub fn get_format(&self) -> AudioFormat {
+        match self {
+            AudioBufferMut::I16(_) => AudioFormat::I16,
+            AudioBufferMut::I24(_) => AudioFormat::I24,
+            AudioBufferMut::I32(_) => AudioFormat::I32,
+            AudioBufferMut::F32(_) => AudioFormat::F32,
+        }
+    }
+
+    /**
+     * Get the number of samples
+     */
+    pub fn len(&self) -> usize {
+        match self {
+            AudioBufferMut::I16(data) => data.len(),
+            AudioBufferMut::I24(data) => data.len(),
+            AudioBufferMut::I32(data) => data.len(),
+            AudioBufferMut::F32(data) => data.len(),
+        }
+    }
+
+    /**
+     * Check that buffer has no samples
+     */
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /**
+     * Convert samples to floating points in range `-1.0..1.0`
+     *
+     * Returns the number of converted samples which is minimal length of buffers.
+     */
+    pub fn copy_to_f32(&self, output: &mut [f32]) -> usize {
+        match self {
+            AudioBufferMut::I16(data) => convert(data, output, |s| i16_to_f32(*s)),
+            AudioBufferMut::I24(data) => convert(data, output, |s| s.to_f32()),
+            AudioBufferMut::I32(data) => convert(data, output, |s| i32_to_f32(*s)),
+            AudioBufferMut::F32(data) => convert(data, output, |s| *s),
+        }
+    }
+
+    /**
+     * Convert samples from floating points in range `-1.0..1.0`
+     *
+     * The samples out of range will be clipped.
+     * Returns the number of converted samples which is minimal length of buffers.
+     */
+    pub fn copy_from_f32(&mut self, input: &[f32]) -> usize {
+        match self {
+            AudioBufferMut::I16(data) => convert(input, data, |s| f32_to_i16(*s)),
+            AudioBufferMut::I24(data) => convert(input, data, |s| I24::from_f32(*s)),
+            AudioBufferMut::I32(data) => convert(input, data, |s| f32_to_i32(*s)),
+            AudioBufferMut::F32(data) => convert(input, data, |s| *s),
+        }
+    }
+
+    /**
+     * Fill buffer with silence
+     */
+    pub fn fill_silence(&mut self) {
+        match self {
+            AudioBufferMut::I16(data) => data.fill(0),
+            AudioBufferMut::I24(data) => data.fill(I24::default()),
+            AudioBufferMut::I32(data) => data.fill(0),
+            AudioBufferMut::F32(data) => data.fill(0.0),
+        }
+    }
+}
+
+impl<'a> RawFrameBufferMut for AudioBufferMut<'a> {
+    unsafe fn _wrap_raw_mut(
+        data: *mut c_void,
+        frames: usize,
+        channels: usize,
+        format: AudioFormat,
+    ) -> Result<Self> {
+        let samples = frames * channels;
+        Ok(match format {
+            AudioFormat::I16 => AudioBufferMut::I16(from_raw_parts_mut(data as _, samples)),
+            AudioFormat::I24 => AudioBufferMut::I24(from_raw_parts_mut(data as _, samples)),
+            AudioFormat::I32 => AudioBufferMut::I32(from_raw_parts_mut(data as _, samples)),
+            AudioFormat::F32 => AudioBufferMut::F32(from_raw_parts_mut(data as _, samples)),
+            // The opened stream always has a known PCM format
+            _ => return Err(Error::InvalidFormat),
+        })
+    }
+
+    fn _raw_frames_mut(
+        &mut self,
+        channels: usize,
+        format: AudioFormat,
+    ) -> Result<(*mut c_void, usize)> {
+        let frames = frames_of(self.get_format(), self.len(), channels, format)?;
+        let data = match self {
+            AudioBufferMut::I16(data) => data.as_mut_ptr() as *mut c_void,
+            AudioBufferMut::I24(data) => data.as_mut_ptr() as *mut c_void,
+            AudioBufferMut::I32(data) => data.as_mut_ptr() as *mut c_void,
+            AudioBufferMut::F32(data) => data.as_mut_ptr() as *mut c_void,
+        };
+        Ok((data, frames))
+    }
+
+    fn _bytes_per_frame(&self, channels: usize) -> usize {
+        channels * bytes_per_sample(self.get_format())
+    }
+}
+
+/**
+ * The stream reads or writes the whole frames in its own format
+ */
+fn frames_of(
+    buffer_format: AudioFormat,
+    samples: usize,
+    channels: usize,
+    format: AudioFormat,
+) -> Result<usize> {
+    if buffer_format != format {
+        return Err(Error::InvalidFormat);
+    }
+    if channels == 0 {
+        return Err(Error::IllegalArgument);
+    }
+    Ok(samples / channels)
+}
+
+fn bytes_per_sample(format: AudioFormat) -> usize {
+    match format {
+        AudioFormat::I16 => size_of::<i16>(),
+        AudioFormat::I24 => size_of::<I24>(),
+        AudioFormat::I32 => size_of::<i32>(),
+        AudioFormat::F32 => size_of::<f32>(),
+        _ => 0,
+    }
+}
+
+fn convert<S, D>(input: &[S], output: &mut [D], func: impl Fn(&S) -> D) -> usize {
+    let len = input.len().min(output.len());
+    for (output, input) in output.iter_mut().zip(input) {
+        *output = func(input);
+    }
+    len
+}
+
+fn i16_to_f32(sample: i16) -> f32 {
+    sample as f32 / 32768.0
+}
+
+fn f32_to_i16(sample: f32) -> i16 {
+    (sample.clamp(-1.0, 1.0) * 32767.0) as i16
+}
+
+fn i32_to_f32(sample: i32) -> f32 {
+    sample as f32 / 2147483648.0
+}
+
+fn f32_to_i32(sample: f32) -> i32 {
+    (sample.clamp(-1.0, 1.0) * 2147483647.0) as i32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn i16_converts_to_f32() {
+        assert_eq!(i16_to_f32(0), 0.0);
+        assert_eq!(i16_to_f32(i16::MIN), -1.0);
+        assert_eq!(i16_to_f32(16384), 0.5);
+        assert!(i16_to_f32(i16::MAX) < 1.0);
+
+        assert_eq!(f32_to_i16(0.0), 0);
+        assert_eq!(f32_to_i16(1.0), i16::MAX);
+        assert_eq!(f32_to_i16(-1.0), -i16::MAX);
+        assert_eq!(f32_to_i16(0.5), 16383);
+        assert_eq!(f32_to_i16(2.0), i16::MAX);
+        assert_eq!(f32_to_i16(-2.0), -i16::MAX);
+        assert_eq!(f32_to_i16(f32::NAN), 0);
+    }
+
+    #[test]
+    fn i32_converts_to_f32() {
+        assert_eq!(i32_to_f32(0), 0.0);
+        assert_eq!(i32_to_f32(i32::MIN), -1.0);
+        assert_eq!(i32_to_f32(1 << 30), 0.5);
+        assert!(i32_to_f32(i32::MAX) <= 1.0);
+
+        assert_eq!(f32_to_i32(0.0), 0);
+        assert_eq!(f32_to_i32(1.0), i32::MAX);
+        // The scale isn't representable in single precision, so the full scale is `i32::MIN`
+        assert_eq!(f32_to_i32(-1.0), i32::MIN);
+        assert_eq!(f32_to_i32(0.5), 1 << 30);
+        assert_eq!(f32_to_i32(10.0), i32::MAX);
+        assert_eq!(f32_to_i32(f32::NEG_INFINITY), i32::MIN);
+    }
+
+    #[test]
+    fn conversion_round_trips() {
+        for sample in [i16::MIN + 1, -1000, -1, 0, 1, 1000, i16::MAX] {
+            assert!((f32_to_i16(i16_to_f32(sample)) - sample).abs() <= 1);
+        }
+        for sample in [-1.0f32, -0.25, 0.0, 0.25, 1.0] {
+            assert!((i32_to_f32(f32_to_i32(sample)) - sample).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn buffers_copy_the_minimal_length() {
+        let samples = [i16::MIN, 0, 16384];
+        let mut output = [1.0f32; 2];
+        assert_eq!(AudioBuffer::I16(&samples).copy_to_f32(&mut output), 2);
+        assert_eq!(output, [-1.0, 0.0]);
+
+        let mut output = [1.0f32; 4];
+        assert_eq!(AudioBuffer::I16(&samples).copy_to_f32(&mut output), 3);
+        assert_eq!(output, [-1.0, 0.0, 0.5, 1.0]);
+
+        let mut samples = [0i32; 3];
+        let mut buffer = AudioBufferMut::I32(&mut samples);
+        assert_eq!(buffer.copy_from_f32(&[0.5, -2.0]), 2);
+        let mut output = [0.0f32; 3];
+        assert_eq!(buffer.copy_to_f32(&mut output), 3);
+        assert_eq!(output[0], 0.5);
+        assert!(output[1] <= -1.0 + f32::EPSILON);
+        assert_eq!(samples, [1 << 30, i32::MIN, 0]);
+    }
+
+    #[test]
+    fn f32_buffer_copies_samples_as_is() {
+        let mut samples = [0.0f32; 2];
+        let mut buffer = AudioBufferMut::F32(&mut samples);
+        assert_eq!(buffer.copy_from_f32(&[1.5, -0.25, 3.0]), 2);
+        let mut output = [0.0f32; 2];
+        assert_eq!(buffer.copy_to_f32(&mut output), 2);
+        assert_eq!(output, [1.5, -0.25]);
+        buffer.fill_silence();
+        assert_eq!(samples, [0.0, 0.0]);
+    }
+
+    #[test]
+    fn raw_frames_require_stream_format() {
+        let samples = [0i16; 6];
+        let buffer = AudioBuffer::I16(&samples);
+        assert_eq!(buffer._raw_frames(2, AudioFormat::I16).unwrap().1, 3);
+        assert_eq!(
+            buffer._raw_frames(2, AudioFormat::F32).unwrap_err(),
+            Error::InvalidFormat
+        );
+        assert_eq!(
+            buffer._raw_frames(0, AudioFormat::I16).unwrap_err(),
+            Error::IllegalArgument
+        );
+
+        let mut samples = [0.0f32; 4];
+        let mut buffer = AudioBufferMut::F32(&mut samples);
+        assert_eq!(buffer._raw_frames_mut(4, AudioFormat::F32).unwrap().1, 1);
+        assert_eq!(
+            buffer._raw_frames_mut(1, AudioFormat::I32).unwrap_err(),
+            Error::InvalidFormat
+        );
+    }
+
+    #[test]
+    fn raw_frames_are_wrapped_in_stream_format() {
+        let samples = [1i32, 2, 3, 4];
+        let buffer =
+            unsafe { AudioBuffer::_wrap_raw(samples.as_ptr() as _, 2, 2, AudioFormat::I32) };
+        assert!(matches!(buffer, Ok(AudioBuffer::I32(data)) if data == samples));
+
+        for format in [
+            AudioFormat::Unspecified,
+            AudioFormat::Invalid,
+            AudioFormat::Unknown(42),
+        ] {
+            let buffer = unsafe { AudioBuffer::_wrap_raw(samples.as_ptr() as _, 2, 2, format) };
+            assert!(matches!(buffer, Err(Error::InvalidFormat)));
+
+            let mut samples = [0u8; 8];
+            let buffer =
+                unsafe { AudioBufferMut::_wrap_raw_mut(samples.as_mut_ptr() as _, 2, 1, format) };
+            assert!(matches!(buffer, Err(Error::InvalidFormat)));
+        }
+    }
+}
@@ -1,19 +1,27 @@
-use num_traits::FromPrimitive;
 use oboe_sys as ffi;
 use std::{
+    any::Any,
     ffi::c_void,
     fmt::{self, Display},
     marker::PhantomData,
     mem::{transmute, MaybeUninit},
     ops::{Deref, DerefMut},
+    ptr::NonNull,
+    sync::Arc,
 };
 
 use super::{
-    audio_stream_base_fmt, wrap_result, wrap_status, AudioApi, AudioStreamBase, FrameTimestamp,
-    Input, IsFrameType, Output, RawAudioInputStream, RawAudioOutputStream, RawAudioStream,
-    RawAudioStreamBase, Result, Status, StreamState, NANOS_PER_MILLISECOND,
+    audio_stream_base_fmt, transfer, wrap_result, wrap_status, AudioApi, AudioStreamBase,
+    AudioStreamConfig, CallbackPanic, CallbackSlot, Clock, Error, FrameTimestamp, Input,
+    IsFrameType, NegotiationReport, Output, RawAudioInputStream, RawAudioOutputStream,
+    RawAudioStream, RawAudioStreamBase, RawFrameBuffer, RawFrameBufferMut, Result, Status,
+    StreamControl, StreamState, Timeout, Timestamp, TransferError, TransferResult,
+    NANOS_PER_MILLISECOND,
 };
 
+#[cfg(feature = "async")]
+use super::WaitHelper;
+
 /**
  * The default number of nanoseconds to wait for when performing state change operations on the
  * stream, such as `start` and `stop`.
@@ -68,7 +76,7 @@ pub trait AudioStreamSafe: AudioStreamBase {
      * 2 * 4 = 8 bytes per frame.
      */
     fn get_bytes_per_frame(&mut self) -> i32 {
-        self.get_channel_count() as i32 * self.get_bytes_per_sample()
+        i32::from(self.get_channel_count()) * self.get_bytes_per_sample()
     }
 
     /**
@@ -118,6 +126,21 @@ pub trait AudioStreamSafe: AudioStreamBase {
      */
     fn get_timestamp(&mut self, clock_id: i32) -> Result<FrameTimestamp>;
 
+    /**
+     * Get the estimated time on the `clock` that the frame at the position entered or left
+     * the audio processing pipeline, see [`get_timestamp`](Self::get_timestamp).
+     *
+     * Fails with [`Error::Unimplemented`](crate::Error::Unimplemented) for OpenSL ES streams,
+     * which don't provide timestamps. See [`Clock::is_supported_by`] for the supported clocks.
+     */
+    fn get_timestamp_for_clock(&mut self, clock: Clock) -> Result<Timestamp> {
+        if self.get_audio_api() == AudioApi::OpenSLES {
+            return Err(Error::Unimplemented);
+        }
+        self.get_timestamp(clock.clock_id())
+            .map(|timestamp| Timestamp::new(timestamp, clock))
+    }
+
     /**
      * Get the underlying audio API which the stream uses.
      */
@@ -134,6 +157,24 @@ pub trait AudioStreamSafe: AudioStreamBase {
      * Returns the number of frames of data currently in the buffer
      */
     fn get_available_frames(&mut self) -> Result<i32>;
+
+    /**
+     * Get the snapshot of the actual configuration which was negotiated for the stream.
+     */
+    fn actual_config(&self) -> AudioStreamConfig {
+        AudioStreamConfig::from_base(self, self.get_audio_api())
+    }
+
+    /**
+     * Get the native stream, so the helpers like [`LatencyTuner`](crate::LatencyTuner)
+     * can act on it
+     *
+     * Returns `None` for the streams which aren't backed by Oboe.
+     */
+    #[doc(hidden)]
+    fn _native_stream(&mut self) -> Option<NonNull<ffi::oboe_AudioStream>> {
+        None
+    }
 }
 
 /**
@@ -169,6 +210,17 @@ pub trait AudioStream: AudioStreamSafe {
      */
     fn start_with_timeout(&mut self, timeout_nanoseconds: i64) -> Status;
 
+    /**
+     * Start the stream. This will block until the stream has been started, an error occurs
+     * or `timeout` has been reached.
+     */
+    fn start_within(&mut self, timeout: impl Into<Timeout>) -> Status
+    where
+        Self: Sized,
+    {
+        self.start_with_timeout(timeout.into().as_nanos())
+    }
+
     /**
      * Stop the stream. This will block until the stream has been stopped, an error occurs
      * or `timeoutNanoseconds` has been reached.
@@ -183,6 +235,17 @@ pub trait AudioStream: AudioStreamSafe {
      */
     fn stop_with_timeout(&mut self, timeout_nanoseconds: i64) -> Status;
 
+    /**
+     * Stop the stream. This will block until the stream has been stopped, an error occurs
+     * or `timeout` has been reached.
+     */
+    fn stop_within(&mut self, timeout: impl Into<Timeout>) -> Status
+    where
+        Self: Sized,
+    {
+        self.stop_with_timeout(timeout.into().as_nanos())
+    }
+
     /**
      * Start the stream asynchronously. Returns immediately (does not block). Equivalent to calling
      * `start(0)`.
@@ -226,6 +289,21 @@ pub trait AudioStream: AudioStreamSafe {
         timeout_nanoseconds: i64,
     ) -> Result<StreamState>;
 
+    /**
+     * Wait until the stream's current state no longer matches the input state
+     * or `timeout` has been reached, see [`wait_for_state_change`](Self::wait_for_state_change).
+     */
+    fn wait_for_state_change_within(
+        &mut self,
+        input_state: StreamState,
+        timeout: impl Into<Timeout>,
+    ) -> Result<StreamState>
+    where
+        Self: Sized,
+    {
+        self.wait_for_state_change(input_state, timeout.into().as_nanos())
+    }
+
     /**
      * Wait until the stream has a minimum amount of data available in its buffer.
      * This can be used with an EXCLUSIVE MMAP input stream to avoid reading data too close to
@@ -236,6 +314,21 @@ pub trait AudioStream: AudioStreamSafe {
         num_frames: i32,
         timeout_nanoseconds: i64,
     ) -> Result<i32>;
+
+    /**
+     * Wait until the stream has a minimum amount of data available in its buffer
+     * or `timeout` has been reached, see [`wait_for_available_frames`](Self::wait_for_available_frames).
+     */
+    fn wait_for_available_frames_within(
+        &mut self,
+        num_frames: i32,
+        timeout: impl Into<Timeout>,
+    ) -> Result<i32>
+    where
+        Self: Sized,
+    {
+        self.wait_for_available_frames(num_frames, timeout.into().as_nanos())
+    }
 }
 
 /**
@@ -268,9 +361,62 @@ pub trait AudioInputStreamSync: AudioInputStream {
      */
     fn read(
         &mut self,
-        _buffer: &mut [<Self::FrameType as IsFrameType>::Type],
+        _buffer: <Self::FrameType as IsFrameType>::BufferMut<'_>,
         _timeout_nanoseconds: i64,
     ) -> Result<i32>;
+
+    /**
+     * Read data into the supplied buffer from the stream. This method will block until the read
+     * is complete or `timeout` has been reached.
+     *
+     * With [`Timeout::NonBlocking`] this call will not wait.
+     */
+    fn read_within(
+        &mut self,
+        buffer: <Self::FrameType as IsFrameType>::BufferMut<'_>,
+        timeout: impl Into<Timeout>,
+    ) -> Result<i32>
+    where
+        Self: Sized,
+    {
+        self.read(buffer, timeout.into().as_nanos())
+    }
+
+    /**
+     * Read data into the whole supplied buffer from the stream. This method repeats reads
+     * until the buffer is filled, an error occurs or `deadline` has been reached.
+     *
+     * On failure the number of frames which were read is reported.
+     */
+    fn read_exact(
+        &mut self,
+        mut buffer: <Self::FrameType as IsFrameType>::BufferMut<'_>,
+        deadline: impl Into<Timeout>,
+    ) -> TransferResult
+    where
+        Self: Sized,
+    {
+        let channels = self.get_channel_count().count() as usize;
+        let format = self.get_format();
+        let (data, frames) = buffer
+            ._raw_frames_mut(channels, format)
+            .map_err(|error| TransferError { frames: 0, error })?;
+        // The stride of buffer itself, which is validated against the stream above
+        let bytes_per_frame = buffer._bytes_per_frame(channels);
+
+        transfer(frames, deadline.into(), |offset, timeout_nanoseconds| {
+            // SAFETY: The rest of frames is a part of the buffer which is borrowed during the transfer.
+            let rest = unsafe {
+                <<Self::FrameType as IsFrameType>::BufferMut<'_>>::_wrap_raw_mut(
+                    (data as *mut u8).add(offset * bytes_per_frame) as *mut c_void,
+                    frames - offset,
+                    channels,
+                    format,
+                )
+            }?;
+            self.read(rest, timeout_nanoseconds)
+        })
+    }
 }
 
 /**
@@ -302,6 +448,17 @@ pub trait AudioOutputStream: AudioStream + AudioOutputStreamSafe {
      */
     fn pause_with_timeout(&mut self, timeout_nanoseconds: i64) -> Status;
 
+    /**
+     * Pause the stream. This will block until the stream has been paused, an error occurs
+     * or `timeout` has been reached.
+     */
+    fn pause_within(&mut self, timeout: impl Into<Timeout>) -> Status
+    where
+        Self: Sized,
+    {
+        self.pause_with_timeout(timeout.into().as_nanos())
+    }
+
     /**
      * Flush the stream. This will block until the stream has been flushed, an error occurs
      * or `timeoutNanoseconds` has been reached.
@@ -316,6 +473,17 @@ pub trait AudioOutputStream: AudioStream + AudioOutputStreamSafe {
      */
     fn flush_with_timeout(&mut self, timeout_nanoseconds: i64) -> Status;
 
+    /**
+     * Flush the stream. This will block until the stream has been flushed, an error occurs
+     * or `timeout` has been reached.
+     */
+    fn flush_within(&mut self, timeout: impl Into<Timeout>) -> Status
+    where
+        Self: Sized,
+    {
+        self.flush_with_timeout(timeout.into().as_nanos())
+    }
+
     /**
      * Pause the stream asynchronously. Returns immediately (does not block). Equivalent to calling
      * `pause(0)`.
@@ -343,9 +511,62 @@ pub trait AudioOutputStreamSync: AudioOutputStream {
      */
     fn write(
         &mut self,
-        _buffer: &[<Self::FrameType as IsFrameType>::Type],
+        _buffer: <Self::FrameType as IsFrameType>::Buffer<'_>,
         _timeout_nanoseconds: i64,
     ) -> Result<i32>;
+
+    /**
+     * Write data from the supplied buffer into the stream. This method will block until the write
+     * is complete or `timeout` has been reached.
+     *
+     * With [`Timeout::NonBlocking`] this call will not wait.
+     */
+    fn write_within(
+        &mut self,
+        buffer: <Self::FrameType as IsFrameType>::Buffer<'_>,
+        timeout: impl Into<Timeout>,
+    ) -> Result<i32>
+    where
+        Self: Sized,
+    {
+        self.write(buffer, timeout.into().as_nanos())
+    }
+
+    /**
+     * Write data from the whole supplied buffer into the stream. This method repeats writes
+     * until the buffer is drained, an error occurs or `deadline` has been reached.
+     *
+     * On failure the number of frames which were written is reported.
+     */
+    fn write_all(
+        &mut self,
+        buffer: <Self::FrameType as IsFrameType>::Buffer<'_>,
+        deadline: impl Into<Timeout>,
+    ) -> TransferResult
+    where
+        Self: Sized,
+    {
+        let channels = self.get_channel_count().count() as usize;
+        let format = self.get_format();
+        let (data, frames) = buffer
+            ._raw_frames(channels, format)
+            .map_err(|error| TransferError { frames: 0, error })?;
+        // The stride of buffer itself, which is validated against the stream above
+        let bytes_per_frame = buffer._bytes_per_frame(channels);
+
+        transfer(frames, deadline.into(), |offset, timeout_nanoseconds| {
+            // SAFETY: The rest of frames is a part of the buffer which is borrowed during the transfer.
+            let rest = unsafe {
+                <<Self::FrameType as IsFrameType>::Buffer<'_>>::_wrap_raw(
+                    (data as *const u8).add(offset * bytes_per_frame) as *const c_void,
+                    frames - offset,
+                    channels,
+                    format,
+                )
+            }?;
+            self.write(rest, timeout_nanoseconds)
+        })
+    }
 }
 
 impl<T: RawAudioStream + RawAudioStreamBase> AudioStreamSafe for T {
@@ -356,10 +577,7 @@ impl<T: RawAudioStream + RawAudioStreamBase> AudioStreamSafe for T {
     }
 
     fn get_state(&self) -> StreamState {
-        FromPrimitive::from_i32(unsafe {
-            ffi::oboe_AudioStream_getState(self._raw_stream() as *const _ as *mut _)
-        })
-        .unwrap()
+        unsafe { ffi::oboe_AudioStream_getState(self._raw_stream() as *const _ as *mut _) }.into()
     }
 
     fn get_xrun_count(&self) -> Result<i32> {
@@ -394,48 +612,53 @@ impl<T: RawAudioStream + RawAudioStreamBase> AudioStreamSafe for T {
     }
 
     fn get_audio_api(&self) -> AudioApi {
-        FromPrimitive::from_i32(unsafe { ffi::oboe_AudioStream_getAudioApi(self._raw_stream()) })
-            .unwrap()
+        unsafe { ffi::oboe_AudioStream_getAudioApi(self._raw_stream()) }.into()
     }
 
     fn get_available_frames(&mut self) -> Result<i32> {
         wrap_result(unsafe { ffi::oboe_AudioStream_getAvailableFrames(self._raw_stream_mut()) })
     }
+
+    fn _native_stream(&mut self) -> Option<NonNull<ffi::oboe_AudioStream>> {
+        Some(NonNull::from(self._raw_stream_mut()))
+    }
 }
 
 impl<T: RawAudioStream + RawAudioStreamBase> AudioStream for T {
     fn open(&mut self) -> Status {
-        wrap_status(unsafe { ffi::oboe_AudioStream_open(self._raw_stream_mut()) })
+        wrap_status(self._change_state(|raw| unsafe { ffi::oboe_AudioStream_open(raw.cast()) }))
     }
 
     fn close(&mut self) -> Status {
-        wrap_status(unsafe { ffi::oboe_AudioStream_close1(self._raw_stream_mut()) })
+        wrap_status(self._change_state(|raw| unsafe { ffi::oboe_AudioStream_close1(raw.cast()) }))
     }
 
     fn start_with_timeout(&mut self, timeout_nanoseconds: i64) -> Status {
-        wrap_status(unsafe {
-            ffi::oboe_AudioStream_start(
-                self._raw_stream_mut() as *mut _ as *mut c_void,
-                timeout_nanoseconds,
-            )
-        })
+        wrap_status(
+            self._change_state(|raw| unsafe {
+                ffi::oboe_AudioStream_start(raw, timeout_nanoseconds)
+            }),
+        )
     }
 
     fn stop_with_timeout(&mut self, timeout_nanoseconds: i64) -> Status {
-        wrap_status(unsafe {
-            ffi::oboe_AudioStream_stop(
-                self._raw_stream_mut() as *mut _ as *mut c_void,
-                timeout_nanoseconds,
-            )
-        })
+        wrap_status(
+            self._change_state(|raw| unsafe {
+                ffi::oboe_AudioStream_stop(raw, timeout_nanoseconds)
+            }),
+        )
     }
 
     fn request_start(&mut self) -> Status {
-        wrap_status(unsafe { ffi::oboe_AudioStream_requestStart(self._raw_stream_mut()) })
+        wrap_status(
+            self._change_state(|raw| unsafe { ffi::oboe_AudioStream_requestStart(raw.cast()) }),
+        )
     }
 
     fn request_stop(&mut self) -> Status {
-        wrap_status(unsafe { ffi::oboe_AudioStream_requestStop(self._raw_stream_mut()) })
+        wrap_status(
+            self._change_state(|raw| unsafe { ffi::oboe_AudioStream_requestStop(raw.cast()) }),
+        )
     }
 
     fn wait_for_state_change(
@@ -443,16 +666,16 @@ impl<T: RawAudioStream + RawAudioStreamBase> AudioStream for T {
         input_state: StreamState,
         timeout_nanoseconds: i64,
     ) -> Result<StreamState> {
-        let mut next_state = MaybeUninit::<StreamState>::uninit();
+        let mut next_state = MaybeUninit::<i32>::uninit();
         wrap_status(unsafe {
             ffi::oboe_AudioStream_waitForStateChange(
                 self._raw_stream_mut(),
-                input_state as i32,
-                next_state.as_mut_ptr() as *mut i32,
+                input_state.into(),
+                next_state.as_mut_ptr(),
                 timeout_nanoseconds,
             )
         })
-        .map(|_| unsafe { next_state.assume_init() })
+        .map(|_| unsafe { next_state.assume_init() }.into())
     }
 
     fn wait_for_available_frames(
@@ -490,29 +713,31 @@ impl<T: RawAudioOutputStream + RawAudioStream + RawAudioStreamBase> AudioOutputS
 
 impl<T: RawAudioOutputStream + RawAudioStream + RawAudioStreamBase> AudioOutputStream for T {
     fn pause_with_timeout(&mut self, timeout_nanoseconds: i64) -> Status {
-        wrap_status(unsafe {
-            ffi::oboe_AudioStream_pause(
-                self._raw_stream_mut() as *mut _ as *mut c_void,
-                timeout_nanoseconds,
-            )
-        })
+        wrap_status(
+            self._change_state(|raw| unsafe {
+                ffi::oboe_AudioStream_pause(raw, timeout_nanoseconds)
+            }),
+        )
     }
 
     fn flush_with_timeout(&mut self, timeout_nanoseconds: i64) -> Status {
-        wrap_status(unsafe {
-            ffi::oboe_AudioStream_flush(
-                self._raw_stream_mut() as *mut _ as *mut c_void,
-                timeout_nanoseconds,
-            )
-        })
+        wrap_status(
+            self._change_state(|raw| unsafe {
+                ffi::oboe_AudioStream_flush(raw, timeout_nanoseconds)
+            }),
+        )
     }
 
     fn request_pause(&mut self) -> Status {
-        wrap_status(unsafe { ffi::oboe_AudioStream_requestPause(self._raw_stream_mut()) })
+        wrap_status(
+            self._change_state(|raw| unsafe { ffi::oboe_AudioStream_requestPause(raw.cast()) }),
+        )
     }
 
     fn request_flush(&mut self) -> Status {
-        wrap_status(unsafe { ffi::oboe_AudioStream_requestFlush(self._raw_stream_mut()) })
+        wrap_status(
+            self._change_state(|raw| unsafe { ffi::oboe_AudioStream_requestFlush(raw.cast()) }),
+        )
     }
 }
 
@@ -640,6 +865,12 @@ impl<'s> RawAudioOutputStream for AudioStreamRef<'s, Output> {}
  */
 pub struct AudioStreamAsync<D, F> {
     raw: AudioStreamHandle,
+    panic: Arc<CallbackPanic>,
+    requested: AudioStreamConfig,
+    control: StreamControl<D>,
+    callback: CallbackSlot<F>,
+    #[cfg(feature = "async")]
+    helper: WaitHelper,
     _phantom: PhantomData<(D, F)>,
 }
 
@@ -650,20 +881,107 @@ impl<D, F> fmt::Debug for AudioStreamAsync<D, F> {
 }
 
 impl<D, F> AudioStreamAsync<D, F> {
+    #[cfg(feature = "async")]
+    pub(crate) fn control(&self) -> &StreamControl<D> {
+        &self.control
+    }
+
+    #[cfg(feature = "async")]
+    pub(crate) fn helper(&self) -> &WaitHelper {
+        &self.helper
+    }
+
     // SAFETY: `raw` must be valid.
-    pub(crate) fn wrap_handle(raw: AudioStreamHandle) -> Self {
+    pub(crate) fn wrap_handle(
+        raw: AudioStreamHandle,
+        panic: Arc<CallbackPanic>,
+        requested: AudioStreamConfig,
+        callback: CallbackSlot<F>,
+    ) -> Self {
         Self {
+            control: StreamControl::new(raw.clone()),
             raw,
+            panic,
+            requested,
+            callback,
+            #[cfg(feature = "async")]
+            helper: WaitHelper::default(),
             _phantom: PhantomData,
         }
     }
+
+    /**
+     * Get the report of the parameters which were requested and granted when the stream was opened.
+     */
+    pub fn negotiation_report(&self) -> NegotiationReport {
+        NegotiationReport {
+            requested: self.requested,
+            granted: self.actual_config(),
+        }
+    }
+
+    /**
+     * Take the payload of the panic which was caught in the stream callbacks.
+     *
+     * Only the first panic is kept until it is taken.
+     * See [`PanicPolicy`](crate::PanicPolicy) for details.
+     */
+    pub fn take_panic(&self) -> Option<Box<dyn Any + Send>> {
+        self.panic.take()
+    }
+
+    /**
+     * Get the number of panics which were caught in the stream callbacks, but which payloads
+     * were dropped, because the previous payload wasn't taken yet.
+     */
+    pub fn get_dropped_panic_count(&self) -> usize {
+        self.panic.dropped()
+    }
+
+    /**
+     * Get the thread-safe handle to control the stream from other threads.
+     *
+     * See [`StreamControl`] for the thread-safety guarantees.
+     */
+    pub fn control_handle(&self) -> StreamControl<D> {
+        self.control.clone()
+    }
+
+    /**
+     * Close the stream and take back the callback object with its state.
+     *
+     * The callback can be moved into a newly built stream, for example to continue
+     * the playback on another device after the stream was disconnected:
+     *
+     * ```ignore
+     * let callback = stream.close_and_take_callback()?;
+     * let stream = AudioStreamBuilder::default()
+     *     .set_output()
+     *     .set_f32()
+     *     .set_stereo()
+     *     .set_callback(callback)
+     *     .open_stream()?;
+     * ```
+     *
+     * The stream which was closed by Oboe because of an error gives the callback back too.
+     * Fails with [`Error::Unavailable`](crate::Error::Unavailable) when the callback
+     * is owned by [`ReconnectingStream`](crate::ReconnectingStream).
+     */
+    pub fn close_and_take_callback(self) -> Result<F> {
+        // The stream which was closed by Oboe fails to close again, which doesn't matter here
+        let _ = self.control.lock().close();
+        // The callback isn't used anymore, except by the error methods which are waited for
+        self.callback.take().ok_or(Error::Unavailable)
+    }
 }
 
 impl<D, F> Drop for AudioStreamAsync<D, F> {
     fn drop(&mut self) {
         // SAFETY: As long as the conditions on Self::wrap_raw are guaranteed on the creation of
         // self, this is safe.
-        let _ = self.close();
+        // Close through the control lock, so the stream isn't closed while it is used by the handles.
+        // The helper of the futures is joined after that, when the fields are dropped.
+        let _ = self.control.lock().close();
     }
 }
 
@@ -685,6 +1003,12 @@ impl<D, F> RawAudioStream for AudioStreamAsync<D, F> {
     fn _raw_stream_mut(&mut self) -> &mut ffi::oboe_AudioStream {
         &mut self.raw
     }
+
+    fn _change_state<R>(&mut self, change: impl FnOnce(*mut c_void) -> R) -> R {
+        // The state is changed by the owner and the control handles one at a time
+        let _control = self.control.lock();
+        change(&mut *self.raw as *mut _ as *mut c_void)
+    }
 }
 
 impl<F> RawAudioInputStream for AudioStreamAsync<Input, F> {}
@@ -696,6 +1020,11 @@ impl<F> RawAudioOutputStream for AudioStreamAsync<Output, F> {}
  */
 pub struct AudioStreamSync<D, F> {
     raw: AudioStreamHandle,
+    panic: Arc<CallbackPanic>,
+    requested: AudioStreamConfig,
+    control: StreamControl<D>,
+    #[cfg(feature = "async")]
+    helper: WaitHelper,
     _phantom: PhantomData<(D, F)>,
 }
 
@@ -706,20 +1035,69 @@ impl<D, F> fmt::Debug for AudioStreamSync<D, F> {
 }
 
 impl<D, F> AudioStreamSync<D, F> {
+    #[cfg(feature = "async")]
+    pub(crate) fn control(&self) -> &StreamControl<D> {
+        &self.control
+    }
+
+    #[cfg(feature = "async")]
+    pub(crate) fn helper(&self) -> &WaitHelper {
+        &self.helper
+    }
+
     // SAFETY: `raw` must be valid.
-    pub(crate) fn wrap_handle(raw: AudioStreamHandle) -> Self {
+    pub(crate) fn wrap_handle(
+        raw: AudioStreamHandle,
+        panic: Arc<CallbackPanic>,
+        requested: AudioStreamConfig,
+    ) -> Self {
         Self {
+            control: StreamControl::new(raw.clone()),
             raw,
+            panic,
+            requested,
+            #[cfg(feature = "async")]
+            helper: WaitHelper::default(),
             _phantom: PhantomData,
         }
     }
+
+    /**
+     * Get the report of the parameters which were requested and granted when the stream was opened.
+     */
+    pub fn negotiation_report(&self) -> NegotiationReport {
+        NegotiationReport {
+            requested: self.requested,
+            granted: self.actual_config(),
+        }
+    }
+
+    /**
+     * Take the payload of the panic which was caught in the error callback.
+     *
+     * Only the first panic is kept until it is taken.
+     * See [`PanicPolicy`](crate::PanicPolicy) for details.
+     */
+    pub fn take_panic(&self) -> Option<Box<dyn Any + Send>> {
+        self.panic.take()
+    }
+
+    /**
+     * Get the number of panics which were caught in the error callback, but which payloads
+     * were dropped, because the previous payload wasn't taken yet.
+     */
+    pub fn get_dropped_panic_count(&self) -> usize {
+        self.panic.dropped()
+    }
 }
 
 impl<D, F> Drop for AudioStreamSync<D, F> {
     fn drop(&mut self) {
         // SAFETY: As long as the conditions on Self::wrap_raw are guaranteed on the creation of
         // self, this is safe.
-        let _ = self.close();
+        // Close through the control lock, so the stream isn't closed while the futures wait on it.
+        // The helper of the futures is joined after that, when the fields are dropped.
+        let _ = self.control.lock().close();
     }
 }
 
@@ -741,6 +1119,12 @@ impl<D, F> RawAudioStream for AudioStreamSync<D, F> {
     fn _raw_stream_mut(&mut self) -> &mut ffi::oboe_AudioStream {
         &mut self.raw
     }
+
+    fn _change_state<R>(&mut self, change: impl FnOnce(*mut c_void) -> R) -> R {
+        // The state isn't changed while the futures wait on the stream
+        let _control = self.control.lock();
+        change(&mut *self.raw as *mut _ as *mut c_void)
+    }
 }
 
 impl<F> RawAudioInputStream for AudioStreamSync<Input, F> {}
@@ -752,16 +1136,13 @@ impl<F: IsFrameType> AudioInputStreamSync for AudioStreamSync<Input, F> {
 
     fn read(
         &mut self,
-        buffer: &mut [<Self::FrameType as IsFrameType>::Type],
+        mut buffer: <Self::FrameType as IsFrameType>::BufferMut<'_>,
         timeout_nanoseconds: i64,
     ) -> Result<i32> {
+        let channels = self.get_channel_count().count() as usize;
+        let (data, frames) = buffer._raw_frames_mut(channels, self.get_format())?;
         wrap_result(unsafe {
-            ffi::oboe_AudioStream_read(
-                &mut *self.raw,
-                buffer.as_mut_ptr() as *mut c_void,
-                buffer.len() as i32,
-                timeout_nanoseconds,
-            )
+            ffi::oboe_AudioStream_read(&mut *self.raw, data, frames as i32, timeout_nanoseconds)
         })
     }
 }
@@ -771,16 +1152,13 @@ impl<F: IsFrameType> AudioOutputStreamSync for AudioStreamSync<Output, F> {
 
     fn write(
         &mut self,
-        buffer: &[<Self::FrameType as IsFrameType>::Type],
+        buffer: <Self::FrameType as IsFrameType>::Buffer<'_>,
         timeout_nanoseconds: i64,
     ) -> Result<i32> {
+        let channels = self.get_channel_count().count() as usize;
+        let (data, frames) = buffer._raw_frames(channels, self.get_format())?;
         wrap_result(unsafe {
-            ffi::oboe_AudioStream_write(
-                &mut *self.raw,
-                buffer.as_ptr() as *const c_void,
-                buffer.len() as i32,
-                timeout_nanoseconds,
-            )
+            ffi::oboe_AudioStream_write(&mut *self.raw, data, frames as i32, timeout_nanoseconds)
         })
     }
 }
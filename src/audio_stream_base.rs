@@ -1,10 +1,9 @@
 //use oboe_sys as ffi;
-use num_traits::FromPrimitive;
 
 use std::fmt::{self, Display};
 
 use super::{
-    AudioFormat, ChannelCount, ContentType, Direction, InputPreset, PerformanceMode,
+    AudioFormat, ChannelCount, ChannelMask, ContentType, Direction, InputPreset, PerformanceMode,
     RawAudioStreamBase, SampleRateConversionQuality, SessionId, SharingMode, Usage,
 };
 
@@ -17,6 +16,11 @@ pub trait AudioStreamBase {
      */
     fn get_channel_count(&self) -> ChannelCount;
 
+    /**
+     * Get actual channel mask
+     */
+    fn get_channel_mask(&self) -> ChannelMask;
+
     /**
      * Get actual stream direction
      *
@@ -103,11 +107,15 @@ pub trait AudioStreamBase {
 
 impl<T: RawAudioStreamBase> AudioStreamBase for T {
     fn get_channel_count(&self) -> ChannelCount {
-        FromPrimitive::from_i32(self._raw_base().mChannelCount).unwrap()
+        self._raw_base().mChannelCount.into()
+    }
+
+    fn get_channel_mask(&self) -> ChannelMask {
+        self._raw_base().mChannelMask.into()
     }
 
     fn get_direction(&self) -> Direction {
-        FromPrimitive::from_i32(self._raw_base().mDirection).unwrap()
+        self._raw_base().mDirection.into()
     }
 
     fn get_sample_rate(&self) -> i32 {
@@ -119,7 +127,7 @@ impl<T: RawAudioStreamBase> AudioStreamBase for T {
     }
 
     fn get_format(&self) -> AudioFormat {
-        FromPrimitive::from_i32(self._raw_base().mFormat).unwrap()
+        self._raw_base().mFormat.into()
     }
 
     fn get_buffer_size_in_frames(&self) -> i32 {
@@ -131,11 +139,11 @@ impl<T: RawAudioStreamBase> AudioStreamBase for T {
     }
 
     fn get_sharing_mode(&self) -> SharingMode {
-        FromPrimitive::from_i32(self._raw_base().mSharingMode).unwrap()
+        self._raw_base().mSharingMode.into()
     }
 
     fn get_performance_mode(&self) -> PerformanceMode {
-        FromPrimitive::from_i32(self._raw_base().mPerformanceMode).unwrap()
+        self._raw_base().mPerformanceMode.into()
     }
 
     fn get_device_id(&self) -> i32 {
@@ -143,19 +151,19 @@ impl<T: RawAudioStreamBase> AudioStreamBase for T {
     }
 
     fn get_usage(&self) -> Usage {
-        FromPrimitive::from_i32(self._raw_base().mUsage).unwrap()
+        self._raw_base().mUsage.into()
     }
 
     fn get_content_type(&self) -> ContentType {
-        FromPrimitive::from_i32(self._raw_base().mContentType).unwrap()
+        self._raw_base().mContentType.into()
     }
 
     fn get_input_preset(&self) -> InputPreset {
-        FromPrimitive::from_i32(self._raw_base().mInputPreset).unwrap()
+        self._raw_base().mInputPreset.into()
     }
 
     fn get_session_id(&self) -> SessionId {
-        FromPrimitive::from_i32(self._raw_base().mSessionId).unwrap()
+        self._raw_base().mSessionId.into()
     }
 
     fn is_channel_conversion_allowed(&self) -> bool {
@@ -167,7 +175,7 @@ impl<T: RawAudioStreamBase> AudioStreamBase for T {
     }
 
     fn get_sample_rate_conversion_quality(&self) -> SampleRateConversionQuality {
-        FromPrimitive::from_i32(self._raw_base().mSampleRateConversionQuality).unwrap()
+        self._raw_base().mSampleRateConversionQuality.into()
     }
 }
 
@@ -197,6 +205,10 @@ pub(crate) fn audio_stream_base_fmt<T: AudioStreamBase>(
     fmt::Debug::fmt(&base.get_sample_rate_conversion_quality(), f)?;
     "\nChannel count: ".fmt(f)?;
     fmt::Debug::fmt(&base.get_channel_count(), f)?;
+    if base.get_channel_mask() != ChannelMask::Unspecified {
+        "\nChannel mask: ".fmt(f)?;
+        fmt::Debug::fmt(&base.get_channel_mask(), f)?;
+    }
     if base.is_channel_conversion_allowed() {
         " (conversion allowed)".fmt(f)?;
     }
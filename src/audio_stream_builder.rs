@@ -1,20 +1,27 @@
-use num_traits::FromPrimitive;
 use oboe_sys as ffi;
 use std::{
     fmt,
     marker::PhantomData,
     mem::{ManuallyDrop, MaybeUninit},
     ops::{Deref, DerefMut},
+    ptr,
+    sync::Arc,
 };
 
-use crate::{set_input_callback, set_output_callback};
+use crate::{
+    open_with_strategy, set_error_callback, set_input_callback, set_output_callback,
+    set_stabilized_output_callback, CallbackPanic, CallbackSlot,
+};
 
 use super::{
-    audio_stream_base_fmt, wrap_status, AudioApi, AudioInputCallback, AudioOutputCallback,
-    AudioStreamAsync, AudioStreamHandle, AudioStreamSync, ContentType, Input, InputPreset,
-    IsChannelCount, IsDirection, IsFormat, IsFrameType, Mono, Output, PerformanceMode,
-    RawAudioStreamBase, Result, SampleRateConversionQuality, SessionId, SharingMode, Stereo,
-    Unspecified, Usage,
+    audio_stream_base_fmt, wrap_status, AudioApi, AudioErrorCallback, AudioFormat,
+    AudioInputCallback, AudioInputStreamSafe, AudioOutputCallback, AudioOutputStreamSafe,
+    AudioStreamAsync, AudioStreamConfig, AudioStreamHandle, AudioStreamSync, ChannelCount,
+    ChannelMask, Channels, ContentType, DataCallbackResult, Error, Input, InputCallbackFn,
+    InputPreset, IsChannelCount, IsDirection, IsFormat, IsFrameType, Mono, OpenResult,
+    OpenStrategy, Output, OutputCallbackFn, PanicPolicy, PerformanceMode, RawAudioStreamBase,
+    ReconnectingStream, Result, SampleRateConversionQuality, SessionId, SharingMode, Stereo,
+    StrategyResult, StreamParameter, Unspecified, Usage,
 };
 
 #[repr(transparent)]
@@ -22,6 +29,18 @@ pub(crate) struct AudioStreamBuilderHandle(ffi::oboe_AudioStreamBuilder);
 
 impl AudioStreamBuilderHandle {
     pub(crate) fn open_stream(&mut self) -> Result<AudioStreamHandle> {
+        // The frames of callbacks have the layout of channel count, so the mask must match it
+        // SAFETY: The builder is valid.
+        let base = unsafe { &*ffi::oboe_AudioStreamBuilder_getBase(&mut **self) };
+        let channel_mask = ChannelMask::from(base.mChannelMask);
+        let channel_count = ChannelCount::from(base.mChannelCount);
+        if channel_mask != ChannelMask::Unspecified
+            && channel_count != ChannelCount::Unspecified
+            && channel_count != channel_mask.channel_count()
+        {
+            return Err(Error::IllegalArgument);
+        }
+
         let mut stream = AudioStreamHandle::default();
 
         wrap_status(unsafe {
@@ -62,20 +81,64 @@ impl DerefMut for AudioStreamBuilderHandle {
     }
 }
 
+/**
+ * Write the configuration to the native builder
+ */
+pub(crate) fn apply_config(raw: &mut AudioStreamBuilderHandle, config: &AudioStreamConfig) {
+    // SAFETY: The builder is valid.
+    let base = unsafe { &mut *ffi::oboe_AudioStreamBuilder_getBase(&mut **raw) };
+    base.mDirection = config.direction.into();
+    base.mSampleRate = config.sample_rate;
+    base.mFramesPerCallback = config.frames_per_callback;
+    base.mFormat = config.format.into();
+    base.mChannelCount = config.channel_count.into();
+    base.mChannelMask = config.channel_mask.into();
+    base.mBufferCapacityInFrames = config.buffer_capacity_in_frames;
+    base.mSharingMode = config.sharing_mode.into();
+    base.mPerformanceMode = config.performance_mode.into();
+    base.mDeviceId = config.device_id;
+    base.mSessionId = config.session_id.into();
+    base.mUsage = config.usage.into();
+    base.mContentType = config.content_type.into();
+    base.mInputPreset = config.input_preset.into();
+    base.mChannelConversionAllowed = config.channel_conversion_allowed;
+    base.mFormatConversionAllowed = config.format_conversion_allowed;
+    base.mSampleRateConversionQuality = config.sample_rate_conversion_quality.into();
+
+    // SAFETY: The builder is valid.
+    unsafe { ffi::oboe_AudioStreamBuilder_setAudioApi(&mut **raw, config.audio_api.into()) }
+}
+
+/**
+ * The settings which aren't kept by the native builder
+ */
+pub(crate) struct BuilderSettings {
+    pub(crate) panic: Arc<CallbackPanic>,
+    /// The parameters which mismatch makes opening fail
+    pub(crate) strict: Vec<StreamParameter>,
+}
+
+/**
+ * The native builder with the settings which it doesn't keep
+ */
+struct BuilderInner {
+    raw: AudioStreamBuilderHandle,
+    settings: BuilderSettings,
+}
+
 /**
  * Factory for an audio stream.
  */
-#[repr(transparent)]
 pub struct AudioStreamBuilder<D, C, T> {
-    raw: ManuallyDrop<AudioStreamBuilderHandle>,
+    inner: ManuallyDrop<BuilderInner>,
     _phantom: PhantomData<(D, C, T)>,
 }
 
 impl<D, C, T> Drop for AudioStreamBuilder<D, C, T> {
     fn drop(&mut self) {
-        // SAFETY: self.raw is only drop here, or taken in Self::destructs, which don't drop self.
+        // SAFETY: self.inner is only drop here, or taken in Self::destructs, which don't drop self.
         unsafe {
-            ManuallyDrop::drop(&mut self.raw);
+            ManuallyDrop::drop(&mut self.inner);
         }
     }
 }
@@ -88,11 +151,11 @@ impl<D, C, T> fmt::Debug for AudioStreamBuilder<D, C, T> {
 
 impl<D, C, T> RawAudioStreamBase for AudioStreamBuilder<D, C, T> {
     fn _raw_base(&self) -> &ffi::oboe_AudioStreamBase {
-        unsafe { &*ffi::oboe_AudioStreamBuilder_getBase(&**self.raw as *const _ as *mut _) }
+        unsafe { &*ffi::oboe_AudioStreamBuilder_getBase(&*self.inner.raw as *const _ as *mut _) }
     }
 
     fn _raw_base_mut(&mut self) -> &mut ffi::oboe_AudioStreamBase {
-        unsafe { &mut *ffi::oboe_AudioStreamBuilder_getBase(&mut **self.raw) }
+        unsafe { &mut *ffi::oboe_AudioStreamBuilder_getBase(&mut *self.inner.raw) }
     }
 }
 
@@ -102,7 +165,13 @@ impl Default for AudioStreamBuilder<Output, Unspecified, Unspecified> {
      */
     fn default() -> Self {
         Self {
-            raw: Default::default(),
+            inner: ManuallyDrop::new(BuilderInner {
+                raw: Default::default(),
+                settings: BuilderSettings {
+                    panic: Arc::new(CallbackPanic::new(PanicPolicy::default())),
+                    strict: Vec::new(),
+                },
+            }),
             _phantom: PhantomData,
         }
     }
@@ -110,8 +179,9 @@ impl Default for AudioStreamBuilder<Output, Unspecified, Unspecified> {
 
 impl<D, C, T> AudioStreamBuilder<D, C, T> {
     fn convert<D1, C1, T1>(self) -> AudioStreamBuilder<D1, C1, T1> {
+        let (raw, settings) = self.destructs();
         AudioStreamBuilder {
-            raw: ManuallyDrop::new(self.destructs()),
+            inner: ManuallyDrop::new(BuilderInner { raw, settings }),
             _phantom: PhantomData,
         }
     }
@@ -124,7 +194,8 @@ impl<D, C, T> AudioStreamBuilder<D, C, T> {
      */
     pub fn set_channel_count<X: IsChannelCount>(self) -> AudioStreamBuilder<D, X, T> {
         let mut builder = self.convert();
-        builder._raw_base_mut().mChannelCount = X::CHANNEL_COUNT as i32;
+        builder._raw_base_mut().mChannelCount = X::CHANNEL_COUNT.into();
+        builder._raw_base_mut().mChannelMask = ChannelMask::Unspecified.into();
         builder
     }
 
@@ -142,6 +213,40 @@ impl<D, C, T> AudioStreamBuilder<D, C, T> {
         self.set_channel_count::<Stereo>()
     }
 
+    /**
+     * Request multichannel mode with `N` channels for a stream
+     */
+    pub fn set_channels<const N: usize>(self) -> AudioStreamBuilder<D, Channels<N>, T> {
+        self.set_channel_count::<Channels<N>>()
+    }
+
+    /**
+     * Request a specific channel mask.
+     *
+     * Default is `Unspecified`. If the value is unspecified then
+     * the application should query for the actual value after the stream is opened.
+     *
+     * When the channel count type is [`Unspecified`] the channel count is derived from the mask.
+     * Otherwise the mask must describe the same number of channels as the channel count type,
+     * so the frames of callbacks has an expected layout, or opening the stream fails
+     * with [`Error::IllegalArgument`].
+     *
+     * Note that the mask is reset by [`set_channel_count`](Self::set_channel_count).
+     *
+     * Added in API level 32.
+     */
+    pub fn set_channel_mask(mut self, channel_mask: ChannelMask) -> Self
+    where
+        C: IsChannelCount,
+    {
+        if channel_mask != ChannelMask::Unspecified && C::CHANNEL_COUNT == ChannelCount::Unspecified
+        {
+            self._raw_base_mut().mChannelCount = channel_mask.channel_count().into();
+        }
+        self._raw_base_mut().mChannelMask = channel_mask.into();
+        self
+    }
+
     /**
      * Request the direction for a stream
      *
@@ -149,7 +254,7 @@ impl<D, C, T> AudioStreamBuilder<D, C, T> {
      */
     pub fn set_direction<X: IsDirection>(self) -> AudioStreamBuilder<X, C, T> {
         let mut builder = self.convert();
-        builder._raw_base_mut().mDirection = X::DIRECTION as i32;
+        builder._raw_base_mut().mDirection = X::DIRECTION.into();
         builder
     }
 
@@ -208,7 +313,7 @@ impl<D, C, T> AudioStreamBuilder<D, C, T> {
      */
     pub fn set_format<X: IsFormat>(self) -> AudioStreamBuilder<D, C, X> {
         let mut builder = self.convert();
-        builder._raw_base_mut().mFormat = X::FORMAT as i32;
+        builder._raw_base_mut().mFormat = X::FORMAT.into();
         builder
     }
 
@@ -243,8 +348,7 @@ impl<D, C, T> AudioStreamBuilder<D, C, T> {
      * returns true. Otherwise __OpenSL ES__ will be used.
      */
     pub fn get_audio_api(&self) -> AudioApi {
-        FromPrimitive::from_i32(unsafe { ffi::oboe_AudioStreamBuilder_getAudioApi(&**self.raw) })
-            .unwrap()
+        unsafe { ffi::oboe_AudioStreamBuilder_getAudioApi(&*self.inner.raw) }.into()
     }
 
     /**
@@ -258,7 +362,7 @@ impl<D, C, T> AudioStreamBuilder<D, C, T> {
      * If the caller requests AAudio and it is supported then AAudio will be used.
      */
     pub fn set_audio_api(mut self, audio_api: AudioApi) -> Self {
-        unsafe { ffi::oboe_AudioStreamBuilder_setAudioApi(&mut **self.raw, audio_api as i32) }
+        unsafe { ffi::oboe_AudioStreamBuilder_setAudioApi(&mut *self.inner.raw, audio_api.into()) }
         self
     }
 
@@ -287,7 +391,7 @@ impl<D, C, T> AudioStreamBuilder<D, C, T> {
      * So the application should query for the actual mode after the stream is opened.
      */
     pub fn set_sharing_mode(mut self, sharing_mode: SharingMode) -> Self {
-        self._raw_base_mut().mSharingMode = sharing_mode as i32;
+        self._raw_base_mut().mSharingMode = sharing_mode.into();
         self
     }
 
@@ -311,7 +415,7 @@ impl<D, C, T> AudioStreamBuilder<D, C, T> {
      * protection from glitches.
      */
     pub fn set_performance_mode(mut self, performance_mode: PerformanceMode) -> Self {
-        self._raw_base_mut().mPerformanceMode = performance_mode as i32;
+        self._raw_base_mut().mPerformanceMode = performance_mode.into();
         self
     }
 
@@ -326,7 +430,7 @@ impl<D, C, T> AudioStreamBuilder<D, C, T> {
      * Added in API level 28.
      */
     pub fn set_usage(mut self, usage: Usage) -> Self {
-        self._raw_base_mut().mUsage = usage as i32;
+        self._raw_base_mut().mUsage = usage.into();
         self
     }
 
@@ -341,7 +445,7 @@ impl<D, C, T> AudioStreamBuilder<D, C, T> {
      * Added in API level 28.
      */
     pub fn set_content_type(mut self, content_type: ContentType) -> Self {
-        self._raw_base_mut().mContentType = content_type as i32;
+        self._raw_base_mut().mContentType = content_type.into();
         self
     }
 
@@ -359,7 +463,7 @@ impl<D, C, T> AudioStreamBuilder<D, C, T> {
      * Added in API level 28.
      */
     pub fn set_input_preset(mut self, input_preset: InputPreset) -> Self {
-        self._raw_base_mut().mInputPreset = input_preset as i32;
+        self._raw_base_mut().mInputPreset = input_preset.into();
         self
     }
 
@@ -374,8 +478,9 @@ impl<D, C, T> AudioStreamBuilder<D, C, T> {
      * If set to `SessionId::Allocate` then a session ID will be allocated
      * when the stream is opened.
      *
-     * The allocated session ID can be obtained by calling AudioStream::getSessionId()
-     * and then used with this function when opening another stream.
+     * The allocated session ID can be obtained as `SessionId::Id` by calling
+     * `get_session_id()` on the opened stream and then used with this function
+     * when opening another stream.
      * This allows effects to be shared between streams.
      *
      * Session IDs from Oboe can be used the Android Java APIs and vice versa.
@@ -387,7 +492,7 @@ impl<D, C, T> AudioStreamBuilder<D, C, T> {
      * Added in API level 28.
      */
     pub fn set_session_id(mut self, session_id: SessionId) -> Self {
-        self._raw_base_mut().mSessionId = session_id as i32;
+        self._raw_base_mut().mSessionId = session_id.into();
         self
     }
 
@@ -453,7 +558,64 @@ impl<D, C, T> AudioStreamBuilder<D, C, T> {
         mut self,
         quality: SampleRateConversionQuality,
     ) -> Self {
-        self._raw_base_mut().mSampleRateConversionQuality = quality as i32;
+        self._raw_base_mut().mSampleRateConversionQuality = quality.into();
+        self
+    }
+
+    /**
+     * Set the way to handle panics in the stream callbacks.
+     *
+     * Default is `PanicPolicy::Stop`
+     */
+    pub fn set_panic_policy(self, panic_policy: PanicPolicy) -> Self {
+        self.inner.settings.panic.set_policy(panic_policy);
+        self
+    }
+
+    /**
+     * Get the way to handle panics in the stream callbacks.
+     */
+    pub fn get_panic_policy(&self) -> PanicPolicy {
+        self.inner.settings.panic.policy()
+    }
+
+    /**
+     * Specifies an object to handle error related callbacks from the underlying API.
+     *
+     * It can be used with both synchronous (blocking) and asynchronous (callback-driven) streams.
+     * The error methods of this object are called instead of the error methods of the data
+     * callback object, regardless of the order in which both were set.
+     *
+     * __Important: See AudioStreamCallback for restrictions on what may be called
+     * from the callback methods.__
+     */
+    pub fn set_error_callback<E>(mut self, error_callback: E) -> Self
+    where
+        E: AudioErrorCallback + Send,
+    {
+        let inner = &mut *self.inner;
+        set_error_callback(&mut inner.raw, error_callback, &inner.settings.panic);
+        self
+    }
+
+    /**
+     * Get the snapshot of the current configuration.
+     */
+    pub fn config(&self) -> AudioStreamConfig {
+        AudioStreamConfig::from_base(self, self.get_audio_api())
+    }
+
+    /**
+     * Refuse to open the stream when any of the parameters is mismatched.
+     *
+     * The stream is closed and opening fails with [`OpenError::Negotiation`],
+     * which carries the report, when Oboe grants another value than requested
+     * for any of these parameters. See [`NegotiationReport`] for details.
+     *
+     * Default is empty, so any mismatch is accepted.
+     */
+    pub fn set_strict_negotiation(mut self, parameters: &[StreamParameter]) -> Self {
+        self.inner.settings.strict = parameters.to_vec();
         self
     }
 
@@ -466,33 +628,128 @@ impl<D, C, T> AudioStreamBuilder<D, C, T> {
             || (audio_api == AudioApi::Unspecified && Self::is_aaudio_recommended())
     }
 
-    /// Descontructs self into its handle, without calling drop.
-    fn destructs(mut self) -> AudioStreamBuilderHandle {
-        // Safety: the std::mem::forget prevents `raw` from being dropped by Self::drop.
-        let raw = unsafe { ManuallyDrop::take(&mut self.raw) };
+    /// Descontructs self into its handle and settings without calling drop.
+    fn destructs(mut self) -> (AudioStreamBuilderHandle, BuilderSettings) {
+        // Safety: the std::mem::forget prevents `inner` from being dropped by Self::drop.
+        let inner = unsafe { ManuallyDrop::take(&mut self.inner) };
 
         std::mem::forget(self);
 
-        raw
+        (inner.raw, inner.settings)
     }
 }
 
 impl<D: IsDirection, C: IsChannelCount, T: IsFormat> AudioStreamBuilder<D, C, T> {
+    /**
+     * Create a builder with the configuration from snapshot.
+     *
+     * The direction of configuration should match the builder type.
+     * The format and channel count should match the builder type too,
+     * unless those are unspecified by type, so it is resolved at runtime.
+     * The specified channel mask should describe the same number of channels
+     * as the channel count of configuration and the builder type.
+     *
+     * The buffer size in frames isn't applied, because it can be set for opened streams only.
+     */
+    pub fn from_config(config: &AudioStreamConfig) -> Result<Self> {
+        if config.direction != D::DIRECTION {
+            return Err(Error::IllegalArgument);
+        }
+        if T::FORMAT != AudioFormat::Unspecified && config.format != T::FORMAT {
+            return Err(Error::InvalidFormat);
+        }
+        if C::CHANNEL_COUNT != ChannelCount::Unspecified && config.channel_count != C::CHANNEL_COUNT
+        {
+            return Err(Error::IllegalArgument);
+        }
+        if config.channel_mask != ChannelMask::Unspecified {
+            let mask_count = config.channel_mask.channel_count();
+            if (config.channel_count != ChannelCount::Unspecified
+                && config.channel_count != mask_count)
+                || (C::CHANNEL_COUNT != ChannelCount::Unspecified && C::CHANNEL_COUNT != mask_count)
+            {
+                return Err(Error::IllegalArgument);
+            }
+        }
+
+        let mut builder: Self = AudioStreamBuilder::default().convert();
+        apply_config(&mut builder.inner.raw, config);
+        Ok(builder)
+    }
+
     /**
      * Create and open a synchronous (blocking) stream based on the current settings.
      */
-    pub fn open_stream(self) -> Result<AudioStreamSync<D, (T, C)>> {
-        let mut raw = self.destructs();
+    pub fn open_stream(self) -> OpenResult<AudioStreamSync<D, (T, C)>> {
+        let requested = self.config();
+        let (mut raw, settings) = self.destructs();
 
-        let stream = raw.open_stream().map(AudioStreamSync::wrap_handle);
+        let stream = raw
+            .open_stream()
+            .map(|raw| AudioStreamSync::wrap_handle(raw, settings.panic, requested));
 
         drop(raw);
 
-        stream
+        let stream = stream?;
+        stream.negotiation_report().check(&settings.strict)?;
+        Ok(stream)
+    }
+
+    /**
+     * Create and open a synchronous (blocking) stream trying the attempts of strategy in order.
+     *
+     * The attempt fails when the stream can't be opened or the negotiation is refused by
+     * [`set_strict_negotiation`](Self::set_strict_negotiation).
+     */
+    pub fn open_stream_with_strategy(
+        self,
+        strategy: &OpenStrategy,
+    ) -> StrategyResult<AudioStreamSync<D, (T, C)>> {
+        let config = self.config();
+        let (mut raw, settings) = self.destructs();
+
+        open_with_strategy(
+            &mut raw,
+            config,
+            C::CHANNEL_COUNT,
+            strategy,
+            |raw, requested| {
+                let stream = AudioStreamSync::wrap_handle(
+                    raw.open_stream()?,
+                    settings.panic.clone(),
+                    requested,
+                );
+                stream.negotiation_report().check(&settings.strict)?;
+                Ok(stream)
+            },
+        )
     }
 }
 
 impl<C: IsChannelCount, T: IsFormat> AudioStreamBuilder<Input, C, T> {
+    /**
+     * Create and open an asynchronous (callback-driven) input stream which is reopened
+     * automatically when the device is disconnected.
+     *
+     * The error methods of the callback are called before each reconnection.
+     * The error callback which was set by [`set_error_callback`](Self::set_error_callback)
+     * is replaced, because the reconnection relies on the error methods of the callback.
+     *
+     * See [`ReconnectingStream`] for details.
+     */
+    pub fn open_reconnecting_stream<F>(
+        self,
+        stream_callback: F,
+    ) -> OpenResult<ReconnectingStream<Input, F>>
+    where
+        F: AudioInputCallback<FrameType = (T, C)> + Send,
+        (T, C): IsFrameType,
+    {
+        let requested = self.config();
+        let (raw, settings) = self.destructs();
+        ReconnectingStream::open_input(raw, settings, requested, stream_callback)
+    }
+
     /**
      * Specifies an object to handle data or error related callbacks from the underlying API.
      *
@@ -516,16 +773,78 @@ impl<C: IsChannelCount, T: IsFormat> AudioStreamBuilder<Input, C, T> {
         F: AudioInputCallback<FrameType = (T, C)>,
         (T, C): IsFrameType,
     {
-        let mut raw = self.destructs();
-        set_input_callback(&mut raw, stream_callback);
+        let (mut raw, settings) = self.destructs();
+        let callback = CallbackSlot::new(stream_callback);
+        set_input_callback(&mut raw, callback.clone(), &settings.panic);
         AudioStreamBuilderAsync {
             raw: ManuallyDrop::new(raw),
+            settings,
+            callback,
             _phantom: PhantomData,
         }
     }
+
+    /**
+     * Specifies a closure to handle the data callbacks from the underlying API.
+     *
+     * The frame type of the closure is defined by the builder.
+     * The closures to handle errors can be added using
+     * [`on_error_before_close_fn`](AudioStreamBuilderAsync::on_error_before_close_fn) and
+     * [`on_error_after_close_fn`](AudioStreamBuilderAsync::on_error_after_close_fn).
+     *
+     * ```ignore
+     * let stream = AudioStreamBuilder::default()
+     *     .set_input()
+     *     .set_f32()
+     *     .set_mono()
+     *     .set_callback_fn(|_stream, frames| {
+     *         recorder.extend_from_slice(frames);
+     *         DataCallbackResult::Continue
+     *     })
+     *     .open_stream()?;
+     * ```
+     *
+     * See [`set_callback`](Self::set_callback) for details.
+     */
+    pub fn set_callback_fn<A>(
+        self,
+        audio_ready: A,
+    ) -> AudioStreamBuilderAsync<Input, InputCallbackFn<(T, C), A>>
+    where
+        A: for<'a> FnMut(
+            &mut dyn AudioInputStreamSafe,
+            <(T, C) as IsFrameType>::Buffer<'a>,
+        ) -> DataCallbackResult,
+        (T, C): IsFrameType,
+    {
+        self.set_callback(InputCallbackFn::new(audio_ready))
+    }
 }
 
 impl<C: IsChannelCount, T: IsFormat> AudioStreamBuilder<Output, C, T> {
+    /**
+     * Create and open an asynchronous (callback-driven) output stream which is reopened
+     * automatically when the device is disconnected.
+     *
+     * The error methods of the callback are called before each reconnection.
+     * The error callback which was set by [`set_error_callback`](Self::set_error_callback)
+     * is replaced, because the reconnection relies on the error methods of the callback.
+     *
+     * See [`ReconnectingStream`] for details.
+     */
+    pub fn open_reconnecting_stream<F>(
+        self,
+        stream_callback: F,
+    ) -> OpenResult<ReconnectingStream<Output, F>>
+    where
+        F: AudioOutputCallback<FrameType = (T, C)> + Send,
+        (T, C): IsFrameType,
+    {
+        let requested = self.config();
+        let (raw, settings) = self.destructs();
+        ReconnectingStream::open_output(raw, settings, requested, stream_callback)
+    }
+
     /**
      * Specifies an object to handle data or error related callbacks from the underlying API.
      *
@@ -549,10 +868,89 @@ impl<C: IsChannelCount, T: IsFormat> AudioStreamBuilder<Output, C, T> {
         F: AudioOutputCallback<FrameType = (T, C)>,
         (T, C): IsFrameType,
     {
-        let mut raw = self.destructs();
-        set_output_callback(&mut raw, stream_callback);
+        let (mut raw, settings) = self.destructs();
+        let callback = CallbackSlot::new(stream_callback);
+        set_output_callback(&mut raw, callback.clone(), &settings.panic);
+        AudioStreamBuilderAsync {
+            raw: ManuallyDrop::new(raw),
+            settings,
+            callback,
+            _phantom: PhantomData,
+        }
+    }
+
+    /**
+     * Specifies a closure to handle the data callbacks from the underlying API.
+     *
+     * The frame type of the closure is defined by the builder.
+     * The closures to handle errors can be added using
+     * [`on_error_before_close_fn`](AudioStreamBuilderAsync::on_error_before_close_fn) and
+     * [`on_error_after_close_fn`](AudioStreamBuilderAsync::on_error_after_close_fn).
+     *
+     * ```ignore
+     * let mut phase = 0.0f32;
+     * let stream = AudioStreamBuilder::default()
+     *     .set_output()
+     *     .set_f32()
+     *     .set_stereo()
+     *     .set_callback_fn(move |stream, frames| {
+     *         let delta = 440.0 * 2.0 * PI / stream.get_sample_rate() as f32;
+     *         for frame in frames {
+     *             let value = phase.sin();
+     *             *frame = (value, value);
+     *             phase = (phase + delta) % (2.0 * PI);
+     *         }
+     *         DataCallbackResult::Continue
+     *     })
+     *     .open_stream()?;
+     * ```
+     *
+     * See [`set_callback`](Self::set_callback) for details.
+     */
+    pub fn set_callback_fn<A>(
+        self,
+        audio_ready: A,
+    ) -> AudioStreamBuilderAsync<Output, OutputCallbackFn<(T, C), A>>
+    where
+        A: for<'a> FnMut(
+            &mut dyn AudioOutputStreamSafe,
+            <(T, C) as IsFrameType>::BufferMut<'a>,
+        ) -> DataCallbackResult,
+        (T, C): IsFrameType,
+    {
+        self.set_callback(OutputCallbackFn::new(audio_ready))
+    }
+
+    /**
+     * Specifies an object to handle data or error related callbacks like
+     * [`set_callback`](Self::set_callback) does, but keeps the CPU load of the callback stable.
+     *
+     * On many devices (especially with big.LITTLE cores) the CPU frequency is scaled down
+     * when the audio callback finishes quickly. A sudden raise of the rendering load
+     * can then cause glitches until the frequency is scaled up again.
+     * The stabilized callback generates an artificial load, so the callback always takes
+     * roughly the same part of the buffer duration.
+     *
+     * The callback object keeps the same [`AudioOutputCallback`] contract,
+     * so any existing callback can be stabilized without changes.
+     *
+     * Note that the artificial load increases the power consumption.
+     */
+    pub fn set_stabilized_callback<F>(
+        self,
+        stream_callback: F,
+    ) -> AudioStreamBuilderAsync<Output, F>
+    where
+        F: AudioOutputCallback<FrameType = (T, C)>,
+        (T, C): IsFrameType,
+    {
+        let (mut raw, settings) = self.destructs();
+        let callback = CallbackSlot::new(stream_callback);
+        set_stabilized_output_callback(&mut raw, callback.clone(), &settings.panic);
         AudioStreamBuilderAsync {
             raw: ManuallyDrop::new(raw),
+            settings,
+            callback,
             _phantom: PhantomData,
         }
     }
@@ -563,6 +961,8 @@ impl<C: IsChannelCount, T: IsFormat> AudioStreamBuilder<Output, C, T> {
  */
 pub struct AudioStreamBuilderAsync<D, F> {
     raw: ManuallyDrop<AudioStreamBuilderHandle>,
+    settings: BuilderSettings,
+    callback: CallbackSlot<F>,
     _phantom: PhantomData<(D, F)>,
 }
 
@@ -592,14 +992,102 @@ impl<D, F> RawAudioStreamBase for AudioStreamBuilderAsync<D, F> {
 }
 
 impl<D, F> AudioStreamBuilderAsync<D, F> {
-    /// Descontructs self into its handle without calling drop.
-    fn destructs(mut self) -> AudioStreamBuilderHandle {
+    /**
+     * Get the snapshot of the current configuration.
+     */
+    pub fn config(&self) -> AudioStreamConfig {
+        let audio_api = unsafe { ffi::oboe_AudioStreamBuilder_getAudioApi(&**self.raw) };
+        AudioStreamConfig::from_base(self, audio_api.into())
+    }
+
+    /**
+     * Specifies an object to handle error related callbacks from the underlying API.
+     *
+     * The error methods of this object are called instead of the error methods of the data
+     * callback object.
+     *
+     * __Important: See AudioStreamCallback for restrictions on what may be called
+     * from the callback methods.__
+     */
+    pub fn set_error_callback<E>(mut self, error_callback: E) -> Self
+    where
+        E: AudioErrorCallback + Send,
+    {
+        set_error_callback(&mut self.raw, error_callback, &self.settings.panic);
+        self
+    }
+
+    /// Descontructs self into its handle, settings and callback without calling drop.
+    fn destructs(mut self) -> (AudioStreamBuilderHandle, BuilderSettings, CallbackSlot<F>) {
         // Safety: the std::mem::forget prevents `raw` from being dropped by Self::drop.
         let raw = unsafe { ManuallyDrop::take(&mut self.raw) };
+        // Safety: the std::mem::forget prevents `settings` and `callback` from being dropped twice.
+        let settings = unsafe { ptr::read(&self.settings) };
+        let callback = unsafe { ptr::read(&self.callback) };
 
         std::mem::forget(self);
 
-        raw
+        (raw, settings, callback)
+    }
+}
+
+impl<F, A> AudioStreamBuilderAsync<Input, InputCallbackFn<F, A>> {
+    /**
+     * Specifies a closure to call when an error occurs, before the stream is closed.
+     *
+     * See [`AudioInputCallback::on_error_before_close`].
+     */
+    pub fn on_error_before_close_fn(
+        self,
+        before_close: impl FnMut(&mut dyn AudioInputStreamSafe, Error) + Send + 'static,
+    ) -> Self {
+        self.callback
+            .with(|callback| callback.set_before_close(Box::new(before_close)));
+        self
+    }
+
+    /**
+     * Specifies a closure to call when an error occurs, after the stream is closed.
+     *
+     * See [`AudioInputCallback::on_error_after_close`].
+     */
+    pub fn on_error_after_close_fn(
+        self,
+        after_close: impl FnMut(&mut dyn AudioInputStreamSafe, Error) + Send + 'static,
+    ) -> Self {
+        self.callback
+            .with(|callback| callback.set_after_close(Box::new(after_close)));
+        self
+    }
+}
+
+impl<F, A> AudioStreamBuilderAsync<Output, OutputCallbackFn<F, A>> {
+    /**
+     * Specifies a closure to call when an error occurs, before the stream is closed.
+     *
+     * See [`AudioOutputCallback::on_error_before_close`].
+     */
+    pub fn on_error_before_close_fn(
+        self,
+        before_close: impl FnMut(&mut dyn AudioOutputStreamSafe, Error) + Send + 'static,
+    ) -> Self {
+        self.callback
+            .with(|callback| callback.set_before_close(Box::new(before_close)));
+        self
+    }
+
+    /**
+     * Specifies a closure to call when an error occurs, after the stream is closed.
+     *
+     * See [`AudioOutputCallback::on_error_after_close`].
+     */
+    pub fn on_error_after_close_fn(
+        self,
+        after_close: impl FnMut(&mut dyn AudioOutputStreamSafe, Error) + Send + 'static,
+    ) -> Self {
+        self.callback
+            .with(|callback| callback.set_after_close(Box::new(after_close)));
+        self
     }
 }
 
@@ -607,14 +1095,52 @@ impl<F: AudioInputCallback + Send> AudioStreamBuilderAsync<Input, F> {
     /**
      * Create and open an asynchronous (callback-driven) input stream based on the current settings.
      */
-    pub fn open_stream(self) -> Result<AudioStreamAsync<Input, F>> {
-        let mut raw = self.destructs();
+    pub fn open_stream(self) -> OpenResult<AudioStreamAsync<Input, F>> {
+        let requested = self.config();
+        let (mut raw, settings, callback) = self.destructs();
 
-        let stream = raw.open_stream().map(AudioStreamAsync::wrap_handle);
+        let stream = raw
+            .open_stream()
+            .map(|raw| AudioStreamAsync::wrap_handle(raw, settings.panic, requested, callback));
 
         drop(raw);
 
-        stream
+        let stream = stream?;
+        stream.negotiation_report().check(&settings.strict)?;
+        Ok(stream)
+    }
+
+    /**
+     * Create and open an asynchronous (callback-driven) input stream trying the attempts
+     * of strategy in order.
+     *
+     * The attempt fails when the stream can't be opened or the negotiation is refused by
+     * [`set_strict_negotiation`](AudioStreamBuilder::set_strict_negotiation).
+     */
+    pub fn open_stream_with_strategy(
+        self,
+        strategy: &OpenStrategy,
+    ) -> StrategyResult<AudioStreamAsync<Input, F>> {
+        let config = self.config();
+        let (mut raw, settings, callback) = self.destructs();
+
+        let channel_count = <F::FrameType as IsFrameType>::ChannelCount::CHANNEL_COUNT;
+        open_with_strategy(
+            &mut raw,
+            config,
+            channel_count,
+            strategy,
+            |raw, requested| {
+                let stream = AudioStreamAsync::wrap_handle(
+                    raw.open_stream()?,
+                    settings.panic.clone(),
+                    requested,
+                    callback.clone(),
+                );
+                stream.negotiation_report().check(&settings.strict)?;
+                Ok(stream)
+            },
+        )
     }
 }
 
@@ -622,13 +1148,176 @@ impl<F: AudioOutputCallback + Send> AudioStreamBuilderAsync<Output, F> {
     /**
      * Create and open an asynchronous (callback-driven) output stream based on the current settings.
      */
-    pub fn open_stream(self) -> Result<AudioStreamAsync<Output, F>> {
-        let mut raw = self.destructs();
+    pub fn open_stream(self) -> OpenResult<AudioStreamAsync<Output, F>> {
+        let requested = self.config();
+        let (mut raw, settings, callback) = self.destructs();
 
-        let stream = raw.open_stream().map(AudioStreamAsync::wrap_handle);
+        let stream = raw
+            .open_stream()
+            .map(|raw| AudioStreamAsync::wrap_handle(raw, settings.panic, requested, callback));
 
         drop(raw);
 
-        stream
+        let stream = stream?;
+        stream.negotiation_report().check(&settings.strict)?;
+        Ok(stream)
+    }
+
+    /**
+     * Create and open an asynchronous (callback-driven) output stream trying the attempts
+     * of strategy in order.
+     *
+     * The attempt fails when the stream can't be opened or the negotiation is refused by
+     * [`set_strict_negotiation`](AudioStreamBuilder::set_strict_negotiation).
+     */
+    pub fn open_stream_with_strategy(
+        self,
+        strategy: &OpenStrategy,
+    ) -> StrategyResult<AudioStreamAsync<Output, F>> {
+        let config = self.config();
+        let (mut raw, settings, callback) = self.destructs();
+
+        let channel_count = <F::FrameType as IsFrameType>::ChannelCount::CHANNEL_COUNT;
+        open_with_strategy(
+            &mut raw,
+            config,
+            channel_count,
+            strategy,
+            |raw, requested| {
+                let stream = AudioStreamAsync::wrap_handle(
+                    raw.open_stream()?,
+                    settings.panic.clone(),
+                    requested,
+                    callback.clone(),
+                );
+                stream.negotiation_report().check(&settings.strict)?;
+                Ok(stream)
+            },
+        )
+    }
+}
+
+#[cfg(all(test, not(target_os = "android")))]
+mod tests {
+    use super::*;
+    use crate::{fake_stream::on_open, AudioStreamBase, OpenError};
+
+    #[test]
+    fn channel_mask_sets_channel_count_of_unspecified_builder() {
+        let builder = AudioStreamBuilder::default().set_channel_mask(ChannelMask::CM5Point1);
+        assert_eq!(builder.get_channel_count(), ChannelCount::Other(6));
+        assert_eq!(builder.get_channel_mask(), ChannelMask::CM5Point1);
+
+        let builder = builder.set_channel_mask(ChannelMask::Stereo);
+        assert_eq!(builder.get_channel_count(), ChannelCount::Stereo);
+
+        let builder = builder.set_channel_mask(ChannelMask::Unspecified);
+        assert_eq!(builder.get_channel_count(), ChannelCount::Stereo);
+        assert_eq!(builder.get_channel_mask(), ChannelMask::Unspecified);
+    }
+
+    #[test]
+    fn channel_mask_matching_channel_count_is_accepted() {
+        let builder = AudioStreamBuilder::default()
+            .set_stereo()
+            .set_channel_mask(ChannelMask::Stereo);
+        assert_eq!(builder.get_channel_mask(), ChannelMask::Stereo);
+
+        let builder = AudioStreamBuilder::default()
+            .set_channels::<6>()
+            .set_channel_mask(ChannelMask::CM5Point1)
+            .set_channel_mask(ChannelMask::Unspecified);
+        assert_eq!(builder.get_channel_count(), ChannelCount::Other(6));
+        assert_eq!(builder.get_channel_mask(), ChannelMask::Unspecified);
+    }
+
+    #[test]
+    fn channel_mask_mismatching_channel_count_fails_to_open() {
+        let builder = AudioStreamBuilder::default()
+            .set_f32()
+            .set_stereo()
+            .set_channel_mask(ChannelMask::CM7Point1);
+        assert_eq!(builder.get_channel_count(), ChannelCount::Stereo);
+        assert_eq!(builder.get_channel_mask(), ChannelMask::CM7Point1);
+        assert_eq!(
+            builder.open_stream().unwrap_err(),
+            OpenError::Open(Error::IllegalArgument)
+        );
+
+        let error = AudioStreamBuilder::default()
+            .set_f32()
+            .set_mono()
+            .set_channel_mask(ChannelMask::Stereo)
+            .set_callback_fn(|_stream, _frames| DataCallbackResult::Continue)
+            .open_stream()
+            .unwrap_err();
+        assert_eq!(error, OpenError::Open(Error::IllegalArgument));
+    }
+
+    #[test]
+    fn channel_count_resets_channel_mask() {
+        let builder = AudioStreamBuilder::default()
+            .set_channel_mask(ChannelMask::CM5Point1)
+            .set_mono();
+        assert_eq!(builder.get_channel_count(), ChannelCount::Mono);
+        assert_eq!(builder.get_channel_mask(), ChannelMask::Unspecified);
+    }
+
+    #[test]
+    fn from_config_accepts_consistent_channel_mask() {
+        let config = AudioStreamBuilder::default()
+            .set_channel_mask(ChannelMask::CM5Point1)
+            .config();
+
+        let builder =
+            AudioStreamBuilder::<Output, Channels<6>, Unspecified>::from_config(&config).unwrap();
+        assert_eq!(builder.get_channel_count(), ChannelCount::Other(6));
+        assert_eq!(builder.get_channel_mask(), ChannelMask::CM5Point1);
+
+        let builder =
+            AudioStreamBuilder::<Output, Unspecified, Unspecified>::from_config(&config).unwrap();
+        assert_eq!(builder.get_channel_mask(), ChannelMask::CM5Point1);
+
+        // The mask can be unspecified for any channel count
+        let config = AudioStreamBuilder::default().set_stereo().config();
+        let builder =
+            AudioStreamBuilder::<Output, Stereo, Unspecified>::from_config(&config).unwrap();
+        assert_eq!(builder.get_channel_mask(), ChannelMask::Unspecified);
+    }
+
+    #[test]
+    fn from_config_rejects_inconsistent_channel_mask() {
+        let mut config = AudioStreamBuilder::default().set_stereo().config();
+        config.channel_mask = ChannelMask::CM5Point1;
+        assert_eq!(
+            AudioStreamBuilder::<Output, Unspecified, Unspecified>::from_config(&config).err(),
+            Some(Error::IllegalArgument)
+        );
+        assert_eq!(
+            AudioStreamBuilder::<Output, Stereo, Unspecified>::from_config(&config).err(),
+            Some(Error::IllegalArgument)
+        );
+    }
+
+    #[test]
+    fn strict_negotiation_refusal_is_reported() {
+        on_open(|fake| {
+            fake.set_base(|base| base.mSampleRate = 48000);
+            Ok(())
+        });
+
+        let error = AudioStreamBuilder::default()
+            .set_f32()
+            .set_mono()
+            .set_sample_rate(44100)
+            .set_strict_negotiation(&[StreamParameter::SampleRate])
+            .open_stream()
+            .unwrap_err();
+
+        let negotiation = error.negotiation().unwrap();
+        assert_eq!(negotiation.mismatches, [StreamParameter::SampleRate]);
+        assert_eq!(negotiation.report.requested.sample_rate, 44100);
+        assert_eq!(negotiation.report.granted.sample_rate, 48000);
+        assert_eq!(error.error(), Error::Unavailable);
     }
 }
@@ -1,15 +1,25 @@
 use std::{
+    any::Any,
+    cell::UnsafeCell,
     ffi::c_void,
-    slice::{from_raw_parts, from_raw_parts_mut},
+    ops::{Deref, DerefMut},
+    panic::{catch_unwind, AssertUnwindSafe},
+    process::abort,
+    ptr::write_bytes,
+    result,
+    sync::{
+        atomic::{AtomicU8, AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    thread,
 };
 
 use oboe_sys as ffi;
 
-use num_traits::FromPrimitive;
-
 use super::{
-    AudioInputStreamSafe, AudioOutputStreamSafe, AudioStreamBuilderHandle, AudioStreamRef,
-    DataCallbackResult, Error, IsFrameType,
+    AudioInputStreamSafe, AudioOutputStreamSafe, AudioStreamBase, AudioStreamBuilderHandle,
+    AudioStreamRef, AudioStreamSafe, CallbackInfo, CallbackInfoTracker, DataCallbackResult, Error,
+    IsFrameType, RawFrameBuffer, RawFrameBufferMut,
 };
 
 /**
@@ -22,12 +32,18 @@ pub trait AudioInputCallback {
     /**
      * The sample type and number of channels for processing.
      *
-     * Oboe supports only two sample types:
+     * Oboe supports the following sample types:
      *
      * - **i16** - signed 16-bit integer samples
+     * - **I24** - packed signed 24-bit integer samples
+     * - **i32** - signed 32-bit integer samples
      * - **f32** - 32-bit floating point samples
      *
-     * Oboe supports only mono and stereo channel configurations.
+     * Use [`Dynamic`](crate::Dynamic) when the format is known at runtime only.
+     *
+     * The channels can be configured as `Mono`, `Stereo` or `Channels<N>`.
+     * With `Unspecified` channels the frames are passed through the interleaved buffer
+     * which number of channels is known at runtime only.
      */
     type FrameType: IsFrameType;
 
@@ -105,8 +121,37 @@ pub trait AudioInputCallback {
     fn on_audio_ready(
         &mut self,
         audio_stream: &mut dyn AudioInputStreamSafe,
-        audio_data: &[<Self::FrameType as IsFrameType>::Type],
+        audio_data: <Self::FrameType as IsFrameType>::Buffer<'_>,
     ) -> DataCallbackResult;
+
+    /**
+     * Whether the stream computes [`CallbackInfo`] for each buffer
+     *
+     * The info is computed by querying the stream before each callback, so it is disabled
+     * by default. It is checked once when the callback is set.
+     */
+    fn wants_callback_info(&self) -> bool {
+        false
+    }
+
+    /**
+     * A buffer is ready for processing, see [`on_audio_ready`](Self::on_audio_ready).
+     *
+     * The [`CallbackInfo`] carries the position and the capture time of the buffer,
+     * which is computed once per callback by the stream.
+     *
+     * The stream calls this method instead of `on_audio_ready` when
+     * [`wants_callback_info`](Self::wants_callback_info) returns true.
+     * It calls `on_audio_ready` by default.
+     */
+    fn on_audio_ready_with_info(
+        &mut self,
+        audio_stream: &mut dyn AudioInputStreamSafe,
+        _info: &CallbackInfo,
+        audio_data: <Self::FrameType as IsFrameType>::Buffer<'_>,
+    ) -> DataCallbackResult {
+        self.on_audio_ready(audio_stream, audio_data)
+    }
 }
 
 /**
@@ -119,12 +164,18 @@ pub trait AudioOutputCallback {
     /**
      * The sample type and number of channels for processing.
      *
-     * Oboe supports only two sample types:
+     * Oboe supports the following sample types:
      *
      * - **i16** - signed 16-bit integer samples
+     * - **I24** - packed signed 24-bit integer samples
+     * - **i32** - signed 32-bit integer samples
      * - **f32** - 32-bit floating point samples
      *
-     * Oboe supports only mono and stereo channel configurations.
+     * Use [`Dynamic`](crate::Dynamic) when the format is known at runtime only.
+     *
+     * The channels can be configured as `Mono`, `Stereo` or `Channels<N>`.
+     * With `Unspecified` channels the frames are passed through the interleaved buffer
+     * which number of channels is known at runtime only.
      */
     type FrameType: IsFrameType;
 
@@ -202,22 +253,541 @@ pub trait AudioOutputCallback {
     fn on_audio_ready(
         &mut self,
         audio_stream: &mut dyn AudioOutputStreamSafe,
-        audio_data: &mut [<Self::FrameType as IsFrameType>::Type],
+        audio_data: <Self::FrameType as IsFrameType>::BufferMut<'_>,
     ) -> DataCallbackResult;
+
+    /**
+     * Whether the stream computes [`CallbackInfo`] for each buffer
+     *
+     * The info is computed by querying the stream before each callback, so it is disabled
+     * by default. It is checked once when the callback is set.
+     */
+    fn wants_callback_info(&self) -> bool {
+        false
+    }
+
+    /**
+     * A buffer is ready for processing, see [`on_audio_ready`](Self::on_audio_ready).
+     *
+     * The [`CallbackInfo`] carries the position and the presentation time of the buffer,
+     * which is computed once per callback by the stream.
+     *
+     * The stream calls this method instead of `on_audio_ready` when
+     * [`wants_callback_info`](Self::wants_callback_info) returns true.
+     * It calls `on_audio_ready` by default.
+     */
+    fn on_audio_ready_with_info(
+        &mut self,
+        audio_stream: &mut dyn AudioOutputStreamSafe,
+        _info: &CallbackInfo,
+        audio_data: <Self::FrameType as IsFrameType>::BufferMut<'_>,
+    ) -> DataCallbackResult {
+        self.on_audio_ready(audio_stream, audio_data)
+    }
+}
+
+impl<T: AudioInputCallback + ?Sized> AudioInputCallback for Box<T> {
+    type FrameType = T::FrameType;
+
+    fn wants_callback_info(&self) -> bool {
+        (**self).wants_callback_info()
+    }
+
+    fn on_error_before_close(&mut self, audio_stream: &mut dyn AudioInputStreamSafe, error: Error) {
+        (**self).on_error_before_close(audio_stream, error)
+    }
+
+    fn on_error_after_close(&mut self, audio_stream: &mut dyn AudioInputStreamSafe, error: Error) {
+        (**self).on_error_after_close(audio_stream, error)
+    }
+
+    fn on_audio_ready(
+        &mut self,
+        audio_stream: &mut dyn AudioInputStreamSafe,
+        audio_data: <Self::FrameType as IsFrameType>::Buffer<'_>,
+    ) -> DataCallbackResult {
+        (**self).on_audio_ready(audio_stream, audio_data)
+    }
+
+    fn on_audio_ready_with_info(
+        &mut self,
+        audio_stream: &mut dyn AudioInputStreamSafe,
+        info: &CallbackInfo,
+        audio_data: <Self::FrameType as IsFrameType>::Buffer<'_>,
+    ) -> DataCallbackResult {
+        (**self).on_audio_ready_with_info(audio_stream, info, audio_data)
+    }
+}
+
+impl<T: AudioOutputCallback + ?Sized> AudioOutputCallback for Box<T> {
+    type FrameType = T::FrameType;
+
+    fn wants_callback_info(&self) -> bool {
+        (**self).wants_callback_info()
+    }
+
+    fn on_error_before_close(
+        &mut self,
+        audio_stream: &mut dyn AudioOutputStreamSafe,
+        error: Error,
+    ) {
+        (**self).on_error_before_close(audio_stream, error)
+    }
+
+    fn on_error_after_close(&mut self, audio_stream: &mut dyn AudioOutputStreamSafe, error: Error) {
+        (**self).on_error_after_close(audio_stream, error)
+    }
+
+    fn on_audio_ready(
+        &mut self,
+        audio_stream: &mut dyn AudioOutputStreamSafe,
+        audio_data: <Self::FrameType as IsFrameType>::BufferMut<'_>,
+    ) -> DataCallbackResult {
+        (**self).on_audio_ready(audio_stream, audio_data)
+    }
+
+    fn on_audio_ready_with_info(
+        &mut self,
+        audio_stream: &mut dyn AudioOutputStreamSafe,
+        info: &CallbackInfo,
+        audio_data: <Self::FrameType as IsFrameType>::BufferMut<'_>,
+    ) -> DataCallbackResult {
+        (**self).on_audio_ready_with_info(audio_stream, info, audio_data)
+    }
+}
+
+/**
+ * This trait defines a callback interface for being alerted when a stream has an error
+ * or when the stream is disconnected.
+ *
+ * It can be used separately from the data callbacks and with the synchronous
+ * (blocking) streams too.
+ */
+pub trait AudioErrorCallback {
+    /**
+     * This will be called before other `on_error_*` methods when an error occurs on a stream,
+     * such as when the stream is disconnected.
+     *
+     * It can be used to override and customize the normal error processing.
+     * Use of this method is considered an advanced technique.
+     * It might, for example, be used if an app want to use a high level lock when
+     * closing and reopening a stream.
+     * Or it might be used when an app want to signal a management thread that handles
+     * all of the stream state.
+     *
+     * If this method returns `false` it indicates that the stream has *not* been stopped
+     * and closed by the application. In this case it will be stopped by Oboe in the following way:
+     * `on_error_before_close()` will be called, then the stream will be closed
+     * and `on_error_after_close()` will be called.
+     *
+     * If this method returns `true` it indicates that the stream has been stopped and closed
+     * by the application and Oboe will not do this. In that case, the app MUST stop and close
+     * the stream.
+     *
+     * This method will be called on a thread created by Oboe.
+     */
+    fn on_error(&mut self, _audio_stream: &mut dyn AudioStreamSafe, _error: Error) -> bool {
+        false
+    }
+
+    /**
+     * This will be called when an error occurs on a stream or when the stream is disconnected.
+     *
+     * Note that this will be called on a different thread than the onAudioReady() thread.
+     * This thread will be created by Oboe.
+     *
+     * The underlying stream will already be stopped by Oboe but not yet closed.
+     * So the stream can be queried.
+     *
+     * Do not close or delete the stream in this method because it will be
+     * closed after this method returns.
+     */
+    fn on_error_before_close(&mut self, _audio_stream: &mut dyn AudioStreamSafe, _error: Error) {}
+
+    /**
+     * This will be called when an error occurs on a stream or when the stream is disconnected.
+     * The underlying AAudio or OpenSL ES stream will already be stopped AND closed by Oboe.
+     * So the underlying stream cannot be referenced.
+     * But you can still query most parameters.
+     *
+     * This callback could be used to reopen a new stream on another device.
+     */
+    fn on_error_after_close(&mut self, _audio_stream: &mut dyn AudioStreamSafe, _error: Error) {}
+}
+
+/**
+ * The way to handle panics which occur in the stream callbacks.
+ *
+ * Unwinding into the native code is undefined behaviour, so panics are always caught
+ * at the callback boundary. The policy defines what happens after that.
+ *
+ * The payload of the first caught panic can be obtained on the control thread
+ * using [`AudioStreamAsync::take_panic`](crate::AudioStreamAsync::take_panic).
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[repr(u8)]
+pub enum PanicPolicy {
+    /**
+     * Output silence instead of the buffer which was being rendered and continue the stream.
+     * The callback will be called again for the next buffer.
+     */
+    Silence,
+
+    /**
+     * Return [`DataCallbackResult::Stop`] to stop the stream. Default.
+     */
+    #[default]
+    Stop,
+
+    /**
+     * Abort the process immediately.
+     */
+    Abort,
+}
+
+/**
+ * The panic state which is shared between the callback and the stream
+ */
+pub(crate) struct CallbackPanic {
+    policy: AtomicU8,
+    payload: Mutex<Option<Box<dyn Any + Send>>>,
+    /// The number of caught panics which payloads weren't kept
+    dropped: AtomicUsize,
+}
+
+impl CallbackPanic {
+    pub(crate) fn new(policy: PanicPolicy) -> Self {
+        Self {
+            policy: AtomicU8::new(policy as u8),
+            payload: Mutex::new(None),
+            dropped: AtomicUsize::new(0),
+        }
+    }
+
+    pub(crate) fn policy(&self) -> PanicPolicy {
+        match self.policy.load(Ordering::Relaxed) {
+            0 => PanicPolicy::Silence,
+            1 => PanicPolicy::Stop,
+            _ => PanicPolicy::Abort,
+        }
+    }
+
+    pub(crate) fn set_policy(&self, policy: PanicPolicy) {
+        self.policy.store(policy as u8, Ordering::Relaxed);
+    }
+
+    /// Call the function and catch the panic which may occur there
+    pub(crate) fn guard<R>(&self, func: impl FnOnce() -> R) -> Option<R> {
+        match catch_unwind(AssertUnwindSafe(func)) {
+            Ok(result) => Some(result),
+            Err(payload) => {
+                if self.policy() == PanicPolicy::Abort {
+                    abort();
+                }
+                // Never block the audio thread, so the payload is dropped when the control
+                // thread takes the previous one at the same time, but it is still counted.
+                let kept = match self.payload.try_lock() {
+                    Ok(mut slot) if slot.is_none() => {
+                        *slot = Some(payload);
+                        true
+                    }
+                    _ => false,
+                };
+                if !kept {
+                    self.dropped.fetch_add(1, Ordering::Relaxed);
+                }
+                None
+            }
+        }
+    }
+
+    pub(crate) fn take(&self) -> Option<Box<dyn Any + Send>> {
+        self.payload
+            .lock()
+            .unwrap_or_else(|error| error.into_inner())
+            .take()
+    }
+
+    pub(crate) fn dropped(&self) -> usize {
+        self.dropped.load(Ordering::Relaxed)
+    }
+}
+
+/// The callback isn't used
+const SLOT_IDLE: u8 = 0;
+/// The callback is used by one of its methods
+const SLOT_BUSY: u8 = 1;
+/// The callback was taken or is owned elsewhere
+const SLOT_TAKEN: u8 = 2;
+
+/**
+ * The data callback which is shared between the native callback wrapper and the stream,
+ * so the stream can take it back when it is closed
+ *
+ * The atomic state marks the callback which is used or taken, so the audio thread
+ * never waits for it. The callback is taken only after the stream is closed,
+ * so only the error methods may use it at the same time.
+ */
+pub(crate) struct CallbackSlot<T>(Arc<SlotInner<T>>);
+
+struct SlotInner<T> {
+    callback: UnsafeCell<Option<T>>,
+    state: AtomicU8,
+}
+
+// SAFETY: The callback is accessed only by the one who switched the state from idle.
+unsafe impl<T: Send> Sync for SlotInner<T> {}
+
+/**
+ * The reason why the callback can't be used
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SlotUnavailable {
+    Busy,
+    Taken,
+}
+
+/**
+ * The exclusive access to the callback, which is released on drop, also when it panics
+ */
+struct SlotGuard<'a, T>(&'a SlotInner<T>);
+
+impl<T> Deref for SlotGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        // SAFETY: The guard has the exclusive access, and the idle slot is never empty.
+        unsafe { (*self.0.callback.get()).as_ref().unwrap_unchecked() }
+    }
+}
+
+impl<T> DerefMut for SlotGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        // SAFETY: The guard has the exclusive access, and the idle slot is never empty.
+        unsafe { (*self.0.callback.get()).as_mut().unwrap_unchecked() }
+    }
+}
+
+impl<T> Drop for SlotGuard<'_, T> {
+    fn drop(&mut self) {
+        self.0.state.store(SLOT_IDLE, Ordering::Release);
+    }
+}
+
+impl<T> Clone for CallbackSlot<T> {
+    fn clone(&self) -> Self {
+        Self(self.0.clone())
+    }
+}
+
+impl<T> CallbackSlot<T> {
+    pub(crate) fn new(callback: T) -> Self {
+        Self(Arc::new(SlotInner {
+            callback: UnsafeCell::new(Some(callback)),
+            state: AtomicU8::new(SLOT_IDLE),
+        }))
+    }
+
+    /// Create the slot for the callback which is owned elsewhere
+    pub(crate) fn empty() -> Self {
+        Self(Arc::new(SlotInner {
+            callback: UnsafeCell::new(None),
+            state: AtomicU8::new(SLOT_TAKEN),
+        }))
+    }
+
+    /// Take the callback, waiting until it isn't used by the error methods
+    pub(crate) fn take(&self) -> Option<T> {
+        loop {
+            match self.0.state.compare_exchange_weak(
+                SLOT_IDLE,
+                SLOT_TAKEN,
+                Ordering::Acquire,
+                Ordering::Relaxed,
+            ) {
+                // SAFETY: The taken slot isn't accessed anymore.
+                Ok(_) => return unsafe { (*self.0.callback.get()).take() },
+                Err(SLOT_TAKEN) => return None,
+                Err(_) => thread::yield_now(),
+            }
+        }
+    }
+
+    /// Use the callback, waiting until it isn't used elsewhere
+    ///
+    /// Returns `None` when the callback was taken.
+    pub(crate) fn with<R>(&self, use_callback: impl FnOnce(&mut T) -> R) -> Option<R> {
+        loop {
+            match self.try_acquire() {
+                Ok(mut callback) => return Some(use_callback(&mut callback)),
+                Err(SlotUnavailable::Taken) => return None,
+                Err(SlotUnavailable::Busy) => thread::yield_now(),
+            }
+        }
+    }
+
+    /// Get the exclusive access to the callback without blocking the audio thread
+    fn try_acquire(&self) -> result::Result<SlotGuard<'_, T>, SlotUnavailable> {
+        match self.0.state.compare_exchange(
+            SLOT_IDLE,
+            SLOT_BUSY,
+            Ordering::Acquire,
+            Ordering::Relaxed,
+        ) {
+            Ok(_) => Ok(SlotGuard(&self.0)),
+            Err(SLOT_TAKEN) => Err(SlotUnavailable::Taken),
+            Err(_) => Err(SlotUnavailable::Busy),
+        }
+    }
+}
+
+/**
+ * Fill the frames with silence
+ */
+fn write_silence(
+    audio_stream: &mut dyn AudioOutputStreamSafe,
+    mut audio_data: impl RawFrameBufferMut,
+) {
+    let channels = audio_stream.get_channel_count().count() as usize;
+    let bytes_per_frame = audio_stream.get_bytes_per_frame().max(0) as usize;
+    if let Ok((data, frames)) = audio_data._raw_frames_mut(channels, audio_stream.get_format()) {
+        // SAFETY: The buffer holds `frames` frames of the stream.
+        // Zeroed memory is the silence for all supported sample formats.
+        unsafe { write_bytes(data as *mut u8, 0, frames * bytes_per_frame) };
+    }
+}
+
+impl<T: AudioInputCallback> AudioInputCallback for CallbackSlot<T> {
+    type FrameType = T::FrameType;
+
+    fn wants_callback_info(&self) -> bool {
+        self.with(|callback| callback.wants_callback_info())
+            .unwrap_or(false)
+    }
+
+    fn on_error_before_close(&mut self, audio_stream: &mut dyn AudioInputStreamSafe, error: Error) {
+        self.with(|callback| callback.on_error_before_close(audio_stream, error));
+    }
+
+    fn on_error_after_close(&mut self, audio_stream: &mut dyn AudioInputStreamSafe, error: Error) {
+        self.with(|callback| callback.on_error_after_close(audio_stream, error));
+    }
+
+    fn on_audio_ready(
+        &mut self,
+        audio_stream: &mut dyn AudioInputStreamSafe,
+        audio_data: <Self::FrameType as IsFrameType>::Buffer<'_>,
+    ) -> DataCallbackResult {
+        match self.try_acquire() {
+            Ok(mut callback) => callback.on_audio_ready(audio_stream, audio_data),
+            // The error methods use the callback, so the frames are skipped
+            Err(SlotUnavailable::Busy) => DataCallbackResult::Continue,
+            Err(SlotUnavailable::Taken) => DataCallbackResult::Stop,
+        }
+    }
+
+    fn on_audio_ready_with_info(
+        &mut self,
+        audio_stream: &mut dyn AudioInputStreamSafe,
+        info: &CallbackInfo,
+        audio_data: <Self::FrameType as IsFrameType>::Buffer<'_>,
+    ) -> DataCallbackResult {
+        match self.try_acquire() {
+            Ok(mut callback) => callback.on_audio_ready_with_info(audio_stream, info, audio_data),
+            Err(SlotUnavailable::Busy) => DataCallbackResult::Continue,
+            Err(SlotUnavailable::Taken) => DataCallbackResult::Stop,
+        }
+    }
+}
+
+impl<T: AudioOutputCallback> AudioOutputCallback for CallbackSlot<T> {
+    type FrameType = T::FrameType;
+
+    fn wants_callback_info(&self) -> bool {
+        self.with(|callback| callback.wants_callback_info())
+            .unwrap_or(false)
+    }
+
+    fn on_error_before_close(
+        &mut self,
+        audio_stream: &mut dyn AudioOutputStreamSafe,
+        error: Error,
+    ) {
+        self.with(|callback| callback.on_error_before_close(audio_stream, error));
+    }
+
+    fn on_error_after_close(&mut self, audio_stream: &mut dyn AudioOutputStreamSafe, error: Error) {
+        self.with(|callback| callback.on_error_after_close(audio_stream, error));
+    }
+
+    fn on_audio_ready(
+        &mut self,
+        audio_stream: &mut dyn AudioOutputStreamSafe,
+        audio_data: <Self::FrameType as IsFrameType>::BufferMut<'_>,
+    ) -> DataCallbackResult {
+        match self.try_acquire() {
+            Ok(mut callback) => callback.on_audio_ready(audio_stream, audio_data),
+            // The error methods use the callback, so the silence is played
+            Err(SlotUnavailable::Busy) => {
+                write_silence(audio_stream, audio_data);
+                DataCallbackResult::Continue
+            }
+            Err(SlotUnavailable::Taken) => DataCallbackResult::Stop,
+        }
+    }
+
+    fn on_audio_ready_with_info(
+        &mut self,
+        audio_stream: &mut dyn AudioOutputStreamSafe,
+        info: &CallbackInfo,
+        audio_data: <Self::FrameType as IsFrameType>::BufferMut<'_>,
+    ) -> DataCallbackResult {
+        match self.try_acquire() {
+            Ok(mut callback) => callback.on_audio_ready_with_info(audio_stream, info, audio_data),
+            Err(SlotUnavailable::Busy) => {
+                write_silence(audio_stream, audio_data);
+                DataCallbackResult::Continue
+            }
+            Err(SlotUnavailable::Taken) => DataCallbackResult::Stop,
+        }
+    }
+}
+
+/**
+ * The data which is passed to the native callback wrapper as context
+ */
+struct CallbackContext<T> {
+    callback: T,
+    panic: Arc<CallbackPanic>,
+    /// The tracker when the callback wants the info
+    info: Option<CallbackInfoTracker>,
+}
+
+impl<T> CallbackContext<T> {
+    fn into_raw(callback: T, panic: &Arc<CallbackPanic>, info: bool) -> *mut Self {
+        let context = Box::new(Self {
+            callback,
+            panic: panic.clone(),
+            info: info.then(CallbackInfoTracker::default),
+        });
+        Box::into_raw(context)
+    }
 }
 
 pub(crate) fn set_input_callback<T: AudioInputCallback>(
     builder: &mut AudioStreamBuilderHandle,
     callback: T,
+    panic: &Arc<CallbackPanic>,
 ) {
-    let callback = Box::into_raw(Box::new(callback));
+    let info = callback.wants_callback_info();
+    let context = CallbackContext::into_raw(callback, panic, info);
 
-    // SAFETY: `callback` has the same type as the first argument of each function, and each
+    // SAFETY: `context` has the same type as the first argument of each function, and each
     // function follows the C ABI.
     unsafe {
         ffi::oboe_AudioStreamBuilder_setCallback(
             &mut **builder as *mut ffi::oboe_AudioStreamBuilder,
-            callback.cast(),
+            context.cast(),
             Some(drop_context::<T>),
             Some(on_audio_ready_input_wrapper::<T>),
             Some(on_error_before_close_input_wrapper::<T>),
@@ -229,16 +799,17 @@ pub(crate) fn set_input_callback<T: AudioInputCallback>(
 pub(crate) fn set_output_callback<T: AudioOutputCallback>(
     builder: &mut AudioStreamBuilderHandle,
     callback: T,
+    panic: &Arc<CallbackPanic>,
 ) {
-    let callback = Box::new(callback);
-    let callback = Box::into_raw(callback);
+    let info = callback.wants_callback_info();
+    let context = CallbackContext::into_raw(callback, panic, info);
 
-    // SAFETY: `callback` has the same type as the first argument of each function, and each
+    // SAFETY: `context` has the same type as the first argument of each function, and each
     // function follows the C ABI.
     unsafe {
         ffi::oboe_AudioStreamBuilder_setCallback(
             &mut **builder as *mut ffi::oboe_AudioStreamBuilder,
-            callback.cast(),
+            context.cast(),
             Some(drop_context::<T>),
             Some(on_audio_ready_output_wrapper::<T>),
             Some(on_error_before_close_output_wrapper::<T>),
@@ -247,9 +818,63 @@ pub(crate) fn set_output_callback<T: AudioOutputCallback>(
     }
 }
 
+pub(crate) fn set_stabilized_output_callback<T: AudioOutputCallback>(
+    builder: &mut AudioStreamBuilderHandle,
+    callback: T,
+    panic: &Arc<CallbackPanic>,
+) {
+    let info = callback.wants_callback_info();
+    let context = CallbackContext::into_raw(callback, panic, info);
+
+    // SAFETY: `context` has the same type as the first argument of each function, and each
+    // function follows the C ABI.
+    unsafe {
+        ffi::oboe_AudioStreamBuilder_setStabilizedCallback(
+            &mut **builder as *mut ffi::oboe_AudioStreamBuilder,
+            context.cast(),
+            Some(drop_context::<T>),
+            Some(on_audio_ready_output_wrapper::<T>),
+            Some(on_error_before_close_output_wrapper::<T>),
+            Some(on_error_after_close_output_wrapper::<T>),
+        );
+    }
+}
+
+pub(crate) fn set_error_callback<T: AudioErrorCallback>(
+    builder: &mut AudioStreamBuilderHandle,
+    callback: T,
+    panic: &Arc<CallbackPanic>,
+) {
+    let context = CallbackContext::into_raw(callback, panic, false);
+
+    // SAFETY: `context` has the same type as the first argument of each function, and each
+    // function follows the C ABI.
+    unsafe {
+        ffi::oboe_AudioStreamBuilder_setErrorCallback(
+            &mut **builder as *mut ffi::oboe_AudioStreamBuilder,
+            context.cast(),
+            Some(drop_context::<T>),
+            Some(on_error_wrapper::<T>),
+            Some(on_error_before_close_wrapper::<T>),
+            Some(on_error_after_close_wrapper::<T>),
+        );
+    }
+}
+
+pub(crate) fn reset_error_callback(builder: &mut AudioStreamBuilderHandle) {
+    // SAFETY: The builder is valid, and the previous error callback is released by Oboe.
+    unsafe {
+        ffi::oboe_AudioStreamBuilder_resetErrorCallback(
+            &mut **builder as *mut ffi::oboe_AudioStreamBuilder,
+        );
+    }
+}
+
 unsafe extern "C" fn drop_context<T>(context: *mut c_void) {
-    let context = Box::from_raw(context as *mut T);
-    drop(context);
+    let context = Box::from_raw(context as *mut CallbackContext<T>);
+    let panic = context.panic.clone();
+
+    panic.guard(move || drop(context));
 }
 
 unsafe extern "C" fn on_error_before_close_input_wrapper<T: AudioInputCallback>(
@@ -258,9 +883,12 @@ unsafe extern "C" fn on_error_before_close_input_wrapper<T: AudioInputCallback>(
     error: ffi::oboe_Result,
 ) {
     let mut audio_stream = AudioStreamRef::wrap_raw(&mut *audio_stream);
-    let callback = &mut *(context as *mut T);
+    let context = &mut *(context as *mut CallbackContext<T>);
+    let callback = &mut context.callback;
 
-    callback.on_error_before_close(&mut audio_stream, FromPrimitive::from_i32(error).unwrap());
+    context
+        .panic
+        .guard(|| callback.on_error_before_close(&mut audio_stream, error.into()));
 }
 
 unsafe extern "C" fn on_error_after_close_input_wrapper<T: AudioInputCallback>(
@@ -269,9 +897,12 @@ unsafe extern "C" fn on_error_after_close_input_wrapper<T: AudioInputCallback>(
     error: ffi::oboe_Result,
 ) {
     let mut audio_stream = AudioStreamRef::wrap_raw(&mut *audio_stream);
-    let callback = &mut *(context as *mut T);
+    let context = &mut *(context as *mut CallbackContext<T>);
+    let callback = &mut context.callback;
 
-    callback.on_error_after_close(&mut audio_stream, FromPrimitive::from_i32(error).unwrap());
+    context
+        .panic
+        .guard(|| callback.on_error_after_close(&mut audio_stream, error.into()));
 }
 
 unsafe extern "C" fn on_audio_ready_input_wrapper<T: AudioInputCallback>(
@@ -282,14 +913,34 @@ unsafe extern "C" fn on_audio_ready_input_wrapper<T: AudioInputCallback>(
 ) -> ffi::oboe_DataCallbackResult {
     let mut audio_stream = AudioStreamRef::wrap_raw(&mut *audio_stream);
 
-    let audio_data = from_raw_parts(
-        audio_data as *const <T::FrameType as IsFrameType>::Type,
+    let channels = audio_stream.get_channel_count().count() as usize;
+    let Ok(audio_data) = <T::FrameType as IsFrameType>::Buffer::_wrap_raw(
+        audio_data,
         num_frames as usize,
-    );
+        channels,
+        audio_stream.get_format(),
+    ) else {
+        // The frames of stream can't be passed to the callback
+        return DataCallbackResult::Stop as i32;
+    };
 
-    let callback = &mut *(context as *mut T);
+    let context = &mut *(context as *mut CallbackContext<T>);
+    let (callback, tracker) = (&mut context.callback, &mut context.info);
 
-    callback.on_audio_ready(&mut audio_stream, audio_data) as i32
+    context
+        .panic
+        .guard(|| match tracker {
+            Some(tracker) => {
+                let frame_position = audio_stream.get_frames_read();
+                let info = tracker.next(&mut audio_stream, frame_position);
+                callback.on_audio_ready_with_info(&mut audio_stream, &info, audio_data)
+            }
+            None => callback.on_audio_ready(&mut audio_stream, audio_data),
+        })
+        .unwrap_or_else(|| match context.panic.policy() {
+            PanicPolicy::Silence => DataCallbackResult::Continue,
+            _ => DataCallbackResult::Stop,
+        }) as i32
 }
 
 unsafe extern "C" fn on_error_before_close_output_wrapper<T: AudioOutputCallback>(
@@ -298,10 +949,12 @@ unsafe extern "C" fn on_error_before_close_output_wrapper<T: AudioOutputCallback
     error: ffi::oboe_Result,
 ) {
     let mut audio_stream = AudioStreamRef::wrap_raw(&mut *audio_stream);
+    let context = &mut *(context as *mut CallbackContext<T>);
+    let callback = &mut context.callback;
 
-    let callback = &mut *(context as *mut T);
-
-    callback.on_error_before_close(&mut audio_stream, FromPrimitive::from_i32(error).unwrap());
+    context
+        .panic
+        .guard(|| callback.on_error_before_close(&mut audio_stream, error.into()));
 }
 
 unsafe extern "C" fn on_error_after_close_output_wrapper<T: AudioOutputCallback>(
@@ -310,9 +963,12 @@ unsafe extern "C" fn on_error_after_close_output_wrapper<T: AudioOutputCallback>
     error: ffi::oboe_Result,
 ) {
     let mut audio_stream = AudioStreamRef::wrap_raw(&mut *audio_stream);
-    let callback = &mut *(context as *mut T);
+    let context = &mut *(context as *mut CallbackContext<T>);
+    let callback = &mut context.callback;
 
-    callback.on_error_after_close(&mut audio_stream, FromPrimitive::from_i32(error).unwrap());
+    context
+        .panic
+        .guard(|| callback.on_error_after_close(&mut audio_stream, error.into()));
 }
 
 unsafe extern "C" fn on_audio_ready_output_wrapper<T: AudioOutputCallback>(
@@ -323,12 +979,448 @@ unsafe extern "C" fn on_audio_ready_output_wrapper<T: AudioOutputCallback>(
 ) -> ffi::oboe_DataCallbackResult {
     let mut audio_stream = AudioStreamRef::wrap_raw(&mut *audio_stream);
 
-    let audio_data = from_raw_parts_mut(
-        audio_data as *mut <T::FrameType as IsFrameType>::Type,
+    let channels = audio_stream.get_channel_count().count() as usize;
+    let bytes_per_frame = audio_stream.get_bytes_per_frame() as usize;
+    let Ok(audio_frames) = <T::FrameType as IsFrameType>::BufferMut::_wrap_raw_mut(
+        audio_data,
         num_frames as usize,
-    );
+        channels,
+        audio_stream.get_format(),
+    ) else {
+        // The frames of stream can't be passed to the callback
+        return DataCallbackResult::Stop as i32;
+    };
+
+    let context = &mut *(context as *mut CallbackContext<T>);
+    let (callback, tracker) = (&mut context.callback, &mut context.info);
 
-    let callback = &mut *(context as *mut T);
+    context
+        .panic
+        .guard(|| match tracker {
+            Some(tracker) => {
+                let frame_position = audio_stream.get_frames_written();
+                let info = tracker.next(&mut audio_stream, frame_position);
+                callback.on_audio_ready_with_info(&mut audio_stream, &info, audio_frames)
+            }
+            None => callback.on_audio_ready(&mut audio_stream, audio_frames),
+        })
+        .unwrap_or_else(|| match context.panic.policy() {
+            PanicPolicy::Silence => {
+                // Zeroed memory is the silence for all supported sample formats
+                write_bytes(
+                    audio_data as *mut u8,
+                    0,
+                    num_frames as usize * bytes_per_frame,
+                );
+                DataCallbackResult::Continue
+            }
+            _ => DataCallbackResult::Stop,
+        }) as i32
+}
 
-    callback.on_audio_ready(&mut audio_stream, audio_data) as i32
+unsafe extern "C" fn on_error_wrapper<T: AudioErrorCallback>(
+    context: *mut c_void,
+    audio_stream: *mut ffi::oboe_AudioStream,
+    error: ffi::oboe_Result,
+) -> bool {
+    let mut audio_stream = AudioStreamRef::<()>::wrap_raw(&mut *audio_stream);
+    let context = &mut *(context as *mut CallbackContext<T>);
+    let callback = &mut context.callback;
+
+    // Let Oboe stop and close the stream when the callback panics
+    context
+        .panic
+        .guard(|| callback.on_error(&mut audio_stream, error.into()))
+        .unwrap_or(false)
+}
+
+unsafe extern "C" fn on_error_before_close_wrapper<T: AudioErrorCallback>(
+    context: *mut c_void,
+    audio_stream: *mut ffi::oboe_AudioStream,
+    error: ffi::oboe_Result,
+) {
+    let mut audio_stream = AudioStreamRef::<()>::wrap_raw(&mut *audio_stream);
+    let context = &mut *(context as *mut CallbackContext<T>);
+    let callback = &mut context.callback;
+
+    context
+        .panic
+        .guard(|| callback.on_error_before_close(&mut audio_stream, error.into()));
+}
+
+unsafe extern "C" fn on_error_after_close_wrapper<T: AudioErrorCallback>(
+    context: *mut c_void,
+    audio_stream: *mut ffi::oboe_AudioStream,
+    error: ffi::oboe_Result,
+) {
+    let mut audio_stream = AudioStreamRef::<()>::wrap_raw(&mut *audio_stream);
+    let context = &mut *(context as *mut CallbackContext<T>);
+    let callback = &mut context.callback;
+
+    context
+        .panic
+        .guard(|| callback.on_error_after_close(&mut audio_stream, error.into()));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn payload(panic: &CallbackPanic) -> Option<String> {
+        panic
+            .take()
+            .map(|payload| *payload.downcast::<&str>().unwrap())
+            .map(String::from)
+    }
+
+    #[test]
+    fn guard_returns_result_without_panic() {
+        let panic = CallbackPanic::new(PanicPolicy::Stop);
+        assert_eq!(panic.guard(|| 42), Some(42));
+        assert!(panic.take().is_none());
+        assert_eq!(panic.dropped(), 0);
+    }
+
+    #[test]
+    fn guard_keeps_first_payload() {
+        let panic = CallbackPanic::new(PanicPolicy::Silence);
+        assert_eq!(
+            panic.guard(|| -> i32 { std::panic::panic_any("first") }),
+            None
+        );
+        assert_eq!(
+            panic.guard(|| -> i32 { std::panic::panic_any("second") }),
+            None
+        );
+        assert_eq!(payload(&panic).as_deref(), Some("first"));
+        assert_eq!(panic.dropped(), 1);
+
+        assert_eq!(
+            panic.guard(|| -> i32 { std::panic::panic_any("third") }),
+            None
+        );
+        assert_eq!(payload(&panic).as_deref(), Some("third"));
+        assert_eq!(panic.dropped(), 1);
+    }
+
+    #[test]
+    fn guard_counts_payload_dropped_on_contention() {
+        let panic = CallbackPanic::new(PanicPolicy::Stop);
+        {
+            let _taking = panic.payload.lock().unwrap();
+            assert_eq!(
+                panic.guard(|| -> i32 { std::panic::panic_any("busy") }),
+                None
+            );
+        }
+        assert!(panic.take().is_none());
+        assert_eq!(panic.dropped(), 1);
+    }
+
+    #[test]
+    fn policy_can_be_changed() {
+        let panic = CallbackPanic::new(PanicPolicy::default());
+        assert_eq!(panic.policy(), PanicPolicy::Stop);
+        for policy in [PanicPolicy::Silence, PanicPolicy::Abort, PanicPolicy::Stop] {
+            panic.set_policy(policy);
+            assert_eq!(panic.policy(), policy);
+        }
+    }
+
+    /// The output callback which fills the frames with ones
+    struct Ones(usize);
+
+    impl AudioOutputCallback for Ones {
+        type FrameType = (f32, crate::Mono);
+
+        fn on_audio_ready(
+            &mut self,
+            _audio_stream: &mut dyn AudioOutputStreamSafe,
+            audio_data: &mut [f32],
+        ) -> DataCallbackResult {
+            self.0 += 1;
+            audio_data.fill(1.0);
+            DataCallbackResult::Continue
+        }
+    }
+
+    #[cfg(not(target_os = "android"))]
+    fn output_stream() -> crate::AudioStreamSync<crate::Output, (f32, crate::Mono)> {
+        use crate::{fake_stream::*, AudioFormat, Direction};
+        sync_stream(&FakeStream::new(Direction::Output, AudioFormat::F32, 1))
+    }
+
+    #[test]
+    #[cfg(not(target_os = "android"))]
+    fn busy_slot_plays_silence_without_stopping() {
+        let mut stream = output_stream();
+        let mut slot = CallbackSlot::new(Ones(0));
+        let mut frames = [0.5; 4];
+
+        {
+            let _error_method = slot.try_acquire().unwrap();
+            assert_eq!(
+                slot.clone().on_audio_ready(&mut stream, &mut frames),
+                DataCallbackResult::Continue
+            );
+            assert_eq!(frames, [0.0; 4]);
+        }
+
+        assert_eq!(
+            slot.on_audio_ready(&mut stream, &mut frames),
+            DataCallbackResult::Continue
+        );
+        assert_eq!(frames, [1.0; 4]);
+        assert_eq!(slot.take().unwrap().0, 1);
+    }
+
+    #[test]
+    #[cfg(not(target_os = "android"))]
+    fn taken_slot_stops_stream() {
+        let mut stream = output_stream();
+        let mut slot = CallbackSlot::new(Ones(0));
+        assert_eq!(slot.take().unwrap().0, 0);
+        assert!(slot.take().is_none());
+        assert_eq!(slot.with(|callback| callback.0), None);
+
+        let mut frames = [0.5; 4];
+        assert_eq!(
+            slot.on_audio_ready(&mut stream, &mut frames),
+            DataCallbackResult::Stop
+        );
+        assert_eq!(frames, [0.5; 4]);
+
+        let mut slot = CallbackSlot::<Ones>::empty();
+        assert_eq!(
+            slot.on_audio_ready(&mut stream, &mut frames),
+            DataCallbackResult::Stop
+        );
+        assert!(slot.take().is_none());
+    }
+
+    #[test]
+    fn take_waits_for_error_methods() {
+        let slot = CallbackSlot::new(Ones(0));
+        let (started, start) = std::sync::mpsc::channel();
+
+        let error_method = {
+            let slot = slot.clone();
+            std::thread::spawn(move || {
+                slot.with(|callback| {
+                    started.send(()).unwrap();
+                    std::thread::sleep(std::time::Duration::from_millis(20));
+                    callback.0 = 42;
+                })
+            })
+        };
+        start.recv().unwrap();
+
+        assert_eq!(slot.take().unwrap().0, 42);
+        assert_eq!(error_method.join().unwrap(), Some(()));
+    }
+
+    #[test]
+    fn panicking_callback_releases_slot() {
+        let slot = CallbackSlot::new(Ones(0));
+        let panic = CallbackPanic::new(PanicPolicy::Silence);
+        assert_eq!(
+            panic.guard(|| slot.with(|_| -> i32 { std::panic::panic_any("callback") })),
+            None
+        );
+
+        assert_eq!(slot.with(|callback| callback.0), Some(0));
+        assert!(slot.take().is_some());
+    }
+
+    type Events = Arc<Mutex<Vec<&'static str>>>;
+
+    /// The error callback which records its calls
+    struct Recorder {
+        events: Events,
+        handled: bool,
+    }
+
+    impl AudioErrorCallback for Recorder {
+        fn on_error(&mut self, _audio_stream: &mut dyn AudioStreamSafe, error: Error) -> bool {
+            assert_eq!(error, Error::Disconnected);
+            self.events.lock().unwrap().push("error");
+            self.handled
+        }
+
+        fn on_error_before_close(
+            &mut self,
+            _audio_stream: &mut dyn AudioStreamSafe,
+            _error: Error,
+        ) {
+            self.events.lock().unwrap().push("before close");
+        }
+
+        fn on_error_after_close(&mut self, _audio_stream: &mut dyn AudioStreamSafe, _error: Error) {
+            self.events.lock().unwrap().push("after close");
+        }
+    }
+
+    impl Drop for Recorder {
+        fn drop(&mut self) {
+            if let Ok(mut events) = self.events.lock() {
+                events.push("dropped");
+            }
+        }
+    }
+
+    /// The data callback which records the calls of its error methods
+    struct DataRecorder(Events);
+
+    impl AudioOutputCallback for DataRecorder {
+        type FrameType = (f32, crate::Mono);
+
+        fn on_error_before_close(
+            &mut self,
+            _audio_stream: &mut dyn AudioOutputStreamSafe,
+            _error: Error,
+        ) {
+            self.0.lock().unwrap().push("data before close");
+        }
+
+        fn on_error_after_close(
+            &mut self,
+            _audio_stream: &mut dyn AudioOutputStreamSafe,
+            _error: Error,
+        ) {
+            self.0.lock().unwrap().push("data after close");
+        }
+
+        fn on_audio_ready(
+            &mut self,
+            _audio_stream: &mut dyn AudioOutputStreamSafe,
+            _audio_data: &mut [f32],
+        ) -> DataCallbackResult {
+            DataCallbackResult::Continue
+        }
+    }
+
+    #[cfg(not(target_os = "android"))]
+    fn record_opened() -> Arc<Mutex<Option<Arc<crate::fake_stream::FakeStream>>>> {
+        let opened = Arc::new(Mutex::new(None));
+        let fakes = opened.clone();
+        crate::fake_stream::on_open(move |fake| {
+            *fakes.lock().unwrap() = Some(fake.clone());
+            Ok(())
+        });
+        opened
+    }
+
+    #[test]
+    #[cfg(not(target_os = "android"))]
+    fn error_callback_replaces_error_methods_of_data_callback() {
+        use crate::{AudioStreamBuilder, StreamState};
+
+        let opened = record_opened();
+        for error_first in [true, false] {
+            let events = Events::default();
+            let error_callback = Recorder {
+                events: events.clone(),
+                handled: false,
+            };
+            let data_callback = DataRecorder(events.clone());
+
+            let builder = AudioStreamBuilder::default().set_f32().set_mono();
+            let builder = if error_first {
+                builder
+                    .set_error_callback(error_callback)
+                    .set_callback(data_callback)
+            } else {
+                builder
+                    .set_callback(data_callback)
+                    .set_error_callback(error_callback)
+            };
+            let stream = builder.open_stream().unwrap();
+            let fake = opened.lock().unwrap().take().unwrap();
+            fake.fail(Error::Disconnected);
+
+            assert_eq!(stream.get_state(), StreamState::Closed);
+            assert_eq!(
+                *events.lock().unwrap(),
+                ["error", "before close", "after close"]
+            );
+        }
+    }
+
+    #[test]
+    #[cfg(not(target_os = "android"))]
+    fn handled_error_skips_close_methods() {
+        use crate::{AudioStreamBuilder, StreamState};
+
+        let opened = record_opened();
+        let events = Events::default();
+        let stream = AudioStreamBuilder::default()
+            .set_f32()
+            .set_mono()
+            .set_error_callback(Recorder {
+                events: events.clone(),
+                handled: true,
+            })
+            .set_callback(DataRecorder(events.clone()))
+            .open_stream()
+            .unwrap();
+        let fake = opened.lock().unwrap().take().unwrap();
+        fake.fail(Error::Disconnected);
+
+        assert_eq!(*events.lock().unwrap(), ["error"]);
+        assert_eq!(stream.get_state(), StreamState::Open);
+    }
+
+    #[test]
+    #[cfg(not(target_os = "android"))]
+    fn sync_stream_receives_error_callback() {
+        use crate::{AudioStreamBuilder, StreamState};
+
+        let opened = record_opened();
+        let events = Events::default();
+        let stream = AudioStreamBuilder::default()
+            .set_output()
+            .set_f32()
+            .set_mono()
+            .set_error_callback(Recorder {
+                events: events.clone(),
+                handled: false,
+            })
+            .open_stream()
+            .unwrap();
+        let fake = opened.lock().unwrap().take().unwrap();
+        fake.fail(Error::Disconnected);
+
+        assert_eq!(stream.get_state(), StreamState::Closed);
+        assert_eq!(
+            *events.lock().unwrap(),
+            ["error", "before close", "after close"]
+        );
+    }
+
+    #[test]
+    #[cfg(not(target_os = "android"))]
+    fn error_callback_is_dropped_with_builder_or_stream() {
+        use crate::AudioStreamBuilder;
+
+        let events = Events::default();
+        let builder = || {
+            AudioStreamBuilder::default()
+                .set_output()
+                .set_f32()
+                .set_mono()
+                .set_error_callback(Recorder {
+                    events: events.clone(),
+                    handled: false,
+                })
+        };
+
+        drop(builder());
+        assert_eq!(*events.lock().unwrap(), ["dropped"]);
+        events.lock().unwrap().clear();
+
+        let stream = builder().open_stream().unwrap();
+        assert!(events.lock().unwrap().is_empty());
+        drop(stream);
+        assert_eq!(*events.lock().unwrap(), ["dropped"]);
+    }
 }
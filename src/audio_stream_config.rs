@@ -0,0 +1,141 @@
+use super::{
+    AudioApi, AudioFormat, AudioStreamBase, ChannelCount, ChannelMask, ContentType, Direction,
+    InputPreset, PerformanceMode, SampleRateConversionQuality, SessionId, SharingMode, Usage,
+};
+
+/**
+ * The plain snapshot of the audio stream configuration
+ *
+ * It can be taken from a builder using [`AudioStreamBuilder::config`](crate::AudioStreamBuilder::config)
+ * or from an opened stream using [`AudioStreamSafe::actual_config`](crate::AudioStreamSafe::actual_config),
+ * and turned back into a builder using
+ * [`AudioStreamBuilder::from_config`](crate::AudioStreamBuilder::from_config).
+ *
+ * With `serde` feature it can be serialized and deserialized.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AudioStreamConfig {
+    /**
+     * The stream direction
+     */
+    pub direction: Direction,
+
+    /**
+     * The sample rate in Hz
+     */
+    pub sample_rate: i32,
+
+    /**
+     * The number of frames in each callback
+     */
+    pub frames_per_callback: i32,
+
+    /**
+     * The sample format
+     */
+    pub format: AudioFormat,
+
+    /**
+     * The number of channels
+     */
+    pub channel_count: ChannelCount,
+
+    /**
+     * The channel mask
+     */
+    pub channel_mask: ChannelMask,
+
+    /**
+     * The buffer size in frames
+     *
+     * It is reported by the opened streams only and isn't applied to the builder.
+     */
+    pub buffer_size_in_frames: i32,
+
+    /**
+     * The buffer capacity in frames
+     */
+    pub buffer_capacity_in_frames: i32,
+
+    /**
+     * The sharing mode
+     */
+    pub sharing_mode: SharingMode,
+
+    /**
+     * The performance mode
+     */
+    pub performance_mode: PerformanceMode,
+
+    /**
+     * The device identifier
+     */
+    pub device_id: i32,
+
+    /**
+     * The session ID or its allocation strategy
+     */
+    pub session_id: SessionId,
+
+    /**
+     * The usage
+     */
+    pub usage: Usage,
+
+    /**
+     * The content type
+     */
+    pub content_type: ContentType,
+
+    /**
+     * The input preset
+     */
+    pub input_preset: InputPreset,
+
+    /**
+     * Whether Oboe can convert channel counts
+     */
+    pub channel_conversion_allowed: bool,
+
+    /**
+     * Whether Oboe can convert data formats
+     */
+    pub format_conversion_allowed: bool,
+
+    /**
+     * Whether and how Oboe can convert sample rates
+     */
+    pub sample_rate_conversion_quality: SampleRateConversionQuality,
+
+    /**
+     * The underlying audio API
+     */
+    pub audio_api: AudioApi,
+}
+
+impl AudioStreamConfig {
+    pub(crate) fn from_base<T: AudioStreamBase + ?Sized>(base: &T, audio_api: AudioApi) -> Self {
+        Self {
+            direction: base.get_direction(),
+            sample_rate: base.get_sample_rate(),
+            frames_per_callback: base.get_frames_per_callback(),
+            format: base.get_format(),
+            channel_count: base.get_channel_count(),
+            channel_mask: base.get_channel_mask(),
+            buffer_size_in_frames: base.get_buffer_size_in_frames(),
+            buffer_capacity_in_frames: base.get_buffer_capacity_in_frames(),
+            sharing_mode: base.get_sharing_mode(),
+            performance_mode: base.get_performance_mode(),
+            device_id: base.get_device_id(),
+            session_id: base.get_session_id(),
+            usage: base.get_usage(),
+            content_type: base.get_content_type(),
+            input_preset: base.get_input_preset(),
+            channel_conversion_allowed: base.is_channel_conversion_allowed(),
+            format_conversion_allowed: base.is_format_conversion_allowed(),
+            sample_rate_conversion_quality: base.get_sample_rate_conversion_quality(),
+            audio_api,
+        }
+    }
+}
@@ -0,0 +1,308 @@
+use std::{fmt, marker::PhantomData};
+
+use super::{
+    AudioInputCallback, AudioInputStreamSafe, AudioOutputCallback, AudioOutputStreamSafe,
+    DataCallbackResult, Error, IsFrameType,
+};
+
+type InputErrorFn = Box<dyn FnMut(&mut dyn AudioInputStreamSafe, Error) + Send>;
+
+type OutputErrorFn = Box<dyn FnMut(&mut dyn AudioOutputStreamSafe, Error) + Send>;
+
+/**
+ * The input callback which calls the closures
+ *
+ * It is created by [`AudioStreamBuilder::set_callback_fn`](crate::AudioStreamBuilder::set_callback_fn).
+ */
+pub struct InputCallbackFn<F, A> {
+    audio_ready: A,
+    before_close: Option<InputErrorFn>,
+    after_close: Option<InputErrorFn>,
+    _phantom: PhantomData<F>,
+}
+
+impl<F, A> InputCallbackFn<F, A> {
+    pub(crate) fn new(audio_ready: A) -> Self {
+        Self {
+            audio_ready,
+            before_close: None,
+            after_close: None,
+            _phantom: PhantomData,
+        }
+    }
+
+    pub(crate) fn set_before_close(&mut self, before_close: InputErrorFn) {
+        self.before_close = Some(before_close);
+    }
+
+    pub(crate) fn set_after_close(&mut self, after_close: InputErrorFn) {
+        self.after_close = Some(after_close);
+    }
+}
+
+impl<F, A> fmt::Debug for InputCallbackFn<F, A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("InputCallbackFn").finish_non_exhaustive()
+    }
+}
+
+impl<F, A> AudioInputCallback for InputCallbackFn<F, A>
+where
+    F: IsFrameType,
+    A: for<'a> FnMut(&mut dyn AudioInputStreamSafe, F::Buffer<'a>) -> DataCallbackResult,
+{
+    type FrameType = F;
+
+    fn on_error_before_close(&mut self, audio_stream: &mut dyn AudioInputStreamSafe, error: Error) {
+        if let Some(before_close) = &mut self.before_close {
+            before_close(audio_stream, error);
+        }
+    }
+
+    fn on_error_after_close(&mut self, audio_stream: &mut dyn AudioInputStreamSafe, error: Error) {
+        if let Some(after_close) = &mut self.after_close {
+            after_close(audio_stream, error);
+        }
+    }
+
+    fn on_audio_ready(
+        &mut self,
+        audio_stream: &mut dyn AudioInputStreamSafe,
+        audio_data: F::Buffer<'_>,
+    ) -> DataCallbackResult {
+        (self.audio_ready)(audio_stream, audio_data)
+    }
+}
+
+/**
+ * The output callback which calls the closures
+ *
+ * It is created by [`AudioStreamBuilder::set_callback_fn`](crate::AudioStreamBuilder::set_callback_fn).
+ */
+pub struct OutputCallbackFn<F, A> {
+    audio_ready: A,
+    before_close: Option<OutputErrorFn>,
+    after_close: Option<OutputErrorFn>,
+    _phantom: PhantomData<F>,
+}
+
+impl<F, A> OutputCallbackFn<F, A> {
+    pub(crate) fn new(audio_ready: A) -> Self {
+        Self {
+            audio_ready,
+            before_close: None,
+            after_close: None,
+            _phantom: PhantomData,
+        }
+    }
+
+    pub(crate) fn set_before_close(&mut self, before_close: OutputErrorFn) {
+        self.before_close = Some(before_close);
+    }
+
+    pub(crate) fn set_after_close(&mut self, after_close: OutputErrorFn) {
+        self.after_close = Some(after_close);
+    }
+}
+
+impl<F, A> fmt::Debug for OutputCallbackFn<F, A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("OutputCallbackFn").finish_non_exhaustive()
+    }
+}
+
+impl<F, A> AudioOutputCallback for OutputCallbackFn<F, A>
+where
+    F: IsFrameType,
+    A: for<'a> FnMut(&mut dyn AudioOutputStreamSafe, F::BufferMut<'a>) -> DataCallbackResult,
+{
+    type FrameType = F;
+
+    fn on_error_before_close(
+        &mut self,
+        audio_stream: &mut dyn AudioOutputStreamSafe,
+        error: Error,
+    ) {
+        if let Some(before_close) = &mut self.before_close {
+            before_close(audio_stream, error);
+        }
+    }
+
+    fn on_error_after_close(&mut self, audio_stream: &mut dyn AudioOutputStreamSafe, error: Error) {
+        if let Some(after_close) = &mut self.after_close {
+            after_close(audio_stream, error);
+        }
+    }
+
+    fn on_audio_ready(
+        &mut self,
+        audio_stream: &mut dyn AudioOutputStreamSafe,
+        audio_data: F::BufferMut<'_>,
+    ) -> DataCallbackResult {
+        (self.audio_ready)(audio_stream, audio_data)
+    }
+}
+
+#[cfg(all(test, not(target_os = "android")))]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use crate::{
+        fake_stream::{on_open, FakeStream},
+        AudioStreamBuilder, AudioStreamSafe, DataCallbackResult, Error, StreamState,
+    };
+
+    type Opened = Arc<Mutex<Option<Arc<FakeStream>>>>;
+
+    fn record_opened() -> Opened {
+        let opened = Opened::default();
+        let fakes = opened.clone();
+        on_open(move |fake| {
+            *fakes.lock().unwrap() = Some(fake.clone());
+            Ok(())
+        });
+        opened
+    }
+
+    fn fake(opened: &Opened) -> Arc<FakeStream> {
+        opened.lock().unwrap().take().expect("The stream is opened")
+    }
+
+    #[test]
+    fn input_closure_receives_frames() {
+        let opened = record_opened();
+        let recorded = Arc::new(Mutex::new(Vec::new()));
+        let recorder = recorded.clone();
+        let _stream = AudioStreamBuilder::default()
+            .set_input()
+            .set_f32()
+            .set_mono()
+            .set_callback_fn(move |_stream, frames| {
+                recorder.lock().unwrap().extend_from_slice(frames);
+                DataCallbackResult::Continue
+            })
+            .open_stream()
+            .unwrap();
+
+        let samples = [0.25f32, -0.5, 0.75, -1.0];
+        let mut buffer: Vec<u8> = samples.iter().flat_map(|s| s.to_ne_bytes()).collect();
+        assert_eq!(
+            fake(&opened).render(&mut buffer),
+            DataCallbackResult::Continue
+        );
+        assert_eq!(*recorded.lock().unwrap(), samples);
+    }
+
+    #[test]
+    fn output_closure_renders_frames() {
+        let opened = record_opened();
+        let mut count = 0;
+        let _stream = AudioStreamBuilder::default()
+            .set_output()
+            .set_f32()
+            .set_stereo()
+            .set_callback_fn(move |_stream, frames| {
+                for frame in frames {
+                    count += 1;
+                    *frame = (count as f32, -count as f32);
+                }
+                DataCallbackResult::Continue
+            })
+            .open_stream()
+            .unwrap();
+
+        let mut buffer = [0; 8 * 3];
+        assert_eq!(
+            fake(&opened).render(&mut buffer),
+            DataCallbackResult::Continue
+        );
+        let samples: Vec<f32> = buffer
+            .chunks(4)
+            .map(|bytes| f32::from_ne_bytes(bytes.try_into().unwrap()))
+            .collect();
+        assert_eq!(samples, [1.0, -1.0, 2.0, -2.0, 3.0, -3.0]);
+    }
+
+    type Calls = Arc<Mutex<Vec<(&'static str, Error, StreamState)>>>;
+
+    fn record(calls: &Calls, name: &'static str) -> impl FnMut(&mut dyn AudioStreamSafe, Error) {
+        let calls = calls.clone();
+        move |stream, error| {
+            calls
+                .lock()
+                .unwrap()
+                .push((name, error, stream.get_state()))
+        }
+    }
+
+    #[test]
+    fn input_error_closures_are_called() {
+        let opened = record_opened();
+        let calls = Calls::default();
+        let (mut before_close, mut after_close) = (
+            record(&calls, "before close"),
+            record(&calls, "after close"),
+        );
+        let _stream = AudioStreamBuilder::default()
+            .set_input()
+            .set_f32()
+            .set_mono()
+            .set_callback_fn(|_stream, _frames| DataCallbackResult::Continue)
+            .on_error_before_close_fn(move |stream, error| before_close(stream, error))
+            .on_error_after_close_fn(move |stream, error| after_close(stream, error))
+            .open_stream()
+            .unwrap();
+
+        fake(&opened).fail(Error::Disconnected);
+        assert_eq!(
+            *calls.lock().unwrap(),
+            [
+                ("before close", Error::Disconnected, StreamState::Open),
+                ("after close", Error::Disconnected, StreamState::Closed),
+            ]
+        );
+    }
+
+    #[test]
+    fn output_error_closures_are_called() {
+        let opened = record_opened();
+        let calls = Calls::default();
+        let (mut before_close, mut after_close) = (
+            record(&calls, "before close"),
+            record(&calls, "after close"),
+        );
+        let _stream = AudioStreamBuilder::default()
+            .set_output()
+            .set_f32()
+            .set_mono()
+            .set_callback_fn(|_stream, _frames| DataCallbackResult::Continue)
+            .on_error_before_close_fn(move |stream, error| before_close(stream, error))
+            .on_error_after_close_fn(move |stream, error| after_close(stream, error))
+            .open_stream()
+            .unwrap();
+
+        fake(&opened).fail(Error::Timeout);
+        assert_eq!(
+            *calls.lock().unwrap(),
+            [
+                ("before close", Error::Timeout, StreamState::Open),
+                ("after close", Error::Timeout, StreamState::Closed),
+            ]
+        );
+    }
+
+    #[test]
+    fn error_closures_are_optional() {
+        let opened = record_opened();
+        let stream = AudioStreamBuilder::default()
+            .set_output()
+            .set_f32()
+            .set_mono()
+            .set_callback_fn(|_stream, _frames| DataCallbackResult::Continue)
+            .open_stream()
+            .unwrap();
+
+        fake(&opened).fail(Error::Disconnected);
+        assert_eq!(stream.get_state(), StreamState::Closed);
+    }
+}
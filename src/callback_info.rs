@@ -0,0 +1,262 @@
+use super::{AudioStreamSafe, Clock, NANOS_PER_SECOND};
+
+/**
+ * The timing and position of the buffer which is passed to the data callback
+ *
+ * It is computed once per callback before calling
+ * [`AudioOutputCallback::on_audio_ready_with_info`](crate::AudioOutputCallback::on_audio_ready_with_info)
+ * or [`AudioInputCallback::on_audio_ready_with_info`](crate::AudioInputCallback::on_audio_ready_with_info),
+ * when the callback [wants it](crate::AudioOutputCallback::wants_callback_info).
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CallbackInfo {
+    /**
+     * The position of the first frame of the buffer in the stream
+     *
+     * It is the number of frames written before the buffer for output streams
+     * and the number of frames read before the buffer for input streams.
+     */
+    pub frame_position: i64,
+
+    /**
+     * The estimated time in `CLOCK_MONOTONIC` nanoseconds when the first frame of the buffer
+     * will be presented (for output streams) or was captured (for input streams)
+     *
+     * It is `None` when the stream can't provide the timestamp yet, for example right after start,
+     * or when the time doesn't fit.
+     */
+    pub frame_time_nanos: Option<i64>,
+
+    /**
+     * The sample rate of the stream in Hz
+     */
+    pub sample_rate: i32,
+
+    /**
+     * The number of the callback since the stream was opened, starting from zero
+     */
+    pub callback_index: u64,
+
+    /**
+     * The number of underruns or overruns since the previous callback
+     *
+     * It is always zero when the xrun counts aren't supported by the stream.
+     */
+    pub xrun_delta: i32,
+}
+
+/**
+ * The state which is kept between callbacks to compute [`CallbackInfo`]
+ */
+#[derive(Default)]
+pub(crate) struct CallbackInfoTracker {
+    callback_index: u64,
+    xrun_count: i32,
+}
+
+impl CallbackInfoTracker {
+    pub(crate) fn next(
+        &mut self,
+        audio_stream: &mut dyn AudioStreamSafe,
+        frame_position: i64,
+    ) -> CallbackInfo {
+        let sample_rate = audio_stream.get_sample_rate();
+
+        let frame_time_nanos = audio_stream
+            .get_timestamp(Clock::Monotonic.clock_id())
+            .ok()
+            .filter(|_| sample_rate > 0)
+            .and_then(|timestamp| {
+                // The positions are arbitrary, so the time which doesn't fit is unknown
+                let frames = frame_position as i128 - timestamp.position as i128;
+                let nanos = timestamp.timestamp as i128
+                    + frames * NANOS_PER_SECOND as i128 / sample_rate as i128;
+                i64::try_from(nanos).ok()
+            });
+
+        let xrun_delta = match audio_stream.get_xrun_count() {
+            Ok(xrun_count) => {
+                let xrun_delta = xrun_count.wrapping_sub(self.xrun_count);
+                self.xrun_count = xrun_count;
+                xrun_delta
+            }
+            Err(_) => 0,
+        };
+
+        let callback_index = self.callback_index;
+        self.callback_index += 1;
+
+        CallbackInfo {
+            frame_position,
+            frame_time_nanos,
+            sample_rate,
+            callback_index,
+            xrun_delta,
+        }
+    }
+}
+
+#[cfg(all(test, not(target_os = "android")))]
+mod tests {
+    use super::*;
+    use crate::{
+        fake_stream::{on_open, sync_stream, FakeStream},
+        AudioFormat, AudioOutputCallback, AudioOutputStreamSafe, AudioStreamAsync,
+        AudioStreamBuilder, AudioStreamSync, DataCallbackResult, Direction, Mono, Output,
+    };
+    use oboe_sys as ffi;
+    use std::sync::{Arc, Mutex};
+
+    fn fake() -> (Arc<FakeStream>, AudioStreamSync<Output, (f32, Mono)>) {
+        let fake = FakeStream::new(Direction::Output, AudioFormat::F32, 1);
+        let stream = sync_stream(&fake);
+        (fake, stream)
+    }
+
+    fn set_timestamp(fake: &FakeStream, position: i64, timestamp: i64) {
+        fake.update(|fake| {
+            fake.timestamp = Some(ffi::oboe_FrameTimestamp {
+                position,
+                timestamp,
+            })
+        });
+    }
+
+    #[test]
+    fn frame_time_is_extrapolated_from_timestamp() {
+        let (fake, mut stream) = fake();
+        let mut tracker = CallbackInfoTracker::default();
+
+        let info = tracker.next(&mut stream, 0);
+        assert_eq!(info.frame_time_nanos, None);
+        assert_eq!(info.sample_rate, 48000);
+
+        set_timestamp(&fake, 48000, 5 * NANOS_PER_SECOND);
+        assert_eq!(
+            tracker.next(&mut stream, 96000).frame_time_nanos,
+            Some(6 * NANOS_PER_SECOND)
+        );
+        assert_eq!(
+            tracker.next(&mut stream, 24000).frame_time_nanos,
+            Some(4 * NANOS_PER_SECOND + NANOS_PER_SECOND / 2)
+        );
+    }
+
+    #[test]
+    fn frame_time_which_doesnt_fit_is_unknown() {
+        let (fake, mut stream) = fake();
+        let mut tracker = CallbackInfoTracker::default();
+
+        set_timestamp(&fake, i64::MIN, i64::MAX - 1);
+        assert_eq!(tracker.next(&mut stream, i64::MAX).frame_time_nanos, None);
+
+        set_timestamp(&fake, i64::MAX, 0);
+        assert_eq!(tracker.next(&mut stream, i64::MIN).frame_time_nanos, None);
+
+        fake.set_base(|base| base.mSampleRate = 0);
+        set_timestamp(&fake, 0, 0);
+        assert_eq!(tracker.next(&mut stream, 0).frame_time_nanos, None);
+    }
+
+    #[test]
+    fn index_and_xruns_are_counted_between_callbacks() {
+        let (fake, mut stream) = fake();
+        let mut tracker = CallbackInfoTracker::default();
+
+        let info = tracker.next(&mut stream, 10);
+        assert_eq!((info.frame_position, info.callback_index), (10, 0));
+        assert_eq!(info.xrun_delta, 0);
+
+        fake.update(|fake| fake.xrun_count = Some(3));
+        let info = tracker.next(&mut stream, 20);
+        assert_eq!((info.callback_index, info.xrun_delta), (1, 3));
+        assert_eq!(tracker.next(&mut stream, 30).xrun_delta, 0);
+
+        fake.update(|fake| fake.xrun_count = None);
+        assert_eq!(tracker.next(&mut stream, 40).xrun_delta, 0);
+    }
+
+    /// The callback which records the info which it gets
+    struct Recorder {
+        wants_info: bool,
+        infos: Arc<Mutex<Vec<Option<CallbackInfo>>>>,
+    }
+
+    impl AudioOutputCallback for Recorder {
+        type FrameType = (f32, Mono);
+
+        fn wants_callback_info(&self) -> bool {
+            self.wants_info
+        }
+
+        fn on_audio_ready(
+            &mut self,
+            _audio_stream: &mut dyn AudioOutputStreamSafe,
+            _audio_data: &mut [f32],
+        ) -> DataCallbackResult {
+            self.infos.lock().unwrap().push(None);
+            DataCallbackResult::Continue
+        }
+
+        fn on_audio_ready_with_info(
+            &mut self,
+            _audio_stream: &mut dyn AudioOutputStreamSafe,
+            info: &CallbackInfo,
+            _audio_data: &mut [f32],
+        ) -> DataCallbackResult {
+            self.infos.lock().unwrap().push(Some(*info));
+            DataCallbackResult::Continue
+        }
+    }
+
+    fn render_with(wants_info: bool) -> (Arc<FakeStream>, Vec<Option<CallbackInfo>>) {
+        let opened = Arc::new(Mutex::new(None));
+        let fakes = opened.clone();
+        on_open(move |fake| {
+            set_timestamp(fake, 0, NANOS_PER_SECOND);
+            fake.update(|fake| fake.frames_written = 480);
+            *fakes.lock().unwrap() = Some(fake.clone());
+            Ok(())
+        });
+
+        let infos = Arc::default();
+        let stream: AudioStreamAsync<Output, _> = AudioStreamBuilder::default()
+            .set_f32()
+            .set_mono()
+            .set_callback(Recorder {
+                wants_info,
+                infos: Arc::clone(&infos),
+            })
+            .open_stream()
+            .unwrap();
+
+        let fake = opened.lock().unwrap().take().unwrap();
+        fake.render(&mut [0; 4 * 16]);
+        fake.render(&mut [0; 4 * 16]);
+        drop(stream);
+
+        let infos = infos.lock().unwrap().clone();
+        (fake, infos)
+    }
+
+    #[test]
+    fn info_isnt_computed_unless_wanted() {
+        let (fake, infos) = render_with(false);
+        assert_eq!(infos, [None, None]);
+        assert!(!fake.lock().calls.contains(&"getTimestamp"));
+    }
+
+    #[test]
+    fn wanted_info_is_passed_to_callback() {
+        let (_fake, infos) = render_with(true);
+        let infos: Vec<_> = infos.into_iter().map(Option::unwrap).collect();
+        assert_eq!(infos.len(), 2);
+        assert_eq!(infos[0].frame_position, 480);
+        assert_eq!(
+            infos[0].frame_time_nanos,
+            Some(NANOS_PER_SECOND + NANOS_PER_SECOND / 100)
+        );
+        assert_eq!(infos[0].callback_index, 0);
+        assert_eq!(infos[1].callback_index, 1);
+    }
+}
@@ -1,7 +1,82 @@
 use num_derive::{FromPrimitive, ToPrimitive};
-use num_traits::FromPrimitive;
+use num_traits::{FromPrimitive, ToPrimitive};
 use oboe_sys as ffi;
-use std::{error, fmt, result};
+use std::{error, fmt, num::NonZeroI32, result};
+
+/**
+ * Defines an enum which mirrors the native one.
+ *
+ * Each known native value maps to an unit variant while any other value is kept
+ * in the `Unknown` variant, so conversion from native codes never fails.
+ */
+macro_rules! native_enum {
+    (
+        $(#[$meta:meta])*
+        pub enum $name:ident {
+            $(
+                $(#[$variant_meta:meta])*
+                $variant:ident = $value:path,
+            )*
+        }
+    ) => {
+        $(#[$meta])*
+        #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+        pub enum $name {
+            $(
+                $(#[$variant_meta])*
+                $variant,
+            )*
+
+            /**
+             * The value which isn't known by this version of bindings.
+             */
+            Unknown(i32),
+        }
+
+        impl From<i32> for $name {
+            fn from(value: i32) -> Self {
+                match value {
+                    $($value => Self::$variant,)*
+                    value => Self::Unknown(value),
+                }
+            }
+        }
+
+        impl From<$name> for i32 {
+            fn from(value: $name) -> Self {
+                match value {
+                    $($name::$variant => $value,)*
+                    $name::Unknown(value) => value,
+                }
+            }
+        }
+
+        #[cfg(test)]
+        impl $name {
+            const KNOWN: &'static [Self] = &[$(Self::$variant,)*];
+        }
+
+        impl FromPrimitive for $name {
+            fn from_i64(n: i64) -> Option<Self> {
+                i32::try_from(n).ok().map(Self::from)
+            }
+
+            fn from_u64(n: u64) -> Option<Self> {
+                i32::try_from(n).ok().map(Self::from)
+            }
+        }
+
+        impl ToPrimitive for $name {
+            fn to_i64(&self) -> Option<i64> {
+                Some(i32::from(*self).into())
+            }
+
+            fn to_u64(&self) -> Option<u64> {
+                u64::try_from(i32::from(*self)).ok()
+            }
+        }
+    };
+}
 
 /**
  * The number of nanoseconds in a microsecond. 1,000.
@@ -23,80 +98,84 @@ pub const MILLIS_PER_SECOND: i64 = 1000;
  */
 pub const NANOS_PER_SECOND: i64 = NANOS_PER_MILLISECOND * MILLIS_PER_SECOND;
 
-/**
- * The state of the audio stream.
- */
-#[derive(Debug, Clone, Copy, PartialEq, Eq, FromPrimitive, ToPrimitive)]
-#[repr(i32)]
-pub enum StreamState {
-    Uninitialized = ffi::oboe_StreamState_Uninitialized,
-    Unknown = ffi::oboe_StreamState_Unknown,
-    Open = ffi::oboe_StreamState_Open,
-    Starting = ffi::oboe_StreamState_Starting,
-    Started = ffi::oboe_StreamState_Started,
-    Pausing = ffi::oboe_StreamState_Pausing,
-    Paused = ffi::oboe_StreamState_Paused,
-    Flushing = ffi::oboe_StreamState_Flushing,
-    Flushed = ffi::oboe_StreamState_Flushed,
-    Stopping = ffi::oboe_StreamState_Stopping,
-    Stopped = ffi::oboe_StreamState_Stopped,
-    Closing = ffi::oboe_StreamState_Closing,
-    Closed = ffi::oboe_StreamState_Closed,
-    Disconnected = ffi::oboe_StreamState_Disconnected,
-}
-
-/**
- * The direction of the stream.
- */
-#[derive(Debug, Clone, Copy, PartialEq, Eq, FromPrimitive, ToPrimitive)]
-#[repr(i32)]
-pub enum Direction {
-    /**
-     * Used for playback.
-     */
-    Output = ffi::oboe_Direction_Output,
-
+native_enum! {
     /**
-     * Used for recording.
+     * The state of the audio stream.
+     *
+     * The native `Unknown` state is `Unknown(ffi::oboe_StreamState_Unknown)`.
      */
-    Input = ffi::oboe_Direction_Input,
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum StreamState {
+        Uninitialized = ffi::oboe_StreamState_Uninitialized,
+        Open = ffi::oboe_StreamState_Open,
+        Starting = ffi::oboe_StreamState_Starting,
+        Started = ffi::oboe_StreamState_Started,
+        Pausing = ffi::oboe_StreamState_Pausing,
+        Paused = ffi::oboe_StreamState_Paused,
+        Flushing = ffi::oboe_StreamState_Flushing,
+        Flushed = ffi::oboe_StreamState_Flushed,
+        Stopping = ffi::oboe_StreamState_Stopping,
+        Stopped = ffi::oboe_StreamState_Stopped,
+        Closing = ffi::oboe_StreamState_Closing,
+        Closed = ffi::oboe_StreamState_Closed,
+        Disconnected = ffi::oboe_StreamState_Disconnected,
+    }
 }
 
-/**
- * The format of audio samples.
- */
-#[derive(Debug, Clone, Copy, PartialEq, Eq, FromPrimitive, ToPrimitive)]
-#[repr(i32)]
-pub enum AudioFormat {
-    /**
-     * Invalid format.
-     */
-    Invalid = ffi::oboe_AudioFormat_Invalid,
-
-    /**
-     * Unspecified format. Format will be decided by Oboe.
-     */
-    Unspecified = ffi::oboe_AudioFormat_Unspecified,
-
-    /**
-     * Signed 16-bit integers.
-     */
-    I16 = ffi::oboe_AudioFormat_I16,
-
+native_enum! {
     /**
-     * Signed 24-bit integers.
+     * The direction of the stream.
      */
-    I24 = ffi::oboe_AudioFormat_I24,
-
-    /**
-     * Signed 32-bit integers.
-     */
-    I32 = ffi::oboe_AudioFormat_I32,
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum Direction {
+        /**
+         * Used for playback.
+         */
+        Output = ffi::oboe_Direction_Output,
+
+        /**
+         * Used for recording.
+         */
+        Input = ffi::oboe_Direction_Input,
+    }
+}
 
+native_enum! {
     /**
-     * Single precision floating points.
+     * The format of audio samples.
      */
-    F32 = ffi::oboe_AudioFormat_Float,
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum AudioFormat {
+        /**
+         * Invalid format.
+         */
+        Invalid = ffi::oboe_AudioFormat_Invalid,
+
+        /**
+         * Unspecified format. Format will be decided by Oboe.
+         */
+        Unspecified = ffi::oboe_AudioFormat_Unspecified,
+
+        /**
+         * Signed 16-bit integers.
+         */
+        I16 = ffi::oboe_AudioFormat_I16,
+
+        /**
+         * Signed 24-bit integers.
+         */
+        I24 = ffi::oboe_AudioFormat_I24,
+
+        /**
+         * Signed 32-bit integers.
+         */
+        I32 = ffi::oboe_AudioFormat_I32,
+
+        /**
+         * Single precision floating points.
+         */
+        F32 = ffi::oboe_AudioFormat_Float,
+    }
 }
 
 /**
@@ -130,7 +209,7 @@ pub(crate) fn wrap_status(result: i32) -> Status {
     if result == ffi::oboe_Result_OK {
         Ok(())
     } else {
-        Err(FromPrimitive::from_i32(result).unwrap())
+        Err(result.into())
     }
 }
 
@@ -138,33 +217,34 @@ pub(crate) fn wrap_result<T>(result: ffi::oboe_ResultWithValue<T>) -> Result<T>
     if result.mError == ffi::oboe_Result_OK {
         Ok(result.mValue)
     } else {
-        Err(FromPrimitive::from_i32(result.mError).unwrap())
+        Err(result.mError.into())
     }
 }
 
-/**
- * The error of an operation.
- */
-#[derive(Debug, Clone, Copy, PartialEq, Eq, FromPrimitive, ToPrimitive)]
-#[repr(i32)]
-pub enum Error {
-    Disconnected = ffi::oboe_Result_ErrorDisconnected,
-    IllegalArgument = ffi::oboe_Result_ErrorIllegalArgument,
-    Internal = ffi::oboe_Result_ErrorInternal,
-    InvalidState = ffi::oboe_Result_ErrorInvalidState,
-    InvalidHandle = ffi::oboe_Result_ErrorInvalidHandle,
-    Unimplemented = ffi::oboe_Result_ErrorUnimplemented,
-    Unavailable = ffi::oboe_Result_ErrorUnavailable,
-    NoFreeHandles = ffi::oboe_Result_ErrorNoFreeHandles,
-    NoMemory = ffi::oboe_Result_ErrorNoMemory,
-    Null = ffi::oboe_Result_ErrorNull,
-    Timeout = ffi::oboe_Result_ErrorTimeout,
-    WouldBlock = ffi::oboe_Result_ErrorWouldBlock,
-    InvalidFormat = ffi::oboe_Result_ErrorInvalidFormat,
-    OutOfRange = ffi::oboe_Result_ErrorOutOfRange,
-    NoService = ffi::oboe_Result_ErrorNoService,
-    InvalidRate = ffi::oboe_Result_ErrorInvalidRate,
-    Closed = ffi::oboe_Result_ErrorClosed,
+native_enum! {
+    /**
+     * The error of an operation.
+     */
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum Error {
+        Disconnected = ffi::oboe_Result_ErrorDisconnected,
+        IllegalArgument = ffi::oboe_Result_ErrorIllegalArgument,
+        Internal = ffi::oboe_Result_ErrorInternal,
+        InvalidState = ffi::oboe_Result_ErrorInvalidState,
+        InvalidHandle = ffi::oboe_Result_ErrorInvalidHandle,
+        Unimplemented = ffi::oboe_Result_ErrorUnimplemented,
+        Unavailable = ffi::oboe_Result_ErrorUnavailable,
+        NoFreeHandles = ffi::oboe_Result_ErrorNoFreeHandles,
+        NoMemory = ffi::oboe_Result_ErrorNoMemory,
+        Null = ffi::oboe_Result_ErrorNull,
+        Timeout = ffi::oboe_Result_ErrorTimeout,
+        WouldBlock = ffi::oboe_Result_ErrorWouldBlock,
+        InvalidFormat = ffi::oboe_Result_ErrorInvalidFormat,
+        OutOfRange = ffi::oboe_Result_ErrorOutOfRange,
+        NoService = ffi::oboe_Result_ErrorNoService,
+        InvalidRate = ffi::oboe_Result_ErrorInvalidRate,
+        Closed = ffi::oboe_Result_ErrorClosed,
+    }
 }
 
 impl error::Error for Error {}
@@ -174,313 +254,672 @@ impl fmt::Display for Error {
     }
 }
 
-/**
- * The sharing mode of the audio stream.
- */
-#[derive(Debug, Clone, Copy, PartialEq, Eq, FromPrimitive, ToPrimitive)]
-#[repr(i32)]
-pub enum SharingMode {
+native_enum! {
+    /**
+     * The sharing mode of the audio stream.
+     */
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum SharingMode {
+        /**
+         * This will be the only stream using a particular source or sink.
+         * This mode will provide the lowest possible latency.
+         * You should close EXCLUSIVE streams immediately when you are not using them.
+         *
+         * If you do not need the lowest possible latency then we recommend using Shared,
+         * which is the default.
+         */
+        Exclusive = ffi::oboe_SharingMode_Exclusive,
+
+        /**
+         * Multiple applications can share the same device.
+         * The data from output streams will be mixed by the audio service.
+         * The data for input streams will be distributed by the audio service.
+         *
+         * This will have higher latency than the EXCLUSIVE mode.
+         */
+        Shared = ffi::oboe_SharingMode_Shared,
+    }
+}
+
+native_enum! {
+    /**
+     * The performance mode of the audio stream.
+     */
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum PerformanceMode {
+        /**
+         * No particular performance needs. Default.
+         */
+        None = ffi::oboe_PerformanceMode_None,
+
+        /**
+         * Extending battery life is most important.
+         */
+        PowerSaving = ffi::oboe_PerformanceMode_PowerSaving,
+
+        /**
+         * Reducing latency is most important.
+         */
+        LowLatency = ffi::oboe_PerformanceMode_LowLatency,
+    }
+}
+
+native_enum! {
     /**
-     * This will be the only stream using a particular source or sink.
-     * This mode will provide the lowest possible latency.
-     * You should close EXCLUSIVE streams immediately when you are not using them.
+     * The underlying audio API used by the audio stream.
+     */
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum AudioApi {
+        /**
+         * Try to use AAudio. If not available then use OpenSL ES.
+         */
+        Unspecified = ffi::oboe_AudioApi_Unspecified,
+
+        /**
+         * Use OpenSL ES.
+         */
+        OpenSLES = ffi::oboe_AudioApi_OpenSLES,
+
+        /**
+         * Try to use AAudio. Fail if unavailable.
+         */
+        AAudio = ffi::oboe_AudioApi_AAudio,
+    }
+}
+
+native_enum! {
+    /**
+     * Specifies the quality of the sample rate conversion performed by Oboe.
+     * Higher quality will require more CPU load.
+     * Higher quality conversion will probably be implemented using a sinc based resampler.
+     */
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum SampleRateConversionQuality {
+        /**
+         * No conversion by Oboe. Underlying APIs may still do conversion.
+         */
+        None = ffi::oboe_SampleRateConversionQuality_None,
+
+        /**
+         * Fastest conversion but may not sound great.
+         * This may be implemented using bilinear interpolation.
+         */
+        Fastest = ffi::oboe_SampleRateConversionQuality_Fastest,
+        Low = ffi::oboe_SampleRateConversionQuality_Low,
+        Medium = ffi::oboe_SampleRateConversionQuality_Medium,
+        High = ffi::oboe_SampleRateConversionQuality_High,
+
+        /**
+         * Highest quality conversion, which may be expensive in terms of CPU.
+         */
+        Best = ffi::oboe_SampleRateConversionQuality_Best,
+    }
+}
+
+native_enum! {
+    /**
+     * The Usage attribute expresses *why* you are playing a sound, what is this sound used for.
+     * This information is used by certain platforms or routing policies
+     * to make more refined volume or routing decisions.
      *
-     * If you do not need the lowest possible latency then we recommend using Shared,
-     * which is the default.
+     * Note that these match the equivalent values in AudioAttributes in the Android Java API.
+     *
+     * This attribute only has an effect on Android API 28+.
      */
-    Exclusive = ffi::oboe_SharingMode_Exclusive,
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum Usage {
+        /**
+         * Use this for streaming media, music performance, video, podcasts, etcetera.
+         */
+        Media = ffi::oboe_Usage_Media,
+
+        /**
+         * Use this for voice over IP, telephony, etcetera.
+         */
+        VoiceCommunication = ffi::oboe_Usage_VoiceCommunication,
+
+        /**
+         * Use this for sounds associated with telephony such as busy tones, DTMF, etcetera.
+         */
+        VoiceCommunicationSignalling = ffi::oboe_Usage_VoiceCommunicationSignalling,
+
+        /**
+         * Use this to demand the users attention.
+         */
+        Alarm = ffi::oboe_Usage_Alarm,
+
+        /**
+         * Use this for notifying the user when a message has arrived or some
+         * other background event has occured.
+         */
+        Notification = ffi::oboe_Usage_Notification,
+
+        /**
+         * Use this when the phone rings.
+         */
+        NotificationRingtone = ffi::oboe_Usage_NotificationRingtone,
+
+        /**
+         * Use this to attract the users attention when, for example, the battery is low.
+         */
+        NotificationEvent = ffi::oboe_Usage_NotificationEvent,
+
+        /**
+         * Use this for screen readers, etcetera.
+         */
+        AssistanceAccessibility = ffi::oboe_Usage_AssistanceAccessibility,
+
+        /**
+         * Use this for driving or navigation directions.
+         */
+        AssistanceNavigationGuidance = ffi::oboe_Usage_AssistanceNavigationGuidance,
+
+        /**
+         * Use this for user interface sounds, beeps, etcetera.
+         */
+        AssistanceSonification = ffi::oboe_Usage_AssistanceSonification,
+
+        /**
+         * Use this for game audio and sound effects.
+         */
+        Game = ffi::oboe_Usage_Game,
+
+        /**
+         * Use this for audio responses to user queries, audio instructions or help utterances.
+         */
+        Assistant = ffi::oboe_Usage_Assistant,
+    }
+}
+
+native_enum! {
+    /**
+     * The ContentType attribute describes *what* you are playing.
+     * It expresses the general category of the content. This information is optional.
+     * But in case it is known (for instance {@link Movie} for a
+     * movie streaming service or {@link Speech} for
+     * an audio book application) this information might be used by the audio framework to
+     * enforce audio focus.
+     *
+     * Note that these match the equivalent values in AudioAttributes in the Android Java API.
+     *
+     * This attribute only has an effect on Android API 28+.
+     */
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum ContentType {
+        /**
+         * Use this for spoken voice, audio books, etcetera.
+         */
+        Speech = ffi::oboe_ContentType_Speech,
+
+        /**
+         * Use this for pre-recorded or live music.
+         */
+        Music = ffi::oboe_ContentType_Music,
+
+        /**
+         * Use this for a movie or video soundtrack.
+         */
+        Movie = ffi::oboe_ContentType_Movie,
+
+        /**
+         * Use this for sound is designed to accompany a user action,
+         * such as a click or beep sound made when the user presses a button.
+         */
+        Sonification = ffi::oboe_ContentType_Sonification,
+    }
+}
 
+native_enum! {
     /**
-     * Multiple applications can share the same device.
-     * The data from output streams will be mixed by the audio service.
-     * The data for input streams will be distributed by the audio service.
+     * Defines the audio source.
+     * An audio source defines both a default physical source of audio signal, and a recording
+     * configuration.
+     *
+     * Note that these match the equivalent values in MediaRecorder.AudioSource in the Android Java API.
      *
-     * This will have higher latency than the EXCLUSIVE mode.
+     * This attribute only has an effect on Android API 28+.
      */
-    Shared = ffi::oboe_SharingMode_Shared,
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum InputPreset {
+        /**
+         * Use this preset when other presets do not apply.
+         */
+        Generic = ffi::oboe_InputPreset_Generic,
+
+        /**
+         * Use this preset when recording video.
+         */
+        Camcorder = ffi::oboe_InputPreset_Camcorder,
+
+        /**
+         * Use this preset when doing speech recognition.
+         */
+        VoiceRecognition = ffi::oboe_InputPreset_VoiceRecognition,
+
+        /**
+         * Use this preset when doing telephony or voice messaging.
+         */
+        VoiceCommunication = ffi::oboe_InputPreset_VoiceCommunication,
+
+        /**
+         * Use this preset to obtain an input with no effects.
+         * Note that this input will not have automatic gain control
+         * so the recorded volume may be very low.
+         */
+        Unprocessed = ffi::oboe_InputPreset_Unprocessed,
+
+        /**
+         * Use this preset for capturing audio meant to be processed in real time
+         * and played back for live performance (e.g karaoke).
+         * The capture path will minimize latency and coupling with playback path.
+         */
+        VoicePerformance = ffi::oboe_InputPreset_VoicePerformance,
+    }
 }
 
 /**
- * The performance mode of the audio stream.
+ * This attribute can be used to allocate a session ID to the audio stream.
+ *
+ * This attribute only has an effect on Android API 28+.
  */
-#[derive(Debug, Clone, Copy, PartialEq, Eq, FromPrimitive, ToPrimitive)]
-#[repr(i32)]
-pub enum PerformanceMode {
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum SessionId {
     /**
-     * No particular performance needs. Default.
+     * Do not allocate a session ID.
+     * Effects cannot be used with this stream.
+     * Default.
      */
-    None = ffi::oboe_PerformanceMode_None,
+    #[default]
+    None,
 
     /**
-     * Extending battery life is most important.
+     * Allocate a session ID that can be used to attach and control
+     * effects using the Java AudioEffects API.
+     * Note that the use of this flag may result in higher latency.
+     *
+     * Note that this matches the value of `AudioManager.AUDIO_SESSION_ID_GENERATE`.
+     */
+    Allocate,
+
+    /**
+     * The session ID which was allocated for an opened stream.
+     *
+     * It can be passed to the Java AudioEffects API or used to open a paired stream
+     * in the same session.
      */
-    PowerSaving = ffi::oboe_PerformanceMode_PowerSaving,
+    Id(NonZeroI32),
+}
 
+impl SessionId {
     /**
-     * Reducing latency is most important.
+     * Get the actual session ID if any
      */
-    LowLatency = ffi::oboe_PerformanceMode_LowLatency,
+    pub fn id(&self) -> Option<NonZeroI32> {
+        match self {
+            SessionId::Id(id) => Some(*id),
+            _ => None,
+        }
+    }
+}
+
+impl From<i32> for SessionId {
+    fn from(value: i32) -> Self {
+        match value {
+            ffi::oboe_SessionId_None => SessionId::None,
+            id => NonZeroI32::new(id).map_or(SessionId::Allocate, SessionId::Id),
+        }
+    }
+}
+
+impl From<SessionId> for i32 {
+    fn from(value: SessionId) -> Self {
+        match value {
+            SessionId::None => ffi::oboe_SessionId_None,
+            SessionId::Allocate => ffi::oboe_SessionId_Allocate,
+            SessionId::Id(id) => id.get(),
+        }
+    }
 }
 
 /**
- * The underlying audio API used by the audio stream.
+ * The channel count of the audio stream.
+ * Use of this enum is convenient to avoid "magic"
+ * numbers when specifying the channel count.
+ *
+ * For example, you can write
+ * `builder.set_channel_count(ChannelCount::Stereo)`
+ * rather than `builder.set_channel_count(2).
  */
-#[derive(Debug, Clone, Copy, PartialEq, Eq, FromPrimitive, ToPrimitive)]
-#[repr(i32)]
-pub enum AudioApi {
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ChannelCount {
     /**
-     * Try to use AAudio. If not available then use OpenSL ES.
+     * Audio channel count definition, use Mono or Stereo
      */
-    Unspecified = ffi::oboe_AudioApi_Unspecified,
+    Unspecified,
 
     /**
-     * Use OpenSL ES.
+     * Use this for mono audio.
      */
-    OpenSLES = ffi::oboe_AudioApi_OpenSLES,
+    Mono,
 
     /**
-     * Try to use AAudio. Fail if unavailable.
+     * Use this for stereo audio.
      */
-    AAudio = ffi::oboe_AudioApi_AAudio,
-}
+    Stereo,
 
-/**
- * Specifies the quality of the sample rate conversion performed by Oboe.
- * Higher quality will require more CPU load.
- * Higher quality conversion will probably be implemented using a sinc based resampler.
- */
-#[derive(Debug, Clone, Copy, PartialEq, Eq, FromPrimitive, ToPrimitive)]
-#[repr(i32)]
-pub enum SampleRateConversionQuality {
     /**
-     * No conversion by Oboe. Underlying APIs may still do conversion.
+     * Use this for multichannel audio (4, 6 or 8 channels for example).
      */
-    None,
+    Other(i32),
+}
 
+impl ChannelCount {
     /**
-     * Fastest conversion but may not sound great.
-     * This may be implemented using bilinear interpolation.
+     * Get channel count for the number of channels
      */
-    Fastest,
-    Low,
-    Medium,
-    High,
+    pub const fn from_count(count: i32) -> Self {
+        match count {
+            ffi::oboe_ChannelCount_Unspecified => ChannelCount::Unspecified,
+            ffi::oboe_ChannelCount_Mono => ChannelCount::Mono,
+            ffi::oboe_ChannelCount_Stereo => ChannelCount::Stereo,
+            count => ChannelCount::Other(count),
+        }
+    }
 
     /**
-     * Highest quality conversion, which may be expensive in terms of CPU.
+     * Get the number of channels
      */
-    Best,
+    pub const fn count(self) -> i32 {
+        match self {
+            ChannelCount::Unspecified => ffi::oboe_ChannelCount_Unspecified,
+            ChannelCount::Mono => ffi::oboe_ChannelCount_Mono,
+            ChannelCount::Stereo => ffi::oboe_ChannelCount_Stereo,
+            ChannelCount::Other(count) => count,
+        }
+    }
+}
+
+impl From<i32> for ChannelCount {
+    fn from(count: i32) -> Self {
+        Self::from_count(count)
+    }
+}
+
+impl From<ChannelCount> for i32 {
+    fn from(channel_count: ChannelCount) -> Self {
+        channel_count.count()
+    }
 }
 
 /**
- * The Usage attribute expresses *why* you are playing a sound, what is this sound used for.
- * This information is used by certain platforms or routing policies
- * to make more refined volume or routing decisions.
+ * The channel mask of the audio stream.
  *
- * Note that these match the equivalent values in AudioAttributes in the Android Java API.
+ * The position masks describe the speaker layout,
+ * so the number of channels is the number of speaker positions in layout.
+ * The index masks simply select the channels by its indexes.
  *
- * This attribute only has an effect on Android API 28+.
+ * Note that the channel masks are different than those defined in AudioFormat.java.
+ * If you get a channel mask from Java API the conversion should be done by you.
+ *
+ * This attribute only has an effect on Android API 32+.
  */
-#[derive(Debug, Clone, Copy, PartialEq, Eq, FromPrimitive, ToPrimitive)]
-#[repr(i32)]
-pub enum Usage {
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ChannelMask {
     /**
-     * Use this for streaming media, music performance, video, podcasts, etcetera.
+     * The channel mask isn't specified. Default.
      */
-    Media = ffi::oboe_Usage_Media,
+    #[default]
+    Unspecified,
 
     /**
-     * Use this for voice over IP, telephony, etcetera.
+     * Supported for Input and Output
      */
-    VoiceCommunication = ffi::oboe_Usage_VoiceCommunication,
+    Mono,
 
     /**
-     * Use this for sounds associated with telephony such as busy tones, DTMF, etcetera.
+     * Supported for Input and Output
      */
-    VoiceCommunicationSignalling = ffi::oboe_Usage_VoiceCommunicationSignalling,
+    Stereo,
 
     /**
-     * Use this to demand the users attention.
+     * Supported for only Output
      */
-    Alarm = ffi::oboe_Usage_Alarm,
+    CM2Point1,
 
     /**
-     * Use this for notifying the user when a message has arrived or some
-     * other background event has occured.
+     * Supported for only Output
      */
-    Notification = ffi::oboe_Usage_Notification,
+    Tri,
 
     /**
-     * Use this when the phone rings.
+     * Supported for only Output
      */
-    NotificationRingtone = ffi::oboe_Usage_NotificationRingtone,
+    TriBack,
 
     /**
-     * Use this to attract the users attention when, for example, the battery is low.
+     * Supported for only Output
      */
-    NotificationEvent = ffi::oboe_Usage_NotificationEvent,
+    CM3Point1,
 
     /**
-     * Use this for screen readers, etcetera.
+     * Supported for Input and Output
      */
-    AssistanceAccessibility = ffi::oboe_Usage_AssistanceAccessibility,
+    CM2Point0Point2,
 
     /**
-     * Use this for driving or navigation directions.
+     * Supported for Input and Output
      */
-    AssistanceNavigationGuidance = ffi::oboe_Usage_AssistanceNavigationGuidance,
+    CM2Point1Point2,
 
     /**
-     * Use this for user interface sounds, beeps, etcetera.
+     * Supported for Input and Output
      */
-    AssistanceSonification = ffi::oboe_Usage_AssistanceSonification,
+    CM3Point0Point2,
 
     /**
-     * Use this for game audio and sound effects.
+     * Supported for Input and Output
      */
-    Game = ffi::oboe_Usage_Game,
+    CM3Point1Point2,
 
     /**
-     * Use this for audio responses to user queries, audio instructions or help utterances.
+     * Supported for only Output
      */
-    Assistant = ffi::oboe_Usage_Assistant,
-}
+    Quad,
 
-/**
- * The ContentType attribute describes *what* you are playing.
- * It expresses the general category of the content. This information is optional.
- * But in case it is known (for instance {@link Movie} for a
- * movie streaming service or {@link Speech} for
- * an audio book application) this information might be used by the audio framework to
- * enforce audio focus.
- *
- * Note that these match the equivalent values in AudioAttributes in the Android Java API.
- *
- * This attribute only has an effect on Android API 28+.
- */
-#[derive(Debug, Clone, Copy, PartialEq, Eq, FromPrimitive, ToPrimitive)]
-#[repr(i32)]
-pub enum ContentType {
     /**
-     * Use this for spoken voice, audio books, etcetera.
+     * Supported for only Output
      */
-    Speech = ffi::oboe_ContentType_Speech,
+    QuadSide,
 
     /**
-     * Use this for pre-recorded or live music.
+     * Supported for only Output
      */
-    Music = ffi::oboe_ContentType_Music,
+    Surround,
 
     /**
-     * Use this for a movie or video soundtrack.
+     * Supported for only Output
      */
-    Movie = ffi::oboe_ContentType_Movie,
+    Penta,
 
     /**
-     * Use this for sound is designed to accompany a user action,
-     * such as a click or beep sound made when the user presses a button.
+     * Supported for Input and Output. aka 5Point1Back
      */
-    Sonification = ffi::oboe_ContentType_Sonification,
-}
+    CM5Point1,
 
-/**
- * Defines the audio source.
- * An audio source defines both a default physical source of audio signal, and a recording
- * configuration.
- *
- * Note that these match the equivalent values in MediaRecorder.AudioSource in the Android Java API.
- *
- * This attribute only has an effect on Android API 28+.
- */
-#[derive(Debug, Clone, Copy, PartialEq, Eq, FromPrimitive, ToPrimitive)]
-#[repr(i32)]
-pub enum InputPreset {
     /**
-     * Use this preset when other presets do not apply.
+     * Supported for only Output
      */
-    Generic = ffi::oboe_InputPreset_Generic,
+    CM5Point1Side,
 
     /**
-     * Use this preset when recording video.
+     * Supported for only Output
      */
-    Camcorder = ffi::oboe_InputPreset_Camcorder,
+    CM6Point1,
 
     /**
-     * Use this preset when doing speech recognition.
+     * Supported for only Output
      */
-    VoiceRecognition = ffi::oboe_InputPreset_VoiceRecognition,
+    CM7Point1,
 
     /**
-     * Use this preset when doing telephony or voice messaging.
+     * Supported for only Output
      */
-    VoiceCommunication = ffi::oboe_InputPreset_VoiceCommunication,
+    CM5Point1Point2,
 
     /**
-     * Use this preset to obtain an input with no effects.
-     * Note that this input will not have automatic gain control
-     * so the recorded volume may be very low.
+     * Supported for only Output
      */
-    Unprocessed = ffi::oboe_InputPreset_Unprocessed,
+    CM5Point1Point4,
 
     /**
-     * Use this preset for capturing audio meant to be processed in real time
-     * and played back for live performance (e.g karaoke).
-     * The capture path will minimize latency and coupling with playback path.
+     * Supported for only Output
      */
-    VoicePerformance = ffi::oboe_InputPreset_VoicePerformance,
-}
+    CM7Point1Point2,
 
-/**
- * This attribute can be used to allocate a session ID to the audio stream.
- *
- * This attribute only has an effect on Android API 28+.
- */
-#[derive(Debug, Clone, Copy, PartialEq, Eq, FromPrimitive, ToPrimitive)]
-#[repr(i32)]
-pub enum SessionId {
     /**
-     * Do not allocate a session ID.
-     * Effects cannot be used with this stream.
-     * Default.
+     * Supported for only Output
      */
-    None = ffi::oboe_SessionId_None,
+    CM7Point1Point4,
 
     /**
-     * Allocate a session ID that can be used to attach and control
-     * effects using the Java AudioEffects API.
-     * Note that the use of this flag may result in higher latency.
-     *
-     * Note that this matches the value of `AudioManager.AUDIO_SESSION_ID_GENERATE`.
+     * Supported for only Output
      */
-    Allocate = ffi::oboe_SessionId_Allocate,
-}
+    CM9Point1Point4,
 
-/**
- * The channel count of the audio stream.
- * Use of this enum is convenient to avoid "magic"
- * numbers when specifying the channel count.
- *
- * For example, you can write
- * `builder.set_channel_count(ChannelCount::Stereo)`
- * rather than `builder.set_channel_count(2).
- */
-#[derive(Debug, Clone, Copy, PartialEq, Eq, FromPrimitive, ToPrimitive)]
-#[repr(i32)]
-pub enum ChannelCount {
     /**
-     * Audio channel count definition, use Mono or Stereo
+     * Supported for only Output
      */
-    Unspecified = ffi::oboe_ChannelCount_Unspecified,
+    CM9Point1Point6,
 
     /**
-     * Use this for mono audio.
+     * Supported for only Input
      */
-    Mono = ffi::oboe_ChannelCount_Mono,
+    FrontBack,
 
     /**
-     * Use this for stereo audio.
+     * The other combination of channel positions (`FrontLeft`, `FrontRight` and so on)
+     * using the values of `oboe_ChannelMask_*` constants.
+     */
+    Position(u32),
+
+    /**
+     * The channels selected by indexes, where bit `N` selects the channel `N`.
+     *
+     * For example, `Index(0b1111)` selects the first four channels of an USB interface.
+     */
+    Index(u32),
+}
+
+/**
+ * The bit which marks the channel index masks
+ */
+const CHANNEL_INDEX_MASK_BIT: u32 = 1 << 31;
+
+const CHANNEL_MASKS: [(ChannelMask, ffi::oboe_ChannelMask); 25] = [
+    (ChannelMask::Mono, ffi::oboe_ChannelMask_Mono),
+    (ChannelMask::Stereo, ffi::oboe_ChannelMask_Stereo),
+    (ChannelMask::CM2Point1, ffi::oboe_ChannelMask_CM2Point1),
+    (ChannelMask::Tri, ffi::oboe_ChannelMask_Tri),
+    (ChannelMask::TriBack, ffi::oboe_ChannelMask_TriBack),
+    (ChannelMask::CM3Point1, ffi::oboe_ChannelMask_CM3Point1),
+    (
+        ChannelMask::CM2Point0Point2,
+        ffi::oboe_ChannelMask_CM2Point0Point2,
+    ),
+    (
+        ChannelMask::CM2Point1Point2,
+        ffi::oboe_ChannelMask_CM2Point1Point2,
+    ),
+    (
+        ChannelMask::CM3Point0Point2,
+        ffi::oboe_ChannelMask_CM3Point0Point2,
+    ),
+    (
+        ChannelMask::CM3Point1Point2,
+        ffi::oboe_ChannelMask_CM3Point1Point2,
+    ),
+    (ChannelMask::Quad, ffi::oboe_ChannelMask_Quad),
+    (ChannelMask::QuadSide, ffi::oboe_ChannelMask_QuadSide),
+    (ChannelMask::Surround, ffi::oboe_ChannelMask_Surround),
+    (ChannelMask::Penta, ffi::oboe_ChannelMask_Penta),
+    (ChannelMask::CM5Point1, ffi::oboe_ChannelMask_CM5Point1),
+    (
+        ChannelMask::CM5Point1Side,
+        ffi::oboe_ChannelMask_CM5Point1Side,
+    ),
+    (ChannelMask::CM6Point1, ffi::oboe_ChannelMask_CM6Point1),
+    (ChannelMask::CM7Point1, ffi::oboe_ChannelMask_CM7Point1),
+    (
+        ChannelMask::CM5Point1Point2,
+        ffi::oboe_ChannelMask_CM5Point1Point2,
+    ),
+    (
+        ChannelMask::CM5Point1Point4,
+        ffi::oboe_ChannelMask_CM5Point1Point4,
+    ),
+    (
+        ChannelMask::CM7Point1Point2,
+        ffi::oboe_ChannelMask_CM7Point1Point2,
+    ),
+    (
+        ChannelMask::CM7Point1Point4,
+        ffi::oboe_ChannelMask_CM7Point1Point4,
+    ),
+    (
+        ChannelMask::CM9Point1Point4,
+        ffi::oboe_ChannelMask_CM9Point1Point4,
+    ),
+    (
+        ChannelMask::CM9Point1Point6,
+        ffi::oboe_ChannelMask_CM9Point1Point6,
+    ),
+    (ChannelMask::FrontBack, ffi::oboe_ChannelMask_FrontBack),
+];
+
+impl ChannelMask {
+    /**
+     * Get the number of channels described by mask
      */
-    Stereo = ffi::oboe_ChannelCount_Stereo,
+    pub fn channel_count(&self) -> ChannelCount {
+        let bits = match *self {
+            ChannelMask::Index(bits) => bits,
+            mask => u32::from(mask),
+        };
+        ChannelCount::from_count(bits.count_ones() as i32)
+    }
+}
+
+impl From<u32> for ChannelMask {
+    fn from(value: u32) -> Self {
+        if value == ffi::oboe_ChannelMask_Unspecified {
+            ChannelMask::Unspecified
+        } else if value & CHANNEL_INDEX_MASK_BIT != 0 {
+            ChannelMask::Index(value & !CHANNEL_INDEX_MASK_BIT)
+        } else {
+            CHANNEL_MASKS
+                .iter()
+                .find(|(_, bits)| *bits == value)
+                .map_or(ChannelMask::Position(value), |(mask, _)| *mask)
+        }
+    }
+}
+
+impl From<ChannelMask> for u32 {
+    fn from(mask: ChannelMask) -> Self {
+        match mask {
+            ChannelMask::Unspecified => ffi::oboe_ChannelMask_Unspecified,
+            ChannelMask::Position(bits) => bits,
+            ChannelMask::Index(bits) => bits | CHANNEL_INDEX_MASK_BIT,
+            mask => CHANNEL_MASKS
+                .iter()
+                .find(|(known, _)| *known == mask)
+                .map(|(_, bits)| *bits)
+                .unwrap_or_default(),
+        }
+    }
 }
 
 /**
@@ -563,3 +1002,116 @@ pub struct FrameTimestamp {
      */
     pub timestamp: i64,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EDGES: [i32; 7] = [i32::MIN, i32::MIN + 1, -1, 0, 1, i32::MAX - 1, i32::MAX];
+
+    macro_rules! assert_round_trip {
+        ($($name:ident),*) => {
+            $(
+                for value in EDGES {
+                    assert_eq!(i32::from($name::from(value)), value, stringify!($name));
+                    assert_eq!($name::from_i32(value).and_then(|v| v.to_i32()), Some(value));
+                }
+                for known in $name::KNOWN {
+                    let value = i32::from(*known);
+                    assert_eq!($name::from(value), *known, stringify!($name));
+                    assert_ne!($name::from(value), $name::Unknown(value), stringify!($name));
+                }
+                assert_eq!($name::from_i64(i64::MAX), None);
+                assert_eq!($name::from_u64(u64::MAX), None);
+            )*
+        };
+    }
+
+    #[test]
+    fn native_enums_round_trip() {
+        assert_round_trip!(
+            StreamState,
+            Direction,
+            AudioFormat,
+            Error,
+            SharingMode,
+            PerformanceMode,
+            AudioApi,
+            SampleRateConversionQuality,
+            Usage,
+            ContentType,
+            InputPreset
+        );
+    }
+
+    #[test]
+    fn unknown_values_are_kept() {
+        assert_eq!(
+            StreamState::from(ffi::oboe_StreamState_Unknown),
+            StreamState::Unknown(ffi::oboe_StreamState_Unknown)
+        );
+        assert_eq!(StreamState::from(i32::MAX), StreamState::Unknown(i32::MAX));
+        assert_eq!(Error::from(i32::MIN), Error::Unknown(i32::MIN));
+        assert_eq!(Usage::from(-1), Usage::Unknown(-1));
+    }
+
+    #[test]
+    fn native_results_are_wrapped() {
+        assert_eq!(wrap_status(ffi::oboe_Result_OK), Ok(()));
+        assert_eq!(
+            wrap_status(ffi::oboe_Result_ErrorTimeout),
+            Err(Error::Timeout)
+        );
+        for value in EDGES
+            .into_iter()
+            .filter(|value| *value != ffi::oboe_Result_OK)
+        {
+            assert_eq!(i32::from(wrap_status(value).unwrap_err()), value);
+        }
+    }
+
+    #[test]
+    fn session_id_round_trip() {
+        for value in EDGES {
+            assert_eq!(i32::from(SessionId::from(value)), value);
+        }
+        assert_eq!(SessionId::from(ffi::oboe_SessionId_None), SessionId::None);
+        assert_eq!(
+            SessionId::from(ffi::oboe_SessionId_Allocate),
+            SessionId::Allocate
+        );
+        assert_eq!(SessionId::from(42).id().map(NonZeroI32::get), Some(42));
+    }
+
+    #[test]
+    fn channel_count_round_trip() {
+        for value in EDGES {
+            assert_eq!(ChannelCount::from(value).count(), value);
+        }
+        assert_eq!(ChannelCount::from(2), ChannelCount::Stereo);
+        assert_eq!(ChannelCount::from(6), ChannelCount::Other(6));
+    }
+
+    #[test]
+    fn channel_mask_round_trip() {
+        for value in [
+            0,
+            1,
+            3,
+            u32::MAX,
+            CHANNEL_INDEX_MASK_BIT,
+            CHANNEL_INDEX_MASK_BIT | 3,
+        ] {
+            assert_eq!(u32::from(ChannelMask::from(value)), value);
+        }
+        for (mask, bits) in CHANNEL_MASKS {
+            assert_eq!(ChannelMask::from(bits), mask);
+            assert_eq!(u32::from(mask), bits);
+        }
+        assert_eq!(ChannelMask::Stereo.channel_count(), ChannelCount::Stereo);
+        assert_eq!(
+            ChannelMask::Index(0b1111).channel_count(),
+            ChannelCount::Other(4)
+        );
+    }
+}
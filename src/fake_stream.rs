@@ -0,0 +1,947 @@
+//! The fake native stream for host tests
+//!
+//! It exports the symbols of the native shim functions which the safe wrappers call,
+//! so the wrappers can be driven without Oboe. The real library provides the symbols
+//! on Android, so the fake is built for the other targets only.
+
+// Each test uses only the parts of the fake which it needs
+#![allow(dead_code)]
+
+use oboe_sys as ffi;
+use std::{
+    cell::{RefCell, UnsafeCell},
+    collections::VecDeque,
+    ffi::c_void,
+    mem::MaybeUninit,
+    ptr::{copy_nonoverlapping, null_mut},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Condvar, Mutex, MutexGuard,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use super::{
+    AudioApi, AudioFormat, AudioStreamAsync, AudioStreamConfig, AudioStreamHandle, AudioStreamRef,
+    AudioStreamSync, CallbackPanic, CallbackSlot, DataCallbackResult, Direction, Error,
+    PanicPolicy, Result, StreamState,
+};
+
+/**
+ * The fake stream object which the native stream pointer points to
+ */
+#[repr(C)]
+pub(crate) struct FakeStream {
+    /// The opaque native object must be the first, so the pointers to it and to the fake are same
+    raw: UnsafeCell<ffi::oboe_AudioStream>,
+    base: UnsafeCell<ffi::oboe_AudioStreamBase>,
+    state: Mutex<FakeState>,
+    changed: Condvar,
+    active: AtomicUsize,
+    max_active: AtomicUsize,
+    data_callback: Option<Arc<FakeCallback>>,
+    error_callback: Option<Arc<FakeCallback>>,
+}
+
+// SAFETY: The tests use the base parameters from single thread only, the rest is locked.
+unsafe impl Send for FakeStream {}
+unsafe impl Sync for FakeStream {}
+
+/**
+ * The state of the fake stream which the tests can inspect and script
+ */
+pub(crate) struct FakeState {
+    pub state: StreamState,
+    pub xrun_count: Option<i32>,
+    pub frames_per_burst: i32,
+    pub frames_written: i64,
+    pub frames_read: i64,
+    pub timestamp: Option<ffi::oboe_FrameTimestamp>,
+    /// The frames which are queued for output or captured for input
+    pub available: i32,
+    /// The bytes which were written
+    pub written: Vec<u8>,
+    /// The bytes which can be read
+    pub captured: VecDeque<u8>,
+    /// The results of the next reads or writes, the count limits the transferred frames
+    pub script: VecDeque<Result<i32>>,
+    /// The time which every call takes
+    pub delay: Duration,
+    /// The names of the called functions
+    pub calls: Vec<&'static str>,
+}
+
+impl FakeStream {
+    pub(crate) fn new(direction: Direction, format: AudioFormat, channels: i32) -> Arc<Self> {
+        // SAFETY: The native objects are plain data, the fake never calls its virtual methods.
+        let mut base: ffi::oboe_AudioStreamBase = unsafe { MaybeUninit::zeroed().assume_init() };
+        base.mDirection = direction.into();
+        base.mFormat = format.into();
+        base.mChannelCount = channels;
+        Self::with_base(base, None, None)
+    }
+
+    fn with_base(
+        mut base: ffi::oboe_AudioStreamBase,
+        data_callback: Option<Arc<FakeCallback>>,
+        error_callback: Option<Arc<FakeCallback>>,
+    ) -> Arc<Self> {
+        // Grant the defaults for the unspecified parameters
+        if base.mChannelCount == 0 {
+            base.mChannelCount = match base.mChannelMask.count_ones() {
+                0 => 2,
+                count => count as i32,
+            };
+        }
+        if base.mFormat == ffi::oboe_AudioFormat_Unspecified {
+            base.mFormat = ffi::oboe_AudioFormat_Float;
+        }
+        if base.mSampleRate == 0 {
+            base.mSampleRate = 48000;
+        }
+        if base.mBufferCapacityInFrames <= 0 {
+            base.mBufferCapacityInFrames = 1024;
+        }
+        base.mBufferSizeInFrames = 256.min(base.mBufferCapacityInFrames);
+
+        Arc::new(Self {
+            // SAFETY: The native object is opaque, the fake never calls its virtual methods.
+            raw: UnsafeCell::new(unsafe { MaybeUninit::zeroed().assume_init() }),
+            base: UnsafeCell::new(base),
+            state: Mutex::new(FakeState {
+                state: StreamState::Open,
+                xrun_count: Some(0),
+                frames_per_burst: 64,
+                frames_written: 0,
+                frames_read: 0,
+                timestamp: None,
+                available: 0,
+                written: Vec::new(),
+                captured: VecDeque::new(),
+                script: VecDeque::new(),
+                delay: Duration::ZERO,
+                calls: Vec::new(),
+            }),
+            changed: Condvar::new(),
+            active: AtomicUsize::new(0),
+            max_active: AtomicUsize::new(0),
+            data_callback,
+            error_callback,
+        })
+    }
+
+    /**
+     * Call the data callback with the buffer of frames
+     */
+    pub(crate) fn render(&self, buffer: &mut [u8]) -> DataCallbackResult {
+        let callback = self
+            .data_callback
+            .as_ref()
+            .expect("The data callback is set");
+        let frames = (buffer.len() / self.bytes_per_frame()) as i32;
+        let ready = callback.audio_ready.expect("The data callback is set");
+        // SAFETY: The callback context is alive as long as the callback.
+        let result = unsafe {
+            ready(
+                callback.context,
+                self.raw.get(),
+                buffer.as_mut_ptr() as *mut c_void,
+                frames,
+            )
+        };
+        if result == ffi::oboe_DataCallbackResult_Continue {
+            DataCallbackResult::Continue
+        } else {
+            DataCallbackResult::Stop
+        }
+    }
+
+    /**
+     * Fail the stream like Oboe does on its error thread
+     *
+     * The error callback is asked to handle the error, otherwise the stream is closed
+     * between the calls of the error methods.
+     */
+    pub(crate) fn fail(&self, error: Error) {
+        let Some(callback) = &self.error_callback else {
+            self.change("fail", StreamState::Closed);
+            return;
+        };
+        let error = error.into();
+        // SAFETY: The callback context is alive as long as the callback.
+        unsafe {
+            if let Some(handle) = callback.error {
+                if handle(callback.context, self.raw.get(), error) {
+                    return;
+                }
+            }
+            if let Some(before_close) = callback.before_close {
+                before_close(callback.context, self.raw.get(), error);
+            }
+            self.change("fail", StreamState::Closed);
+            if let Some(after_close) = callback.after_close {
+                after_close(callback.context, self.raw.get(), error);
+            }
+        }
+    }
+
+    /**
+     * Modify the granted parameters
+     */
+    pub(crate) fn set_base(&self, update: impl FnOnce(&mut ffi::oboe_AudioStreamBase)) {
+        // SAFETY: The tests don't modify the parameters while the stream is used.
+        update(unsafe { &mut *self.base.get() })
+    }
+
+    /**
+     * Make the shared handle which owns a reference to the fake
+     */
+    pub(crate) fn handle(self: &Arc<Self>) -> AudioStreamHandle {
+        let mut handle = AudioStreamHandle::default();
+        handle.as_mut()[0] = Arc::into_raw(self.clone()) as u64;
+        handle
+    }
+
+    /**
+     * Get the parameters of the fake
+     */
+    pub(crate) fn config(&self) -> AudioStreamConfig {
+        let stream = AudioStreamRef::<()>::wrap_raw(unsafe { &mut *self.raw.get() });
+        AudioStreamConfig::from_base(&stream, AudioApi::AAudio)
+    }
+
+    pub(crate) fn lock(&self) -> MutexGuard<'_, FakeState> {
+        self.state.lock().unwrap_or_else(|error| error.into_inner())
+    }
+
+    /**
+     * Update the state and wake up the waiting calls
+     */
+    pub(crate) fn update(&self, update: impl FnOnce(&mut FakeState)) {
+        update(&mut self.lock());
+        self.changed.notify_all();
+    }
+
+    /**
+     * The maximum number of native calls which used the stream at the same time
+     */
+    pub(crate) fn max_active(&self) -> usize {
+        self.max_active.load(Ordering::SeqCst)
+    }
+
+    pub(crate) fn buffer_size(&self) -> i32 {
+        unsafe { (*self.base.get()).mBufferSizeInFrames }
+    }
+
+    fn bytes_per_frame(&self) -> usize {
+        let base = unsafe { &*self.base.get() };
+        (base.mChannelCount * bytes_per_sample(base.mFormat)) as usize
+    }
+
+    fn call(&self, name: &'static str) -> MutexGuard<'_, FakeState> {
+        let active = self.active.fetch_add(1, Ordering::SeqCst) + 1;
+        self.max_active.fetch_max(active, Ordering::SeqCst);
+        let delay = {
+            let mut state = self.lock();
+            state.calls.push(name);
+            state.delay
+        };
+        thread::sleep(delay);
+        self.active.fetch_sub(1, Ordering::SeqCst);
+        self.lock()
+    }
+
+    fn change(&self, name: &'static str, state: StreamState) -> ffi::oboe_Result {
+        let mut fake = self.call(name);
+        if fake.state == StreamState::Closed {
+            return ffi::oboe_Result_ErrorClosed;
+        }
+        fake.state = state;
+        drop(fake);
+        self.changed.notify_all();
+        ffi::oboe_Result_OK
+    }
+
+    /// Wait until the condition or the timeout while counting the call as active
+    fn wait_for<T>(
+        &self,
+        name: &'static str,
+        timeout_nanoseconds: i64,
+        mut ready: impl FnMut(&FakeState) -> Option<T>,
+    ) -> Option<T> {
+        let active = self.active.fetch_add(1, Ordering::SeqCst) + 1;
+        self.max_active.fetch_max(active, Ordering::SeqCst);
+        let deadline = Instant::now().checked_add(Duration::from_nanos(timeout_nanoseconds as u64));
+        let mut state = self.lock();
+        state.calls.push(name);
+        let result = loop {
+            if let Some(value) = ready(&state) {
+                break Some(value);
+            }
+            let now = Instant::now();
+            match deadline {
+                Some(deadline) if deadline <= now => break None,
+                Some(deadline) => {
+                    state = self
+                        .changed
+                        .wait_timeout(state, deadline - now)
+                        .unwrap_or_else(|error| error.into_inner())
+                        .0
+                }
+                None => {
+                    state = self
+                        .changed
+                        .wait(state)
+                        .unwrap_or_else(|error| error.into_inner())
+                }
+            }
+        };
+        self.active.fetch_sub(1, Ordering::SeqCst);
+        result
+    }
+
+    fn transfer(&self, name: &'static str, frames: i32) -> std::result::Result<usize, Error> {
+        let mut state = self.call(name);
+        if state.state == StreamState::Closed {
+            return Err(Error::Closed);
+        }
+        match state.script.pop_front() {
+            Some(Ok(limit)) => Ok(limit.clamp(0, frames) as usize),
+            Some(Err(error)) => Err(error),
+            None => Ok(frames.max(0) as usize),
+        }
+    }
+}
+
+fn bytes_per_sample(format: ffi::oboe_AudioFormat) -> i32 {
+    match format {
+        ffi::oboe_AudioFormat_I16 => 2,
+        ffi::oboe_AudioFormat_I24 => 3,
+        ffi::oboe_AudioFormat_I32 | ffi::oboe_AudioFormat_Float => 4,
+        _ => 0,
+    }
+}
+
+fn with_value<T>(value: T, error: ffi::oboe_Result) -> ffi::oboe_ResultWithValue<T> {
+    ffi::oboe_ResultWithValue {
+        mValue: value,
+        mError: error,
+        _phantom_0: Default::default(),
+    }
+}
+
+fn ok<T>(value: T) -> ffi::oboe_ResultWithValue<T> {
+    with_value(value, ffi::oboe_Result_OK)
+}
+
+fn result<T: Default>(result: Result<T>) -> ffi::oboe_ResultWithValue<T> {
+    match result {
+        Ok(value) => ok(value),
+        Err(error) => with_value(T::default(), error.into()),
+    }
+}
+
+unsafe fn fake<'a>(stream: *const c_void) -> &'a FakeStream {
+    &*(stream as *const FakeStream)
+}
+
+// Shared pointers
+
+#[export_name = "_ZN4oboe23AudioStreamShared_cloneEPKNSt6__ndk110shared_ptrINS_11AudioStreamEEEPS3_"]
+unsafe extern "C" fn shared_clone(
+    shared: *const ffi::oboe_AudioStreamShared,
+    new_shared: *mut ffi::oboe_AudioStreamShared,
+) {
+    let ptr = (*shared)[0] as *const FakeStream;
+    if !ptr.is_null() {
+        Arc::increment_strong_count(ptr);
+    }
+    (*new_shared)[0] = ptr as u64;
+}
+
+#[export_name = "_ZN4oboe24AudioStreamShared_deleteEPNSt6__ndk110shared_ptrINS_11AudioStreamEEE"]
+unsafe extern "C" fn shared_delete(shared: *mut ffi::oboe_AudioStreamShared) {
+    let ptr = (*shared)[0] as *const FakeStream;
+    if !ptr.is_null() {
+        drop(Arc::from_raw(ptr));
+    }
+    (*shared)[0] = 0;
+}
+
+#[export_name = "_ZN4oboe23AudioStreamShared_derefEPNSt6__ndk110shared_ptrINS_11AudioStreamEEE"]
+unsafe extern "C" fn shared_deref(
+    shared: *mut ffi::oboe_AudioStreamShared,
+) -> *mut ffi::oboe_AudioStream {
+    let ptr = (*shared)[0] as *const FakeStream;
+    if ptr.is_null() {
+        null_mut()
+    } else {
+        (*ptr).raw.get()
+    }
+}
+
+// Stream parameters
+
+#[export_name = "_ZN4oboe19AudioStream_getBaseEPNS_11AudioStreamE"]
+unsafe extern "C" fn get_base(
+    stream: *mut ffi::oboe_AudioStream,
+) -> *mut ffi::oboe_AudioStreamBase {
+    fake(stream as _).base.get()
+}
+
+#[export_name = "_ZNK4oboe11AudioStream17getBytesPerSampleEv"]
+unsafe extern "C" fn get_bytes_per_sample(stream: *const ffi::oboe_AudioStream) -> i32 {
+    bytes_per_sample((*fake(stream as _).base.get()).mFormat)
+}
+
+#[export_name = "_ZN4oboe23AudioStream_getAudioApiEPKNS_11AudioStreamE"]
+unsafe extern "C" fn get_audio_api(_stream: *const ffi::oboe_AudioStream) -> ffi::oboe_AudioApi {
+    ffi::oboe_AudioApi_AAudio
+}
+
+#[export_name = "_ZN4oboe29AudioStream_getFramesPerBurstEPNS_11AudioStreamE"]
+unsafe extern "C" fn get_frames_per_burst(stream: *mut ffi::oboe_AudioStream) -> i32 {
+    fake(stream as _).lock().frames_per_burst
+}
+
+#[export_name = "_ZN4oboe33AudioStream_setBufferSizeInFramesEPNS_11AudioStreamEi"]
+unsafe extern "C" fn set_buffer_size_in_frames(
+    stream: *mut ffi::oboe_AudioStream,
+    requested_frames: i32,
+) -> ffi::oboe_ResultWithValue<i32> {
+    let base = &mut *fake(stream as _).base.get();
+    base.mBufferSizeInFrames = requested_frames.clamp(1, base.mBufferCapacityInFrames);
+    ok(base.mBufferSizeInFrames)
+}
+
+#[export_name = "_ZN4oboe34AudioStream_calculateLatencyMillisEPNS_11AudioStreamE"]
+unsafe extern "C" fn calculate_latency_millis(
+    _stream: *mut ffi::oboe_AudioStream,
+) -> ffi::oboe_ResultWithValue<f64> {
+    ok(10.0)
+}
+
+#[export_name = "_ZN4oboe24AudioStream_getXRunCountEPNS_11AudioStreamE"]
+unsafe extern "C" fn get_xrun_count(
+    stream: *mut ffi::oboe_AudioStream,
+) -> ffi::oboe_ResultWithValue<i32> {
+    result(
+        fake(stream as _)
+            .lock()
+            .xrun_count
+            .ok_or(Error::Unimplemented),
+    )
+}
+
+#[export_name = "_ZN4oboe32AudioStream_isXRunCountSupportedEPKNS_11AudioStreamE"]
+unsafe extern "C" fn is_xrun_count_supported(stream: *const ffi::oboe_AudioStream) -> bool {
+    fake(stream as _).lock().xrun_count.is_some()
+}
+
+#[export_name = "_ZN4oboe16AudioStream_openEPNS_11AudioStreamE"]
+unsafe extern "C" fn open(stream: *mut ffi::oboe_AudioStream) -> ffi::oboe_Result {
+    fake(stream as _).change("open", StreamState::Open)
+}
+
+// State
+
+#[export_name = "_ZN4oboe20AudioStream_getStateEPNS_11AudioStreamE"]
+unsafe extern "C" fn get_state(stream: *mut ffi::oboe_AudioStream) -> ffi::oboe_StreamState {
+    fake(stream as _).lock().state.into()
+}
+
+#[export_name = "_ZN4oboe30AudioStream_waitForStateChangeEPNS_11AudioStreamENS_11StreamStateEPS2_l"]
+unsafe extern "C" fn wait_for_state_change(
+    stream: *mut ffi::oboe_AudioStream,
+    input_state: ffi::oboe_StreamState,
+    next_state: *mut ffi::oboe_StreamState,
+    timeout_nanoseconds: i64,
+) -> ffi::oboe_Result {
+    let input_state = StreamState::from(input_state);
+    let state = fake(stream as _).wait_for("wait_for_state_change", timeout_nanoseconds, |fake| {
+        (fake.state != input_state).then_some(fake.state)
+    });
+    match state {
+        Some(state) => {
+            *next_state = state.into();
+            ffi::oboe_Result_OK
+        }
+        None => {
+            *next_state = input_state.into();
+            ffi::oboe_Result_ErrorTimeout
+        }
+    }
+}
+
+#[export_name = "_ZN4oboe17AudioStream_closeEPNS_11AudioStreamE"]
+unsafe extern "C" fn close(stream: *mut ffi::oboe_AudioStream) -> ffi::oboe_Result {
+    fake(stream as _).change("close", StreamState::Closed)
+}
+
+#[export_name = "_ZN4oboe11AudioStream5startEl"]
+unsafe extern "C" fn start(stream: *mut c_void, _timeout_nanoseconds: i64) -> ffi::oboe_Result {
+    fake(stream).change("start", StreamState::Started)
+}
+
+#[export_name = "_ZN4oboe11AudioStream4stopEl"]
+unsafe extern "C" fn stop(stream: *mut c_void, _timeout_nanoseconds: i64) -> ffi::oboe_Result {
+    fake(stream).change("stop", StreamState::Stopped)
+}
+
+#[export_name = "_ZN4oboe11AudioStream5pauseEl"]
+unsafe extern "C" fn pause(stream: *mut c_void, _timeout_nanoseconds: i64) -> ffi::oboe_Result {
+    fake(stream).change("pause", StreamState::Paused)
+}
+
+#[export_name = "_ZN4oboe11AudioStream5flushEl"]
+unsafe extern "C" fn flush(stream: *mut c_void, _timeout_nanoseconds: i64) -> ffi::oboe_Result {
+    fake(stream).change("flush", StreamState::Flushed)
+}
+
+#[export_name = "_ZN4oboe24AudioStream_requestStartEPNS_11AudioStreamE"]
+unsafe extern "C" fn request_start(stream: *mut ffi::oboe_AudioStream) -> ffi::oboe_Result {
+    fake(stream as _).change("request_start", StreamState::Started)
+}
+
+#[export_name = "_ZN4oboe23AudioStream_requestStopEPNS_11AudioStreamE"]
+unsafe extern "C" fn request_stop(stream: *mut ffi::oboe_AudioStream) -> ffi::oboe_Result {
+    fake(stream as _).change("request_stop", StreamState::Stopped)
+}
+
+#[export_name = "_ZN4oboe24AudioStream_requestPauseEPNS_11AudioStreamE"]
+unsafe extern "C" fn request_pause(stream: *mut ffi::oboe_AudioStream) -> ffi::oboe_Result {
+    fake(stream as _).change("request_pause", StreamState::Paused)
+}
+
+#[export_name = "_ZN4oboe24AudioStream_requestFlushEPNS_11AudioStreamE"]
+unsafe extern "C" fn request_flush(stream: *mut ffi::oboe_AudioStream) -> ffi::oboe_Result {
+    fake(stream as _).change("request_flush", StreamState::Flushed)
+}
+
+// Position
+
+#[export_name = "_ZN4oboe11AudioStream16getFramesWrittenEv"]
+unsafe extern "C" fn get_frames_written(stream: *mut c_void) -> i64 {
+    fake(stream).lock().frames_written
+}
+
+#[export_name = "_ZN4oboe11AudioStream13getFramesReadEv"]
+unsafe extern "C" fn get_frames_read(stream: *mut c_void) -> i64 {
+    fake(stream).lock().frames_read
+}
+
+#[export_name = "_ZN4oboe11AudioStream12getTimestampEi"]
+unsafe extern "C" fn get_timestamp(
+    stream: *mut c_void,
+    _clock_id: i32,
+) -> ffi::oboe_ResultWithValue<ffi::oboe_FrameTimestamp> {
+    let mut fake = fake(stream).lock();
+    fake.calls.push("getTimestamp");
+    match fake.timestamp {
+        Some(timestamp) => ok(timestamp),
+        None => with_value(
+            ffi::oboe_FrameTimestamp {
+                position: 0,
+                timestamp: 0,
+            },
+            ffi::oboe_Result_ErrorUnavailable,
+        ),
+    }
+}
+
+#[export_name = "_ZN4oboe11AudioStream18getAvailableFramesEv"]
+unsafe extern "C" fn get_available_frames(
+    stream: *mut ffi::oboe_AudioStream,
+) -> ffi::oboe_ResultWithValue<i32> {
+    ok(fake(stream as _).call("get_available_frames").available)
+}
+
+#[export_name = "_ZN4oboe11AudioStream22waitForAvailableFramesEil"]
+unsafe extern "C" fn wait_for_available_frames(
+    stream: *mut ffi::oboe_AudioStream,
+    num_frames: i32,
+    timeout_nanoseconds: i64,
+) -> ffi::oboe_ResultWithValue<i32> {
+    let available =
+        fake(stream as _).wait_for("wait_for_available_frames", timeout_nanoseconds, |fake| {
+            (fake.available >= num_frames || fake.state == StreamState::Closed)
+                .then_some(fake.available)
+        });
+    match available {
+        Some(available) => ok(available),
+        None => with_value(0, ffi::oboe_Result_ErrorTimeout),
+    }
+}
+
+// Data
+
+#[export_name = "_ZN4oboe17AudioStream_writeEPNS_11AudioStreamEPKvil"]
+unsafe extern "C" fn write(
+    stream: *mut ffi::oboe_AudioStream,
+    buffer: *const c_void,
+    num_frames: i32,
+    _timeout_nanoseconds: i64,
+) -> ffi::oboe_ResultWithValue<i32> {
+    let fake = fake(stream as _);
+    let bytes_per_frame = fake.bytes_per_frame();
+    result(fake.transfer("write", num_frames).map(|frames| {
+        let mut state = fake.lock();
+        let bytes = std::slice::from_raw_parts(buffer as *const u8, frames * bytes_per_frame);
+        state.written.extend_from_slice(bytes);
+        state.frames_written += frames as i64;
+        frames as i32
+    }))
+}
+
+#[export_name = "_ZN4oboe16AudioStream_readEPNS_11AudioStreamEPvil"]
+unsafe extern "C" fn read(
+    stream: *mut ffi::oboe_AudioStream,
+    buffer: *mut c_void,
+    num_frames: i32,
+    _timeout_nanoseconds: i64,
+) -> ffi::oboe_ResultWithValue<i32> {
+    let fake = fake(stream as _);
+    let bytes_per_frame = fake.bytes_per_frame();
+    result(fake.transfer("read", num_frames).map(|frames| {
+        let mut state = fake.lock();
+        let frames = frames.min(state.captured.len() / bytes_per_frame);
+        let bytes: Vec<u8> = state.captured.drain(..frames * bytes_per_frame).collect();
+        copy_nonoverlapping(bytes.as_ptr(), buffer as *mut u8, bytes.len());
+        state.frames_read += frames as i64;
+        frames as i32
+    }))
+}
+
+// Builder
+
+/**
+ * The callback which is registered in the builder
+ *
+ * The context is dropped when the last builder or stream which shares it is gone,
+ * like the shared pointer of the native callback wrapper.
+ */
+pub(crate) struct FakeCallback {
+    context: *mut c_void,
+    drop_context: ffi::oboe_DropContextHandler,
+    audio_ready: ffi::oboe_AudioReadyHandler,
+    error: ffi::oboe_ErrorHandler,
+    before_close: ffi::oboe_ErrorCloseHandler,
+    after_close: ffi::oboe_ErrorCloseHandler,
+}
+
+// SAFETY: The contexts of callbacks are sendable and shareable.
+unsafe impl Send for FakeCallback {}
+unsafe impl Sync for FakeCallback {}
+
+impl Drop for FakeCallback {
+    fn drop(&mut self) {
+        if let Some(drop_context) = self.drop_context {
+            // SAFETY: The context is dropped only once.
+            unsafe { drop_context(self.context) }
+        }
+    }
+}
+
+type OpenHook = Arc<Mutex<dyn FnMut(&Arc<FakeStream>) -> Result<()> + Send>>;
+
+thread_local! {
+    static OPEN_HOOK: RefCell<Option<OpenHook>> = const { RefCell::new(None) };
+}
+
+/**
+ * Set the function which is called for each stream opened by the builders
+ * which are created on the current thread afterwards
+ *
+ * The function can modify the granted parameters or fail the opening.
+ */
+pub(crate) fn on_open(hook: impl FnMut(&Arc<FakeStream>) -> Result<()> + Send + 'static) {
+    OPEN_HOOK.with(|current| *current.borrow_mut() = Some(Arc::new(Mutex::new(hook))));
+}
+
+struct FakeBuilder {
+    base: ffi::oboe_AudioStreamBase,
+    audio_api: ffi::oboe_AudioApi,
+    data_callback: Option<Arc<FakeCallback>>,
+    error_callback: Option<Arc<FakeCallback>>,
+    open_hook: Option<OpenHook>,
+}
+
+unsafe fn fake_builder<'a>(builder: *const ffi::oboe_AudioStreamBuilder) -> &'a mut FakeBuilder {
+    &mut *((*builder)._bindgen_opaque_blob[0] as *mut FakeBuilder)
+}
+
+#[export_name = "_ZN4oboe25AudioStreamBuilder_createEPNS_18AudioStreamBuilderE"]
+unsafe extern "C" fn builder_create(builder: *mut ffi::oboe_AudioStreamBuilder) {
+    let fake = Box::new(FakeBuilder {
+        base: MaybeUninit::zeroed().assume_init(),
+        audio_api: ffi::oboe_AudioApi_Unspecified,
+        data_callback: None,
+        error_callback: None,
+        open_hook: OPEN_HOOK.with(|hook| hook.borrow().clone()),
+    });
+    builder.write(MaybeUninit::zeroed().assume_init());
+    (*builder)._bindgen_opaque_blob[0] = Box::into_raw(fake) as u64;
+}
+
+#[export_name = "_ZN4oboe25AudioStreamBuilder_deleteEPNS_18AudioStreamBuilderE"]
+unsafe extern "C" fn builder_delete(builder: *mut ffi::oboe_AudioStreamBuilder) {
+    drop(Box::from_raw(fake_builder(builder)));
+}
+
+#[export_name = "_ZN4oboe18AudioStreamBuilder17isAAudioSupportedEv"]
+extern "C" fn builder_is_aaudio_supported() -> bool {
+    true
+}
+
+#[export_name = "_ZN4oboe18AudioStreamBuilder19isAAudioRecommendedEv"]
+extern "C" fn builder_is_aaudio_recommended() -> bool {
+    true
+}
+
+#[export_name = "_ZN4oboe26AudioStreamBuilder_getBaseEPNS_18AudioStreamBuilderE"]
+unsafe extern "C" fn builder_get_base(
+    builder: *mut ffi::oboe_AudioStreamBuilder,
+) -> *mut ffi::oboe_AudioStreamBase {
+    &mut fake_builder(builder).base
+}
+
+#[export_name = "_ZN4oboe30AudioStreamBuilder_getAudioApiEPKNS_18AudioStreamBuilderE"]
+unsafe extern "C" fn builder_get_audio_api(
+    builder: *const ffi::oboe_AudioStreamBuilder,
+) -> ffi::oboe_AudioApi {
+    fake_builder(builder).audio_api
+}
+
+#[export_name = "_ZN4oboe30AudioStreamBuilder_setAudioApiEPNS_18AudioStreamBuilderENS_8AudioApiE"]
+unsafe extern "C" fn builder_set_audio_api(
+    builder: *mut ffi::oboe_AudioStreamBuilder,
+    api: ffi::oboe_AudioApi,
+) {
+    fake_builder(builder).audio_api = api;
+}
+
+unsafe fn set_data_callback(
+    builder: *mut ffi::oboe_AudioStreamBuilder,
+    context: *mut c_void,
+    drop_context: ffi::oboe_DropContextHandler,
+    audio_ready: ffi::oboe_AudioReadyHandler,
+    before_close: ffi::oboe_ErrorCloseHandler,
+    after_close: ffi::oboe_ErrorCloseHandler,
+) {
+    let builder = fake_builder(builder);
+    let callback = Arc::new(FakeCallback {
+        context,
+        drop_context,
+        audio_ready,
+        error: None,
+        before_close,
+        after_close,
+    });
+    // Keep the separately registered error callback
+    if builder.error_callback.is_none() {
+        builder.error_callback = Some(callback.clone());
+    }
+    builder.data_callback = Some(callback);
+}
+
+#[export_name = "_ZN4oboe30AudioStreamBuilder_setCallbackEPNS_18AudioStreamBuilderEPvPFvS2_EPFNS_18DataCallbackResultES2_PNS_11AudioStreamES2_iEPFvS2_S7_NS_6ResultEESC_"]
+unsafe extern "C" fn builder_set_callback(
+    builder: *mut ffi::oboe_AudioStreamBuilder,
+    context: *mut c_void,
+    drop_context: ffi::oboe_DropContextHandler,
+    audio_ready: ffi::oboe_AudioReadyHandler,
+    before_close: ffi::oboe_ErrorCloseHandler,
+    after_close: ffi::oboe_ErrorCloseHandler,
+) {
+    set_data_callback(
+        builder,
+        context,
+        drop_context,
+        audio_ready,
+        before_close,
+        after_close,
+    );
+}
+
+#[export_name = "_ZN4oboe40AudioStreamBuilder_setStabilizedCallbackEPNS_18AudioStreamBuilderEPvPFvS2_EPFNS_18DataCallbackResultES2_PNS_11AudioStreamES2_iEPFvS2_S7_NS_6ResultEESC_"]
+unsafe extern "C" fn builder_set_stabilized_callback(
+    builder: *mut ffi::oboe_AudioStreamBuilder,
+    context: *mut c_void,
+    drop_context: ffi::oboe_DropContextHandler,
+    audio_ready: ffi::oboe_AudioReadyHandler,
+    before_close: ffi::oboe_ErrorCloseHandler,
+    after_close: ffi::oboe_ErrorCloseHandler,
+) {
+    set_data_callback(
+        builder,
+        context,
+        drop_context,
+        audio_ready,
+        before_close,
+        after_close,
+    );
+}
+
+#[export_name = "_ZN4oboe35AudioStreamBuilder_setErrorCallbackEPNS_18AudioStreamBuilderEPvPFvS2_EPFbS2_PNS_11AudioStreamENS_6ResultEEPFvS2_S6_S7_ESB_"]
+unsafe extern "C" fn builder_set_error_callback(
+    builder: *mut ffi::oboe_AudioStreamBuilder,
+    context: *mut c_void,
+    drop_context: ffi::oboe_DropContextHandler,
+    error: ffi::oboe_ErrorHandler,
+    before_close: ffi::oboe_ErrorCloseHandler,
+    after_close: ffi::oboe_ErrorCloseHandler,
+) {
+    fake_builder(builder).error_callback = Some(Arc::new(FakeCallback {
+        context,
+        drop_context,
+        audio_ready: None,
+        error,
+        before_close,
+        after_close,
+    }));
+}
+
+#[export_name = "_ZN4oboe37AudioStreamBuilder_resetErrorCallbackEPNS_18AudioStreamBuilderE"]
+unsafe extern "C" fn builder_reset_error_callback(builder: *mut ffi::oboe_AudioStreamBuilder) {
+    fake_builder(builder).error_callback = None;
+}
+
+#[export_name = "_ZN4oboe35AudioStreamBuilder_openStreamSharedEPNS_18AudioStreamBuilderEPNSt6__ndk110shared_ptrINS_11AudioStreamEEE"]
+unsafe extern "C" fn builder_open_stream_shared(
+    builder: *mut ffi::oboe_AudioStreamBuilder,
+    shared_stream: *mut ffi::oboe_AudioStreamShared,
+) -> ffi::oboe_Result {
+    let builder = fake_builder(builder);
+    // SAFETY: The parameters are plain data, the callback pointers of the base aren't used.
+    let stream = FakeStream::with_base(
+        std::ptr::read(&builder.base),
+        builder.data_callback.clone(),
+        builder.error_callback.clone(),
+    );
+    if let Some(hook) = &builder.open_hook {
+        let mut hook = hook.lock().unwrap_or_else(|error| error.into_inner());
+        if let Err(error) = hook(&stream) {
+            return error.into();
+        }
+    }
+    (*shared_stream)[0] = Arc::into_raw(stream) as u64;
+    ffi::oboe_Result_OK
+}
+
+// Latency tuner
+
+/**
+ * The fake tuner which raises the buffer size by a burst on every new underrun
+ */
+pub(crate) struct FakeTuner {
+    pub stream: *const FakeStream,
+    pub maximum_buffer_size: i32,
+    pub minimum_buffer_size: i32,
+    pub reset_requested: bool,
+    pub xrun_count: i32,
+}
+
+#[export_name = "_ZN4oboe26LatencyTunerWrapper_createEPNS_11AudioStreamEi"]
+unsafe extern "C" fn tuner_create(
+    stream: *mut ffi::oboe_AudioStream,
+    maximum_buffer_size: i32,
+) -> *mut ffi::oboe_LatencyTunerWrapper {
+    let fake = fake(stream as _);
+    let maximum_buffer_size = if maximum_buffer_size > 0 {
+        maximum_buffer_size
+    } else {
+        (*fake.base.get()).mBufferCapacityInFrames
+    };
+    Box::into_raw(Box::new(FakeTuner {
+        stream: fake,
+        maximum_buffer_size,
+        minimum_buffer_size: fake.lock().frames_per_burst,
+        reset_requested: true,
+        xrun_count: 0,
+    })) as _
+}
+
+#[export_name = "_ZN4oboe26LatencyTunerWrapper_deleteEPNS_19LatencyTunerWrapperE"]
+unsafe extern "C" fn tuner_delete(tuner: *mut ffi::oboe_LatencyTunerWrapper) {
+    drop(Box::from_raw(tuner as *mut FakeTuner));
+}
+
+#[export_name = "_ZN4oboe24LatencyTunerWrapper_tuneEPNS_19LatencyTunerWrapperE"]
+unsafe extern "C" fn tuner_tune(tuner: *mut ffi::oboe_LatencyTunerWrapper) -> ffi::oboe_Result {
+    let tuner = &mut *(tuner as *mut FakeTuner);
+    let stream = &*tuner.stream;
+    let (state, xrun_count, burst) = {
+        let state = stream.lock();
+        (state.state, state.xrun_count, state.frames_per_burst)
+    };
+    if state == StreamState::Closed {
+        return ffi::oboe_Result_ErrorClosed;
+    }
+    let Some(xrun_count) = xrun_count else {
+        return ffi::oboe_Result_ErrorUnimplemented;
+    };
+    let base = &mut *stream.base.get();
+    if tuner.reset_requested {
+        tuner.reset_requested = false;
+        base.mBufferSizeInFrames = tuner.minimum_buffer_size;
+    } else if xrun_count > tuner.xrun_count {
+        base.mBufferSizeInFrames =
+            (base.mBufferSizeInFrames + burst).min(tuner.maximum_buffer_size);
+    }
+    tuner.xrun_count = xrun_count;
+    ffi::oboe_Result_OK
+}
+
+#[export_name = "_ZN4oboe32LatencyTunerWrapper_requestResetEPNS_19LatencyTunerWrapperE"]
+unsafe extern "C" fn tuner_request_reset(tuner: *mut ffi::oboe_LatencyTunerWrapper) {
+    (*(tuner as *mut FakeTuner)).reset_requested = true;
+}
+
+#[export_name = "_ZN4oboe41LatencyTunerWrapper_isAtMaximumBufferSizeEPNS_19LatencyTunerWrapperE"]
+unsafe extern "C" fn tuner_is_at_maximum_buffer_size(
+    tuner: *mut ffi::oboe_LatencyTunerWrapper,
+) -> bool {
+    let tuner = &*(tuner as *mut FakeTuner);
+    (*(*tuner.stream).base.get()).mBufferSizeInFrames >= tuner.maximum_buffer_size
+}
+
+#[export_name = "_ZN4oboe40LatencyTunerWrapper_setMinimumBufferSizeEPNS_19LatencyTunerWrapperEi"]
+unsafe extern "C" fn tuner_set_minimum_buffer_size(
+    tuner: *mut ffi::oboe_LatencyTunerWrapper,
+    buffer_size: i32,
+) {
+    (*(tuner as *mut FakeTuner)).minimum_buffer_size = buffer_size;
+}
+
+#[export_name = "_ZN4oboe40LatencyTunerWrapper_getMinimumBufferSizeEPKNS_19LatencyTunerWrapperE"]
+unsafe extern "C" fn tuner_get_minimum_buffer_size(
+    tuner: *const ffi::oboe_LatencyTunerWrapper,
+) -> i32 {
+    (*(tuner as *const FakeTuner)).minimum_buffer_size
+}
+
+/**
+ * Wrap the fake into the synchronous stream
+ */
+pub(crate) fn sync_stream<D, F>(fake: &Arc<FakeStream>) -> AudioStreamSync<D, F> {
+    AudioStreamSync::wrap_handle(
+        fake.handle(),
+        Arc::new(CallbackPanic::new(PanicPolicy::default())),
+        fake.config(),
+    )
+}
+
+/**
+ * Wrap the fake into the asynchronous stream which owns the callback
+ *
+ * The native callbacks aren't set, so the fake never calls the callback.
+ */
+pub(crate) fn async_stream<D, F>(fake: &Arc<FakeStream>, callback: F) -> AudioStreamAsync<D, F> {
+    AudioStreamAsync::wrap_handle(
+        fake.handle(),
+        Arc::new(CallbackPanic::new(PanicPolicy::default())),
+        fake.config(),
+        CallbackSlot::new(callback),
+    )
+}
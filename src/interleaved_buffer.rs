@@ -0,0 +1,427 @@
+use std::{
+    ffi::c_void,
+    fmt,
+    iter::StepBy,
+    mem::size_of,
+    slice::{from_raw_parts, from_raw_parts_mut, ChunksExact, ChunksExactMut, Iter, IterMut},
+};
+
+use super::{AudioFormat, Error, RawFrameBuffer, RawFrameBufferMut, Result};
+
+/**
+ * The buffer of interleaved frames for reading
+ *
+ * It is used when the number of channels is known at runtime only,
+ * i.e. for streams with unspecified channel count.
+ *
+ * The samples are stored frame by frame, so sample of channel `c` in frame `i`
+ * has index `i * channels + c`.
+ */
+pub struct InterleavedBuffer<'a, T> {
+    data: &'a [T],
+    channels: usize,
+}
+
+impl<'a, T> InterleavedBuffer<'a, T> {
+    /**
+     * Create buffer from interleaved samples
+     *
+     * Panics when the number of channels is zero or
+     * the number of samples isn't a multiple of number of channels.
+     */
+    pub fn new(data: &'a [T], channels: usize) -> Self {
+        assert!(channels > 0, "The number of channels should be non-zero");
+        assert!(
+            data.chunks_exact(channels).remainder().is_empty(),
+            "The number of samples should be a multiple of number of channels"
+        );
+        Self { data, channels }
+    }
+
+    /**
+     * Get the number of channels
+     */
+    pub fn channels(&self) -> usize {
+        self.channels
+    }
+
+    /**
+     * Get the number of frames
+     */
+    pub fn frames(&self) -> usize {
+        self.data.len() / self.channels
+    }
+
+    /**
+     * Check that buffer has no frames
+     */
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    /**
+     * Get the samples of frame
+     *
+     * Panics when the index is out of bounds.
+     */
+    pub fn frame(&self, index: usize) -> &[T] {
+        &self.data[index * self.channels..][..self.channels]
+    }
+
+    /**
+     * Iterate over frames
+     */
+    pub fn iter_frames(&self) -> ChunksExact<'_, T> {
+        self.data.chunks_exact(self.channels)
+    }
+
+    /**
+     * Iterate over samples of channel
+     *
+     * Panics when the channel is out of bounds.
+     */
+    pub fn channel(&self, channel: usize) -> StepBy<Iter<'_, T>> {
+        assert!(channel < self.channels, "The channel is out of bounds");
+        self.data
+            .get(channel..)
+            .unwrap_or_default()
+            .iter()
+            .step_by(self.channels)
+    }
+
+    /**
+     * Get all interleaved samples
+     */
+    pub fn as_slice(&self) -> &[T] {
+        self.data
+    }
+}
+
+impl<'a, T: fmt::Debug> fmt::Debug for InterleavedBuffer<'a, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter_frames()).finish()
+    }
+}
+
+impl<'a, T> RawFrameBuffer for InterleavedBuffer<'a, T> {
+    unsafe fn _wrap_raw(
+        data: *const c_void,
+        frames: usize,
+        channels: usize,
+        _format: AudioFormat,
+    ) -> Result<Self> {
+        // The opened stream always knows the number of channels
+        if channels == 0 {
+            return Err(Error::IllegalArgument);
+        }
+        let samples = frames * channels;
+        Ok(Self {
+            data: from_raw_parts(data as *const T, samples),
+            channels,
+        })
+    }
+
+    fn _raw_frames(&self, channels: usize, _format: AudioFormat) -> Result<(*const c_void, usize)> {
+        // The stream reads or writes the whole frames
+        if channels != self.channels {
+            return Err(Error::IllegalArgument);
+        }
+        Ok((self.data.as_ptr() as *const c_void, self.frames()))
+    }
+
+    fn _bytes_per_frame(&self, _channels: usize) -> usize {
+        self.channels * size_of::<T>()
+    }
+}
+
+/**
+ * The buffer of interleaved frames for writing
+ *
+ * It is used when the number of channels is known at runtime only,
+ * i.e. for streams with unspecified channel count.
+ *
+ * The samples are stored frame by frame, so sample of channel `c` in frame `i`
+ * has index `i * channels + c`.
+ */
+pub struct InterleavedBufferMut<'a, T> {
+    data: &'a mut [T],
+    channels: usize,
+}
+
+impl<'a, T> InterleavedBufferMut<'a, T> {
+    /**
+     * Create buffer from interleaved samples
+     *
+     * Panics when the number of channels is zero or
+     * the number of samples isn't a multiple of number of channels.
+     */
+    pub fn new(data: &'a mut [T], channels: usize) -> Self {
+        assert!(channels > 0, "The number of channels should be non-zero");
+        assert!(
+            data.chunks_exact(channels).remainder().is_empty(),
+            "The number of samples should be a multiple of number of channels"
+        );
+        Self { data, channels }
+    }
+
+    /**
+     * Get the number of channels
+     */
+    pub fn channels(&self) -> usize {
+        self.channels
+    }
+
+    /**
+     * Get the number of frames
+     */
+    pub fn frames(&self) -> usize {
+        self.data.len() / self.channels
+    }
+
+    /**
+     * Check that buffer has no frames
+     */
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    /**
+     * Get the samples of frame
+     *
+     * Panics when the index is out of bounds.
+     */
+    pub fn frame(&self, index: usize) -> &[T] {
+        &self.data[index * self.channels..][..self.channels]
+    }
+
+    /**
+     * Get the mutable samples of frame
+     *
+     * Panics when the index is out of bounds.
+     */
+    pub fn frame_mut(&mut self, index: usize) -> &mut [T] {
+        &mut self.data[index * self.channels..][..self.channels]
+    }
+
+    /**
+     * Iterate over frames
+     */
+    pub fn iter_frames(&self) -> ChunksExact<'_, T> {
+        self.data.chunks_exact(self.channels)
+    }
+
+    /**
+     * Iterate over mutable frames
+     */
+    pub fn iter_frames_mut(&mut self) -> ChunksExactMut<'_, T> {
+        self.data.chunks_exact_mut(self.channels)
+    }
+
+    /**
+     * Iterate over samples of channel
+     *
+     * Panics when the channel is out of bounds.
+     */
+    pub fn channel(&self, channel: usize) -> StepBy<Iter<'_, T>> {
+        assert!(channel < self.channels, "The channel is out of bounds");
+        self.data
+            .get(channel..)
+            .unwrap_or_default()
+            .iter()
+            .step_by(self.channels)
+    }
+
+    /**
+     * Iterate over mutable samples of channel
+     *
+     * Panics when the channel is out of bounds.
+     */
+    pub fn channel_mut(&mut self, channel: usize) -> StepBy<IterMut<'_, T>> {
+        assert!(channel < self.channels, "The channel is out of bounds");
+        self.data
+            .get_mut(channel..)
+            .unwrap_or_default()
+            .iter_mut()
+            .step_by(self.channels)
+    }
+
+    /**
+     * Get all interleaved samples
+     */
+    pub fn as_slice(&self) -> &[T] {
+        self.data
+    }
+
+    /**
+     * Get all mutable interleaved samples
+     */
+    pub fn as_mut_slice(&mut self) -> &mut [T] {
+        self.data
+    }
+}
+
+impl<'a, T: fmt::Debug> fmt::Debug for InterleavedBufferMut<'a, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter_frames()).finish()
+    }
+}
+
+impl<'a, T> RawFrameBufferMut for InterleavedBufferMut<'a, T> {
+    unsafe fn _wrap_raw_mut(
+        data: *mut c_void,
+        frames: usize,
+        channels: usize,
+        _format: AudioFormat,
+    ) -> Result<Self> {
+        // The opened stream always knows the number of channels
+        if channels == 0 {
+            return Err(Error::IllegalArgument);
+        }
+        let samples = frames * channels;
+        Ok(Self {
+            data: from_raw_parts_mut(data as *mut T, samples),
+            channels,
+        })
+    }
+
+    fn _raw_frames_mut(
+        &mut self,
+        channels: usize,
+        _format: AudioFormat,
+    ) -> Result<(*mut c_void, usize)> {
+        // The stream reads or writes the whole frames
+        if channels != self.channels {
+            return Err(Error::IllegalArgument);
+        }
+        Ok((self.data.as_mut_ptr() as *mut c_void, self.frames()))
+    }
+
+    fn _bytes_per_frame(&self, _channels: usize) -> usize {
+        self.channels * size_of::<T>()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn frames_are_indexed_by_channels() {
+        let data = [0, 1, 10, 11, 20, 21];
+        let buffer = InterleavedBuffer::new(&data, 2);
+        assert_eq!(buffer.channels(), 2);
+        assert_eq!(buffer.frames(), 3);
+        assert!(!buffer.is_empty());
+        assert_eq!(buffer.frame(0), &[0, 1]);
+        assert_eq!(buffer.frame(2), &[20, 21]);
+        assert_eq!(
+            buffer.iter_frames().collect::<Vec<_>>(),
+            [&[0, 1], &[10, 11], &[20, 21]]
+        );
+        assert_eq!(buffer.channel(1).copied().collect::<Vec<_>>(), [1, 11, 21]);
+        assert_eq!(format!("{:?}", buffer), "[[0, 1], [10, 11], [20, 21]]");
+    }
+
+    #[test]
+    fn mutable_frames_are_indexed_by_channels() {
+        let mut data = [0; 6];
+        let mut buffer = InterleavedBufferMut::new(&mut data, 3);
+        assert_eq!(buffer.frames(), 2);
+        buffer.frame_mut(1).copy_from_slice(&[4, 5, 6]);
+        for (sample, value) in buffer.channel_mut(0).zip([1, 7]) {
+            *sample = value;
+        }
+        for frame in buffer.iter_frames_mut() {
+            frame[1] += 100;
+        }
+        assert_eq!(buffer.frame(0), &[1, 100, 0]);
+        assert_eq!(buffer.channel(2).copied().collect::<Vec<_>>(), [0, 6]);
+        assert_eq!(data, [1, 100, 0, 7, 105, 6]);
+    }
+
+    #[test]
+    fn empty_buffer_has_no_frames() {
+        let buffer = InterleavedBuffer::<f32>::new(&[], 4);
+        assert!(buffer.is_empty());
+        assert_eq!(buffer.frames(), 0);
+        assert_eq!(buffer.iter_frames().count(), 0);
+        assert_eq!(buffer.channel(3).count(), 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "out of range")]
+    fn frame_out_of_bounds_panics() {
+        InterleavedBuffer::new(&[0, 1, 2, 3], 2).frame(2);
+    }
+
+    #[test]
+    #[should_panic(expected = "The channel is out of bounds")]
+    fn channel_out_of_bounds_panics() {
+        let _ = InterleavedBuffer::new(&[0, 1, 2, 3], 2).channel(2);
+    }
+
+    #[test]
+    #[should_panic(expected = "multiple of number of channels")]
+    fn incomplete_frame_panics() {
+        InterleavedBuffer::new(&[0, 1, 2], 2);
+    }
+
+    #[test]
+    #[should_panic(expected = "non-zero")]
+    fn zero_channels_panics() {
+        InterleavedBufferMut::<i16>::new(&mut [], 0);
+    }
+
+    #[test]
+    fn raw_frames_require_stream_channels() {
+        let mut data = [0i16; 8];
+        let buffer = InterleavedBuffer::new(&data, 2);
+        let (ptr, frames) = buffer._raw_frames(2, AudioFormat::I16).unwrap();
+        assert_eq!((ptr, frames), (data.as_ptr() as *const c_void, 4));
+        assert_eq!(
+            buffer._raw_frames(4, AudioFormat::I16).unwrap_err(),
+            Error::IllegalArgument
+        );
+
+        let mut buffer = InterleavedBufferMut::new(&mut data, 4);
+        assert_eq!(buffer._raw_frames_mut(4, AudioFormat::I16).unwrap().1, 2);
+        assert_eq!(
+            buffer._raw_frames_mut(1, AudioFormat::I16).unwrap_err(),
+            Error::IllegalArgument
+        );
+    }
+
+    #[test]
+    fn raw_frames_are_wrapped_with_stream_channels() {
+        let data = [1.0f32, 2.0, 3.0, 4.0, 5.0, 6.0];
+        let buffer = unsafe {
+            InterleavedBuffer::<f32>::_wrap_raw(
+                data.as_ptr() as *const c_void,
+                2,
+                3,
+                AudioFormat::F32,
+            )
+        }
+        .unwrap();
+        assert_eq!(buffer.channels(), 3);
+        assert_eq!(buffer.frame(1), &[4.0, 5.0, 6.0]);
+        let error = unsafe {
+            InterleavedBuffer::<f32>::_wrap_raw(data.as_ptr() as _, 2, 0, AudioFormat::F32)
+        };
+        assert_eq!(error.unwrap_err(), Error::IllegalArgument);
+
+        let mut data = [0i32; 4];
+        let mut buffer = unsafe {
+            InterleavedBufferMut::<i32>::_wrap_raw_mut(
+                data.as_mut_ptr() as *mut c_void,
+                4,
+                1,
+                AudioFormat::I32,
+            )
+        }
+        .unwrap();
+        assert_eq!(buffer.frames(), 4);
+        buffer.frame_mut(3)[0] = 7;
+        assert_eq!(data, [0, 0, 0, 7]);
+    }
+}
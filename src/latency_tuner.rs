@@ -0,0 +1,310 @@
+use oboe_sys as ffi;
+use std::{fmt, ptr::NonNull};
+
+use super::{wrap_status, AudioStreamSafe, Status};
+
+/**
+ * LatencyTuner can be used to dynamically tune the latency of an output stream.
+ * It adjusts the stream's buffer size by monitoring the number of underruns.
+ *
+ * This only affects the latency associated with the first level of buffering that is closest
+ * to the application. It does not affect low latency in the HAL, or touch latency in the UI.
+ *
+ * Call [`LatencyTuner::tune`] right before returning from your data callback function
+ * if using callbacks. Call it right before calling `write()` if using blocking writes.
+ *
+ * If you want to see the ongoing results of this tuning process then call
+ * [`AudioStreamBase::get_buffer_size_in_frames`](crate::AudioStreamBase::get_buffer_size_in_frames)
+ * periodically.
+ *
+ * The tuner is constructed from the opened stream, such as [`AudioStreamSync`](crate::AudioStreamSync)
+ * or [`AudioStreamAsync`](crate::AudioStreamAsync), or from the stream which is passed
+ * to the data callback. It does not keep the stream alive, so it can be moved to the thread
+ * which drives the stream or owned by the stream callback.
+ * When the stream is gone, tuning fails with [`Error::Closed`](crate::Error::Closed).
+ *
+ * ```ignore
+ * let mut stream = AudioStreamBuilder::default()
+ *     .set_performance_mode(PerformanceMode::LowLatency)
+ *     .set_f32()
+ *     .set_stereo()
+ *     .open_stream()?;
+ *
+ * let mut tuner = LatencyTuner::new(&mut stream);
+ * stream.start()?;
+ *
+ * loop {
+ *     let frames = render();
+ *     let _ = tuner.tune();
+ *     stream.write(&frames, DEFAULT_TIMEOUT_NANOS)?;
+ * }
+ * ```
+ *
+ * The callback can create the tuner on the first call:
+ *
+ * ```ignore
+ * impl AudioOutputCallback for MyCallback {
+ *     type FrameType = (f32, Stereo);
+ *
+ *     fn on_audio_ready(
+ *         &mut self,
+ *         stream: &mut dyn AudioOutputStreamSafe,
+ *         frames: &mut [(f32, f32)],
+ *     ) -> DataCallbackResult {
+ *         self.render(frames);
+ *         let _ = self.tuner.get_or_insert_with(|| LatencyTuner::new(stream)).tune();
+ *         DataCallbackResult::Continue
+ *     }
+ * }
+ * ```
+ */
+pub struct LatencyTuner {
+    raw: NonNull<ffi::oboe_LatencyTunerWrapper>,
+}
+
+// SAFETY: The tuner has no thread affinity, it only should not be used concurrently.
+unsafe impl Send for LatencyTuner {}
+
+impl fmt::Debug for LatencyTuner {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LatencyTuner")
+            .field("minimum_buffer_size", &self.get_minimum_buffer_size())
+            .finish()
+    }
+}
+
+impl LatencyTuner {
+    /**
+     * Construct a new tuner which will act on the given audio stream.
+     *
+     * The maximum buffer size is the buffer capacity of the stream.
+     *
+     * Panics when the stream isn't backed by Oboe.
+     */
+    pub fn new<S: AudioStreamSafe + ?Sized>(stream: &mut S) -> Self {
+        Self::with_maximum_buffer_size(stream, 0)
+    }
+
+    /**
+     * Construct a new tuner which will act on the given audio stream.
+     *
+     * The tune operation will not set the buffer size above `maximum_buffer_size`.
+     * When it isn't positive the buffer capacity of the stream is used.
+     *
+     * Panics when the stream isn't backed by Oboe.
+     */
+    pub fn with_maximum_buffer_size<S: AudioStreamSafe + ?Sized>(
+        stream: &mut S,
+        maximum_buffer_size: i32,
+    ) -> Self {
+        let stream = stream
+            ._native_stream()
+            .expect("Latency tuner needs the Oboe stream");
+        let raw =
+            unsafe { ffi::oboe_LatencyTunerWrapper_create(stream.as_ptr(), maximum_buffer_size) };
+
+        Self {
+            raw: NonNull::new(raw).expect("Latency tuner should be created"),
+        }
+    }
+
+    /**
+     * Adjust the buffer size of the stream to optimize latency.
+     * It will start with a low latency and then raise it if an underrun occurs.
+     *
+     * Latency tuning is only supported for __AAudio__.
+     * For __OpenSL ES__ it returns [`Error::Unimplemented`](crate::Error::Unimplemented).
+     */
+    pub fn tune(&mut self) -> Status {
+        wrap_status(unsafe { ffi::oboe_LatencyTunerWrapper_tune(self.raw.as_ptr()) })
+    }
+
+    /**
+     * Request the tuner to lower the latency to the minimum and then allow it to rise back up
+     * if there are glitches. The reset will happen on the next call of [`LatencyTuner::tune`].
+     *
+     * This is typically called in response to a user decision to minimize latency.
+     */
+    pub fn request_reset(&mut self) {
+        unsafe { ffi::oboe_LatencyTunerWrapper_requestReset(self.raw.as_ptr()) }
+    }
+
+    /**
+     * Returns true if the buffer size of the stream is at the maximum value.
+     *
+     * If no maximum value was specified when constructing the tuner then the buffer capacity
+     * of the stream is used.
+     */
+    pub fn is_at_maximum_buffer_size(&mut self) -> bool {
+        unsafe { ffi::oboe_LatencyTunerWrapper_isAtMaximumBufferSize(self.raw.as_ptr()) }
+    }
+
+    /**
+     * Set the minimum buffer size in frames that is used when the tuner is reset.
+     *
+     * You may wish to call [`LatencyTuner::request_reset`] after calling this.
+     */
+    pub fn set_minimum_buffer_size(&mut self, buffer_size: i32) {
+        unsafe {
+            ffi::oboe_LatencyTunerWrapper_setMinimumBufferSize(self.raw.as_ptr(), buffer_size)
+        }
+    }
+
+    /**
+     * Get the minimum buffer size in frames that is used when the tuner is reset.
+     */
+    pub fn get_minimum_buffer_size(&self) -> i32 {
+        unsafe { ffi::oboe_LatencyTunerWrapper_getMinimumBufferSize(self.raw.as_ptr()) }
+    }
+}
+
+impl Drop for LatencyTuner {
+    fn drop(&mut self) {
+        unsafe { ffi::oboe_LatencyTunerWrapper_delete(self.raw.as_ptr()) }
+    }
+}
+
+#[cfg(all(test, not(target_os = "android")))]
+mod tests {
+    use super::*;
+    use crate::{
+        fake_stream::{on_open, sync_stream, FakeStream},
+        AudioFormat, AudioOutputCallback, AudioOutputStreamSafe, AudioStream, AudioStreamAsync,
+        AudioStreamBase, AudioStreamBuilder, AudioStreamSync, DataCallbackResult, Direction, Error,
+        Mono, Output,
+    };
+
+    fn output() -> (
+        std::sync::Arc<FakeStream>,
+        AudioStreamSync<Output, (f32, Mono)>,
+    ) {
+        let fake = FakeStream::new(Direction::Output, AudioFormat::F32, 1);
+        let stream = sync_stream(&fake);
+        (fake, stream)
+    }
+
+    #[test]
+    fn reset_lowers_buffer_size_to_minimum() {
+        let (fake, mut stream) = output();
+        let mut tuner = LatencyTuner::new(&mut stream);
+        assert_eq!(tuner.get_minimum_buffer_size(), 64);
+
+        assert_eq!(tuner.tune(), Ok(()));
+        assert_eq!(fake.buffer_size(), 64);
+
+        tuner.set_minimum_buffer_size(128);
+        tuner.request_reset();
+        assert_eq!(tuner.tune(), Ok(()));
+        assert_eq!(fake.buffer_size(), 128);
+    }
+
+    #[test]
+    fn underruns_raise_buffer_size_up_to_maximum() {
+        let (fake, mut stream) = output();
+        let mut tuner = LatencyTuner::with_maximum_buffer_size(&mut stream, 192);
+        assert_eq!(tuner.tune(), Ok(()));
+        assert!(!tuner.is_at_maximum_buffer_size());
+
+        for (xruns, size) in [(1, 128), (1, 128), (2, 192), (3, 192)] {
+            fake.update(|state| state.xrun_count = Some(xruns));
+            assert_eq!(tuner.tune(), Ok(()));
+            assert_eq!(fake.buffer_size(), size);
+        }
+        assert!(tuner.is_at_maximum_buffer_size());
+        assert_eq!(stream.get_buffer_size_in_frames(), 192);
+    }
+
+    #[test]
+    fn maximum_defaults_to_capacity() {
+        let (fake, mut stream) = output();
+        let mut tuner = LatencyTuner::new(&mut stream);
+        for xruns in 1..=32 {
+            fake.update(|state| state.xrun_count = Some(xruns));
+            assert_eq!(tuner.tune(), Ok(()));
+        }
+        assert!(tuner.is_at_maximum_buffer_size());
+        assert_eq!(fake.buffer_size(), 1024);
+    }
+
+    #[test]
+    fn unsupported_xrun_count_fails() {
+        let (fake, mut stream) = output();
+        fake.update(|state| state.xrun_count = None);
+        let mut tuner = LatencyTuner::new(&mut stream);
+        assert_eq!(tuner.tune(), Err(Error::Unimplemented));
+    }
+
+    #[test]
+    fn tuning_closed_stream_fails() {
+        let (_fake, mut stream) = output();
+        let mut tuner = LatencyTuner::new(&mut stream);
+        assert_eq!(tuner.tune(), Ok(()));
+        stream.close().unwrap();
+        drop(stream);
+        assert_eq!(tuner.tune(), Err(Error::Closed));
+    }
+
+    /// The callback which tunes the stream which it renders
+    struct Tuned {
+        tuner: Option<LatencyTuner>,
+        results: std::sync::Arc<std::sync::Mutex<Vec<Status>>>,
+    }
+
+    impl AudioOutputCallback for Tuned {
+        type FrameType = (f32, Mono);
+
+        fn on_audio_ready(
+            &mut self,
+            stream: &mut dyn AudioOutputStreamSafe,
+            frames: &mut [f32],
+        ) -> DataCallbackResult {
+            frames.fill(0.0);
+            let tuner = self.tuner.get_or_insert_with(|| LatencyTuner::new(stream));
+            self.results.lock().unwrap().push(tuner.tune());
+            DataCallbackResult::Continue
+        }
+    }
+
+    #[test]
+    fn tuner_is_created_in_callback() {
+        let opened = std::sync::Arc::new(std::sync::Mutex::new(None));
+        let fakes = opened.clone();
+        on_open(move |fake| {
+            fake.update(|state| state.xrun_count = Some(0));
+            *fakes.lock().unwrap() = Some(fake.clone());
+            Ok(())
+        });
+
+        let results = std::sync::Arc::default();
+        let stream: AudioStreamAsync<Output, _> = AudioStreamBuilder::default()
+            .set_f32()
+            .set_mono()
+            .set_callback(Tuned {
+                tuner: None,
+                results: std::sync::Arc::clone(&results),
+            })
+            .open_stream()
+            .unwrap();
+
+        let fake = opened.lock().unwrap().take().unwrap();
+        fake.render(&mut [0; 4 * 16]);
+        fake.update(|state| state.xrun_count = Some(1));
+        fake.render(&mut [0; 4 * 16]);
+
+        assert_eq!(*results.lock().unwrap(), [Ok(()), Ok(())]);
+        assert_eq!(fake.buffer_size(), 128);
+        drop(stream);
+    }
+
+    #[test]
+    fn tuner_can_be_sent_to_callback_thread() {
+        let (fake, mut stream) = output();
+        let mut tuner = LatencyTuner::new(&mut stream);
+        fake.update(|state| state.xrun_count = Some(0));
+        std::thread::spawn(move || tuner.tune())
+            .join()
+            .unwrap()
+            .unwrap();
+        assert_eq!(fake.buffer_size(), 64);
+    }
+}
@@ -1,24 +1,62 @@
 #![doc = include_str!("../README.md")]
 #![cfg_attr(feature = "doc-cfg", feature(doc_cfg))]
 
+mod audio_buffer;
 mod audio_stream;
 mod audio_stream_base;
 mod audio_stream_builder;
 mod audio_stream_callback;
+mod audio_stream_config;
+mod callback_fn;
+mod callback_info;
 mod definitions;
+#[cfg(all(test, not(target_os = "android")))]
+mod fake_stream;
+mod interleaved_buffer;
+mod latency_tuner;
+mod negotiation_report;
+mod open_strategy;
+mod pcm_io;
 mod private;
+mod reconnecting_stream;
+mod stream_control;
+#[cfg(feature = "async")]
+mod stream_future;
+mod stream_lifecycle;
+mod swappable_callback;
+mod timeout;
+mod timestamp;
+mod transfer;
 mod type_guide;
 mod version;
 
 #[cfg(feature = "java-interface")]
 mod java_interface;
 
+pub use self::audio_buffer::*;
 pub use self::audio_stream::*;
 pub use self::audio_stream_base::*;
 pub use self::audio_stream_builder::*;
 pub use self::audio_stream_callback::*;
+pub use self::audio_stream_config::*;
+pub use self::callback_fn::*;
+pub use self::callback_info::*;
 pub use self::definitions::*;
+pub use self::interleaved_buffer::*;
+pub use self::latency_tuner::*;
+pub use self::negotiation_report::*;
+pub use self::open_strategy::*;
+pub use self::pcm_io::*;
 pub(crate) use self::private::*;
+pub use self::reconnecting_stream::*;
+pub use self::stream_control::*;
+#[cfg(feature = "async")]
+pub use self::stream_future::*;
+pub use self::stream_lifecycle::*;
+pub use self::swappable_callback::*;
+pub use self::timeout::*;
+pub use self::timestamp::*;
+pub use self::transfer::*;
 pub use self::type_guide::*;
 pub use self::version::*;
 
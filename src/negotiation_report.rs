@@ -0,0 +1,489 @@
+use std::{
+    error,
+    fmt::{self, Display},
+    result,
+};
+
+use super::{
+    AudioApi, AudioFormat, AudioStreamConfig, ChannelCount, ChannelMask, Error, SessionId,
+};
+
+/**
+ * The stream parameter which may be changed by Oboe when the stream is opened
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum StreamParameter {
+    /**
+     * The sample rate
+     */
+    SampleRate,
+
+    /**
+     * The number of frames in each callback
+     */
+    FramesPerCallback,
+
+    /**
+     * The sample format
+     */
+    Format,
+
+    /**
+     * The number of channels
+     */
+    ChannelCount,
+
+    /**
+     * The channel mask
+     */
+    ChannelMask,
+
+    /**
+     * The buffer capacity in frames
+     */
+    BufferCapacity,
+
+    /**
+     * The sharing mode
+     */
+    SharingMode,
+
+    /**
+     * The performance mode
+     */
+    PerformanceMode,
+
+    /**
+     * The device identifier
+     */
+    DeviceId,
+
+    /**
+     * The session ID
+     */
+    SessionId,
+
+    /**
+     * The usage
+     */
+    Usage,
+
+    /**
+     * The content type
+     */
+    ContentType,
+
+    /**
+     * The input preset
+     */
+    InputPreset,
+
+    /**
+     * The underlying audio API
+     */
+    AudioApi,
+}
+
+impl StreamParameter {
+    /**
+     * All parameters in the report order
+     */
+    pub const ALL: [StreamParameter; 14] = [
+        StreamParameter::SampleRate,
+        StreamParameter::FramesPerCallback,
+        StreamParameter::Format,
+        StreamParameter::ChannelCount,
+        StreamParameter::ChannelMask,
+        StreamParameter::BufferCapacity,
+        StreamParameter::SharingMode,
+        StreamParameter::PerformanceMode,
+        StreamParameter::DeviceId,
+        StreamParameter::SessionId,
+        StreamParameter::Usage,
+        StreamParameter::ContentType,
+        StreamParameter::InputPreset,
+        StreamParameter::AudioApi,
+    ];
+
+    fn name(&self) -> &'static str {
+        match self {
+            StreamParameter::SampleRate => "Sample rate",
+            StreamParameter::FramesPerCallback => "Frames per callback",
+            StreamParameter::Format => "Format",
+            StreamParameter::ChannelCount => "Channel count",
+            StreamParameter::ChannelMask => "Channel mask",
+            StreamParameter::BufferCapacity => "Buffer capacity in frames",
+            StreamParameter::SharingMode => "Sharing mode",
+            StreamParameter::PerformanceMode => "Performance mode",
+            StreamParameter::DeviceId => "DeviceId",
+            StreamParameter::SessionId => "SessionId",
+            StreamParameter::Usage => "Usage",
+            StreamParameter::ContentType => "Content type",
+            StreamParameter::InputPreset => "Input preset",
+            StreamParameter::AudioApi => "Audio API",
+        }
+    }
+}
+
+impl Display for StreamParameter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.name().fmt(f)
+    }
+}
+
+/**
+ * The report of the parameters which were requested and granted by Oboe when the stream was opened
+ *
+ * The parameter is mismatched when it was requested explicitly but another value was granted.
+ * The unspecified parameters are always chosen by Oboe, so these never mismatch.
+ * The buffer capacity mismatches only when it is smaller than requested.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct NegotiationReport {
+    /**
+     * The configuration of the builder
+     */
+    pub requested: AudioStreamConfig,
+
+    /**
+     * The actual configuration of the opened stream
+     */
+    pub granted: AudioStreamConfig,
+}
+
+impl NegotiationReport {
+    /**
+     * Check that the parameter was requested explicitly but another value was granted
+     */
+    pub fn is_mismatched(&self, parameter: StreamParameter) -> bool {
+        let (requested, granted) = (&self.requested, &self.granted);
+
+        fn differs<T: PartialEq>(requested: T, granted: T, unspecified: T) -> bool {
+            requested != unspecified && requested != granted
+        }
+
+        match parameter {
+            StreamParameter::SampleRate => differs(requested.sample_rate, granted.sample_rate, 0),
+            StreamParameter::FramesPerCallback => differs(
+                requested.frames_per_callback,
+                granted.frames_per_callback,
+                0,
+            ),
+            StreamParameter::Format => {
+                differs(requested.format, granted.format, AudioFormat::Unspecified)
+            }
+            StreamParameter::ChannelCount => differs(
+                requested.channel_count,
+                granted.channel_count,
+                ChannelCount::Unspecified,
+            ),
+            StreamParameter::ChannelMask => differs(
+                requested.channel_mask,
+                granted.channel_mask,
+                ChannelMask::Unspecified,
+            ),
+            // The larger buffer holds the requested number of frames too
+            StreamParameter::BufferCapacity => {
+                requested.buffer_capacity_in_frames > 0
+                    && granted.buffer_capacity_in_frames < requested.buffer_capacity_in_frames
+            }
+            StreamParameter::SharingMode => requested.sharing_mode != granted.sharing_mode,
+            StreamParameter::PerformanceMode => {
+                requested.performance_mode != granted.performance_mode
+            }
+            StreamParameter::DeviceId => differs(requested.device_id, granted.device_id, 0),
+            StreamParameter::SessionId => match requested.session_id {
+                SessionId::None => false,
+                // Any allocated session ID is granted
+                SessionId::Allocate => granted.session_id.id().is_none(),
+                SessionId::Id(_) => requested.session_id != granted.session_id,
+            },
+            StreamParameter::Usage => requested.usage != granted.usage,
+            StreamParameter::ContentType => requested.content_type != granted.content_type,
+            StreamParameter::InputPreset => requested.input_preset != granted.input_preset,
+            StreamParameter::AudioApi => differs(
+                requested.audio_api,
+                granted.audio_api,
+                AudioApi::Unspecified,
+            ),
+        }
+    }
+
+    /**
+     * Iterate over mismatched parameters
+     */
+    pub fn mismatches(&self) -> impl Iterator<Item = StreamParameter> + '_ {
+        StreamParameter::ALL
+            .into_iter()
+            .filter(|parameter| self.is_mismatched(*parameter))
+    }
+
+    /**
+     * Check that none of the parameters are mismatched
+     *
+     * The error lists the mismatched parameters of the given ones.
+     */
+    pub fn check(&self, parameters: &[StreamParameter]) -> result::Result<(), NegotiationError> {
+        let mismatches: Vec<_> = StreamParameter::ALL
+            .into_iter()
+            .filter(|parameter| parameters.contains(parameter) && self.is_mismatched(*parameter))
+            .collect();
+
+        if mismatches.is_empty() {
+            Ok(())
+        } else {
+            Err(NegotiationError {
+                report: Box::new(*self),
+                mismatches,
+            })
+        }
+    }
+
+    fn values(&self, parameter: StreamParameter) -> (&dyn fmt::Debug, &dyn fmt::Debug) {
+        let (requested, granted) = (&self.requested, &self.granted);
+        match parameter {
+            StreamParameter::SampleRate => (&requested.sample_rate, &granted.sample_rate),
+            StreamParameter::FramesPerCallback => {
+                (&requested.frames_per_callback, &granted.frames_per_callback)
+            }
+            StreamParameter::Format => (&requested.format, &granted.format),
+            StreamParameter::ChannelCount => (&requested.channel_count, &granted.channel_count),
+            StreamParameter::ChannelMask => (&requested.channel_mask, &granted.channel_mask),
+            StreamParameter::BufferCapacity => (
+                &requested.buffer_capacity_in_frames,
+                &granted.buffer_capacity_in_frames,
+            ),
+            StreamParameter::SharingMode => (&requested.sharing_mode, &granted.sharing_mode),
+            StreamParameter::PerformanceMode => {
+                (&requested.performance_mode, &granted.performance_mode)
+            }
+            StreamParameter::DeviceId => (&requested.device_id, &granted.device_id),
+            StreamParameter::SessionId => (&requested.session_id, &granted.session_id),
+            StreamParameter::Usage => (&requested.usage, &granted.usage),
+            StreamParameter::ContentType => (&requested.content_type, &granted.content_type),
+            StreamParameter::InputPreset => (&requested.input_preset, &granted.input_preset),
+            StreamParameter::AudioApi => (&requested.audio_api, &granted.audio_api),
+        }
+    }
+}
+
+impl Display for NegotiationReport {
+    // Lists each parameter like `Sharing mode: Exclusive requested, Shared granted`
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for parameter in StreamParameter::ALL {
+            let (requested, granted) = self.values(parameter);
+            parameter.fmt(f)?;
+            ": ".fmt(f)?;
+            if self.is_mismatched(parameter) {
+                fmt::Debug::fmt(requested, f)?;
+                " requested, ".fmt(f)?;
+                fmt::Debug::fmt(granted, f)?;
+                " granted".fmt(f)?;
+            } else {
+                fmt::Debug::fmt(granted, f)?;
+            }
+            '\n'.fmt(f)?;
+        }
+        Ok(())
+    }
+}
+
+/**
+ * The refused negotiation which reports the parameters which were granted differently
+ */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NegotiationError {
+    /**
+     * The report of the negotiation
+     */
+    pub report: Box<NegotiationReport>,
+
+    /**
+     * The checked parameters which are mismatched in the report order
+     */
+    pub mismatches: Vec<StreamParameter>,
+}
+
+impl Display for NegotiationError {
+    // Lists the mismatches like `Sharing mode (Exclusive requested, Shared granted)`
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        "Negotiation refused: ".fmt(f)?;
+        for (index, parameter) in self.mismatches.iter().enumerate() {
+            if index > 0 {
+                ", ".fmt(f)?;
+            }
+            let (requested, granted) = self.report.values(*parameter);
+            write!(
+                f,
+                "{parameter} ({requested:?} requested, {granted:?} granted)"
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl error::Error for NegotiationError {}
+
+impl From<NegotiationError> for Error {
+    fn from(_: NegotiationError) -> Self {
+        Error::Unavailable
+    }
+}
+
+/**
+ * The failure to open the stream
+ *
+ * It converts to [`Error`], so the `?` operator can be used in functions which return
+ * [`Result`](crate::Result), but the refused negotiation is reported in full here.
+ */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OpenError {
+    /**
+     * The stream couldn't be opened
+     */
+    Open(Error),
+
+    /**
+     * The stream was opened, but the negotiation was refused by
+     * [`set_strict_negotiation`](crate::AudioStreamBuilder::set_strict_negotiation),
+     * so the stream was closed
+     */
+    Negotiation(NegotiationError),
+}
+
+impl OpenError {
+    /**
+     * Get the error code, which is [`Error::Unavailable`] for the refused negotiation
+     */
+    pub fn error(&self) -> Error {
+        match self {
+            OpenError::Open(error) => *error,
+            OpenError::Negotiation(_) => Error::Unavailable,
+        }
+    }
+
+    /**
+     * Get the refused negotiation with its report
+     */
+    pub fn negotiation(&self) -> Option<&NegotiationError> {
+        match self {
+            OpenError::Open(_) => None,
+            OpenError::Negotiation(error) => Some(error),
+        }
+    }
+}
+
+impl Display for OpenError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OpenError::Open(error) => error.fmt(f),
+            OpenError::Negotiation(error) => error.fmt(f),
+        }
+    }
+}
+
+impl error::Error for OpenError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            OpenError::Open(error) => Some(error),
+            OpenError::Negotiation(error) => Some(error),
+        }
+    }
+}
+
+impl From<Error> for OpenError {
+    fn from(error: Error) -> Self {
+        OpenError::Open(error)
+    }
+}
+
+impl From<NegotiationError> for OpenError {
+    fn from(error: NegotiationError) -> Self {
+        OpenError::Negotiation(error)
+    }
+}
+
+impl From<OpenError> for Error {
+    fn from(error: OpenError) -> Self {
+        error.error()
+    }
+}
+
+/**
+ * The result of opening the stream
+ */
+pub type OpenResult<T> = result::Result<T, OpenError>;
+
+#[cfg(all(test, not(target_os = "android")))]
+mod tests {
+    use super::*;
+    use crate::{AudioStreamBuilder, SharingMode};
+
+    fn report() -> NegotiationReport {
+        let requested = AudioStreamBuilder::default()
+            .set_sample_rate(44100)
+            .set_sharing_mode(SharingMode::Exclusive)
+            .set_device_id(7)
+            .config();
+        let mut granted = requested;
+        granted.sample_rate = 48000;
+        granted.sharing_mode = SharingMode::Shared;
+        NegotiationReport { requested, granted }
+    }
+
+    #[test]
+    fn check_passes_matched_parameters() {
+        let report = report();
+        assert_eq!(report.check(&[]), Ok(()));
+        assert_eq!(
+            report.check(&[StreamParameter::DeviceId, StreamParameter::Format]),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn check_reports_mismatched_parameters() {
+        let report = report();
+        let error = report
+            .check(&[
+                StreamParameter::SharingMode,
+                StreamParameter::DeviceId,
+                StreamParameter::SampleRate,
+            ])
+            .unwrap_err();
+
+        assert_eq!(*error.report, report);
+        assert_eq!(
+            error.mismatches,
+            [StreamParameter::SampleRate, StreamParameter::SharingMode]
+        );
+        assert_eq!(
+            error.to_string(),
+            "Negotiation refused: Sample rate (44100 requested, 48000 granted), \
+             Sharing mode (Exclusive requested, Shared granted)"
+        );
+        assert_eq!(Error::from(error), Error::Unavailable);
+    }
+
+    #[test]
+    fn larger_buffer_capacity_satisfies_request() {
+        let requested = AudioStreamBuilder::default()
+            .set_buffer_capacity_in_frames(512)
+            .config();
+        let mut granted = requested;
+        let check = |granted| {
+            NegotiationReport { requested, granted }.check(&[StreamParameter::BufferCapacity])
+        };
+
+        granted.buffer_capacity_in_frames = 1024;
+        assert_eq!(check(granted), Ok(()));
+
+        granted.buffer_capacity_in_frames = 256;
+        assert_eq!(
+            check(granted).unwrap_err().mismatches,
+            [StreamParameter::BufferCapacity]
+        );
+    }
+}
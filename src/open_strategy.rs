@@ -0,0 +1,506 @@
+use std::{error, fmt, result, slice};
+
+use super::{
+    apply_config, AudioApi, AudioStreamBuilderHandle, AudioStreamConfig, ChannelCount, ChannelMask,
+    ContentType, Error, InputPreset, OpenError, OpenResult, PerformanceMode,
+    SampleRateConversionQuality, SessionId, SharingMode, Usage,
+};
+
+/**
+ * The overrides of the builder configuration for a single attempt of opening
+ *
+ * The parameters which are `None` are kept as configured by the builder.
+ * The direction and format are defined by the builder type, so can't be overridden.
+ * The channel count is defined by the channel mask, so the attempt with the mask
+ * of another number of channels fails with [`Error::IllegalArgument`]
+ * unless the channel count is unspecified by the builder type.
+ */
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ConfigOverride {
+    /**
+     * The sample rate in Hz
+     */
+    pub sample_rate: Option<i32>,
+
+    /**
+     * The number of frames in each callback
+     */
+    pub frames_per_callback: Option<i32>,
+
+    /**
+     * The channel mask, which also sets the channel count when specified
+     */
+    pub channel_mask: Option<ChannelMask>,
+
+    /**
+     * The buffer capacity in frames
+     */
+    pub buffer_capacity_in_frames: Option<i32>,
+
+    /**
+     * The sharing mode
+     */
+    pub sharing_mode: Option<SharingMode>,
+
+    /**
+     * The performance mode
+     */
+    pub performance_mode: Option<PerformanceMode>,
+
+    /**
+     * The device identifier
+     */
+    pub device_id: Option<i32>,
+
+    /**
+     * The session ID or its allocation strategy
+     */
+    pub session_id: Option<SessionId>,
+
+    /**
+     * The usage
+     */
+    pub usage: Option<Usage>,
+
+    /**
+     * The content type
+     */
+    pub content_type: Option<ContentType>,
+
+    /**
+     * The input preset
+     */
+    pub input_preset: Option<InputPreset>,
+
+    /**
+     * Whether Oboe can convert channel counts
+     */
+    pub channel_conversion_allowed: Option<bool>,
+
+    /**
+     * Whether Oboe can convert data formats
+     */
+    pub format_conversion_allowed: Option<bool>,
+
+    /**
+     * Whether and how Oboe can convert sample rates
+     */
+    pub sample_rate_conversion_quality: Option<SampleRateConversionQuality>,
+
+    /**
+     * The underlying audio API
+     */
+    pub audio_api: Option<AudioApi>,
+}
+
+impl ConfigOverride {
+    /**
+     * Apply overrides to the configuration
+     */
+    pub fn apply(&self, config: &mut AudioStreamConfig) {
+        fn set<T: Copy>(value: &mut T, new_value: Option<T>) {
+            if let Some(new_value) = new_value {
+                *value = new_value;
+            }
+        }
+
+        set(&mut config.sample_rate, self.sample_rate);
+        set(&mut config.frames_per_callback, self.frames_per_callback);
+        set(&mut config.channel_mask, self.channel_mask);
+        if let Some(channel_mask) = self.channel_mask {
+            if channel_mask != ChannelMask::Unspecified {
+                config.channel_count = channel_mask.channel_count();
+            }
+        }
+        set(
+            &mut config.buffer_capacity_in_frames,
+            self.buffer_capacity_in_frames,
+        );
+        set(&mut config.sharing_mode, self.sharing_mode);
+        set(&mut config.performance_mode, self.performance_mode);
+        set(&mut config.device_id, self.device_id);
+        set(&mut config.session_id, self.session_id);
+        set(&mut config.usage, self.usage);
+        set(&mut config.content_type, self.content_type);
+        set(&mut config.input_preset, self.input_preset);
+        set(
+            &mut config.channel_conversion_allowed,
+            self.channel_conversion_allowed,
+        );
+        set(
+            &mut config.format_conversion_allowed,
+            self.format_conversion_allowed,
+        );
+        set(
+            &mut config.sample_rate_conversion_quality,
+            self.sample_rate_conversion_quality,
+        );
+        set(&mut config.audio_api, self.audio_api);
+    }
+}
+
+/**
+ * The ordered list of configurations to try when opening a stream
+ *
+ * Each attempt applies its overrides to the configuration of the builder,
+ * so the attempts usually go from the most wanted configuration to the most compatible one.
+ * The strategy without attempts opens the stream as configured by the builder.
+ *
+ * ```ignore
+ * use oboe::{AudioApi, AudioStreamBuilder, ConfigOverride, OpenStrategy, PerformanceMode, SharingMode};
+ *
+ * let strategy = OpenStrategy::new()
+ *     .add_attempt(ConfigOverride {
+ *         sharing_mode: Some(SharingMode::Exclusive),
+ *         audio_api: Some(AudioApi::AAudio),
+ *         sample_rate: Some(48000),
+ *         ..Default::default()
+ *     })
+ *     .add_attempt(ConfigOverride {
+ *         sharing_mode: Some(SharingMode::Shared),
+ *         ..Default::default()
+ *     });
+ *
+ * let opened = AudioStreamBuilder::default()
+ *     .set_performance_mode(PerformanceMode::LowLatency)
+ *     .set_f32()
+ *     .set_stereo()
+ *     .open_stream_with_strategy(&strategy)
+ *     .unwrap();
+ * ```
+ */
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct OpenStrategy {
+    attempts: Vec<ConfigOverride>,
+}
+
+impl OpenStrategy {
+    /**
+     * Create strategy without attempts
+     */
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /**
+     * Add the attempt to the end of list
+     */
+    pub fn add_attempt(mut self, config_override: ConfigOverride) -> Self {
+        self.attempts.push(config_override);
+        self
+    }
+
+    /**
+     * Get the attempts in order
+     */
+    pub fn attempts(&self) -> &[ConfigOverride] {
+        &self.attempts
+    }
+}
+
+impl From<Vec<ConfigOverride>> for OpenStrategy {
+    fn from(attempts: Vec<ConfigOverride>) -> Self {
+        Self { attempts }
+    }
+}
+
+impl FromIterator<ConfigOverride> for OpenStrategy {
+    fn from_iter<I: IntoIterator<Item = ConfigOverride>>(iter: I) -> Self {
+        Self {
+            attempts: iter.into_iter().collect(),
+        }
+    }
+}
+
+/**
+ * The stream which was opened using [`OpenStrategy`]
+ */
+#[derive(Debug)]
+pub struct StrategyOpened<S> {
+    /**
+     * The opened stream
+     */
+    pub stream: S,
+
+    /**
+     * The index of the attempt which succeeded
+     */
+    pub attempt: usize,
+
+    /**
+     * The errors of the earlier attempts in order
+     */
+    pub errors: Vec<OpenError>,
+}
+
+/**
+ * The failure of all attempts of [`OpenStrategy`]
+ */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StrategyFailed {
+    /**
+     * The errors of the attempts in order
+     */
+    pub errors: Vec<OpenError>,
+}
+
+impl error::Error for StrategyFailed {}
+
+impl fmt::Display for StrategyFailed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        "All attempts to open the stream failed: ".fmt(f)?;
+        fmt::Debug::fmt(&self.errors, f)
+    }
+}
+
+/**
+ * The result of opening the stream using [`OpenStrategy`]
+ */
+pub type StrategyResult<S> = result::Result<StrategyOpened<S>, StrategyFailed>;
+
+/**
+ * Try the attempts of strategy in order until the stream is opened
+ *
+ * The channel count is defined by the builder type unless it is unspecified.
+ */
+pub(crate) fn open_with_strategy<S>(
+    raw: &mut AudioStreamBuilderHandle,
+    config: AudioStreamConfig,
+    channel_count: ChannelCount,
+    strategy: &OpenStrategy,
+    mut open: impl FnMut(&mut AudioStreamBuilderHandle, AudioStreamConfig) -> OpenResult<S>,
+) -> StrategyResult<S> {
+    let no_overrides = ConfigOverride::default();
+    let attempts = if strategy.attempts.is_empty() {
+        slice::from_ref(&no_overrides)
+    } else {
+        &strategy.attempts
+    };
+
+    let mut errors = Vec::new();
+    for (attempt, config_override) in attempts.iter().enumerate() {
+        let mut requested = config;
+        config_override.apply(&mut requested);
+        if channel_count != ChannelCount::Unspecified && requested.channel_count != channel_count {
+            errors.push(Error::IllegalArgument.into());
+            continue;
+        }
+        apply_config(raw, &requested);
+
+        match open(raw, requested) {
+            Ok(stream) => {
+                return Ok(StrategyOpened {
+                    stream,
+                    attempt,
+                    errors,
+                })
+            }
+            Err(error) => errors.push(error),
+        }
+    }
+
+    Err(StrategyFailed { errors })
+}
+
+#[cfg(all(test, not(target_os = "android")))]
+mod tests {
+    use super::*;
+    use crate::{
+        fake_stream::on_open, AudioStreamBase, AudioStreamBuilder, AudioStreamSafe, StreamState,
+    };
+    use oboe_sys as ffi;
+
+    fn builder_config() -> AudioStreamConfig {
+        AudioStreamBuilder::default()
+            .set_sample_rate(48000)
+            .set_sharing_mode(SharingMode::Exclusive)
+            .config()
+    }
+
+    fn native_sample_rate(raw: &mut AudioStreamBuilderHandle) -> i32 {
+        // SAFETY: The builder is valid.
+        unsafe { (*ffi::oboe_AudioStreamBuilder_getBase(&mut **raw)).mSampleRate }
+    }
+
+    #[test]
+    fn attempts_are_tried_in_order_until_success() {
+        let strategy = OpenStrategy::new()
+            .add_attempt(ConfigOverride {
+                sample_rate: Some(96000),
+                ..Default::default()
+            })
+            .add_attempt(ConfigOverride {
+                sharing_mode: Some(SharingMode::Shared),
+                ..Default::default()
+            })
+            .add_attempt(ConfigOverride {
+                sample_rate: Some(44100),
+                sharing_mode: Some(SharingMode::Shared),
+                ..Default::default()
+            });
+
+        let mut tried = Vec::new();
+        let mut raw = AudioStreamBuilderHandle::default();
+        let opened = open_with_strategy(
+            &mut raw,
+            builder_config(),
+            ChannelCount::Unspecified,
+            &strategy,
+            |raw, requested| {
+                // Each attempt is written to the native builder
+                assert_eq!(native_sample_rate(raw), requested.sample_rate);
+                tried.push((requested.sample_rate, requested.sharing_mode));
+                match tried.len() {
+                    1 => Err(Error::Unavailable.into()),
+                    2 => Err(Error::InvalidFormat.into()),
+                    _ => Ok("opened"),
+                }
+            },
+        )
+        .unwrap();
+
+        assert_eq!(opened.stream, "opened");
+        assert_eq!(opened.attempt, 2);
+        assert_eq!(
+            opened.errors,
+            [
+                OpenError::Open(Error::Unavailable),
+                OpenError::Open(Error::InvalidFormat)
+            ]
+        );
+        assert_eq!(
+            tried,
+            [
+                (96000, SharingMode::Exclusive),
+                (48000, SharingMode::Shared),
+                (44100, SharingMode::Shared),
+            ]
+        );
+    }
+
+    #[test]
+    fn failure_reports_errors_of_all_attempts() {
+        let strategy: OpenStrategy = vec![ConfigOverride::default(); 3].into_iter().collect();
+        let mut raw = AudioStreamBuilderHandle::default();
+        let mut count = 0;
+        let failed = open_with_strategy(
+            &mut raw,
+            builder_config(),
+            ChannelCount::Unspecified,
+            &strategy,
+            |_, _| -> OpenResult<()> {
+                count += 1;
+                Err(if count == 2 {
+                    Error::NoFreeHandles
+                } else {
+                    Error::Unavailable
+                }
+                .into())
+            },
+        )
+        .unwrap_err();
+
+        assert_eq!(
+            failed.errors,
+            [
+                OpenError::Open(Error::Unavailable),
+                OpenError::Open(Error::NoFreeHandles),
+                OpenError::Open(Error::Unavailable)
+            ]
+        );
+    }
+
+    #[test]
+    fn empty_strategy_opens_builder_config() {
+        let mut raw = AudioStreamBuilderHandle::default();
+        let opened = open_with_strategy(
+            &mut raw,
+            builder_config(),
+            ChannelCount::Unspecified,
+            &OpenStrategy::new(),
+            |_, requested| Ok(requested),
+        )
+        .unwrap();
+
+        assert_eq!(opened.stream, builder_config());
+        assert_eq!(opened.attempt, 0);
+        assert!(opened.errors.is_empty());
+    }
+
+    #[test]
+    fn channel_mask_override_sets_channel_count() {
+        let mut config = builder_config();
+        ConfigOverride {
+            channel_mask: Some(ChannelMask::CM5Point1),
+            ..Default::default()
+        }
+        .apply(&mut config);
+        assert_eq!(config.channel_mask, ChannelMask::CM5Point1);
+        assert_eq!(config.channel_count, ChannelCount::Other(6));
+
+        ConfigOverride {
+            channel_mask: Some(ChannelMask::Unspecified),
+            ..Default::default()
+        }
+        .apply(&mut config);
+        assert_eq!(config.channel_mask, ChannelMask::Unspecified);
+        assert_eq!(config.channel_count, ChannelCount::Other(6));
+    }
+
+    #[test]
+    fn channel_mask_of_other_channel_count_than_type_fails() {
+        let strategy = OpenStrategy::new()
+            .add_attempt(ConfigOverride {
+                channel_mask: Some(ChannelMask::Stereo),
+                ..Default::default()
+            })
+            .add_attempt(ConfigOverride::default());
+
+        let mut raw = AudioStreamBuilderHandle::default();
+        let mut config = builder_config();
+        config.channel_count = ChannelCount::Other(6);
+        let opened = open_with_strategy(
+            &mut raw,
+            config,
+            ChannelCount::Other(6),
+            &strategy,
+            |_, requested| Ok(requested.channel_count),
+        )
+        .unwrap();
+
+        assert_eq!(opened.stream, ChannelCount::Other(6));
+        assert_eq!(opened.attempt, 1);
+        assert_eq!(opened.errors, [OpenError::Open(Error::IllegalArgument)]);
+    }
+
+    #[test]
+    fn surround_falls_back_to_stereo() {
+        // The device can't play surround sound
+        on_open(|fake| {
+            if fake.config().channel_mask == ChannelMask::CM5Point1 {
+                return Err(Error::Unavailable);
+            }
+            Ok(())
+        });
+
+        let strategy = OpenStrategy::new()
+            .add_attempt(ConfigOverride::default())
+            .add_attempt(ConfigOverride {
+                channel_mask: Some(ChannelMask::Stereo),
+                ..Default::default()
+            });
+        let opened = AudioStreamBuilder::default()
+            .set_f32()
+            .set_channel_mask(ChannelMask::CM5Point1)
+            .open_stream_with_strategy(&strategy)
+            .unwrap();
+
+        assert_eq!(opened.attempt, 1);
+        assert_eq!(opened.errors, [OpenError::Open(Error::Unavailable)]);
+        assert_eq!(opened.stream.get_channel_count(), ChannelCount::Stereo);
+        assert_eq!(opened.stream.get_channel_mask(), ChannelMask::Stereo);
+        assert_eq!(opened.stream.get_state(), StreamState::Open);
+    }
+}
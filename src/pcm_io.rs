@@ -0,0 +1,513 @@
+use std::{
+    ffi::c_void,
+    fmt,
+    io::{self, Read, Write},
+    slice::from_raw_parts_mut,
+};
+
+use super::{
+    AudioFormat, AudioInputStreamSync, AudioOutputStreamSync, AudioStream, Error, IsFrameType,
+    RawFrameBuffer, RawFrameBufferMut, Timeout,
+};
+
+impl From<Error> for io::Error {
+    fn from(error: Error) -> Self {
+        let kind = match error {
+            Error::Timeout => io::ErrorKind::TimedOut,
+            Error::WouldBlock => io::ErrorKind::WouldBlock,
+            Error::Disconnected | Error::Closed => io::ErrorKind::BrokenPipe,
+            Error::InvalidFormat | Error::IllegalArgument => io::ErrorKind::InvalidInput,
+            _ => io::ErrorKind::Other,
+        };
+        io::Error::new(kind, error)
+    }
+}
+
+/**
+ * The adapter which writes raw interleaved bytes in the format of the stream
+ *
+ * The bytes of incomplete frame are kept until the rest of frame is written,
+ * so the data can be written in chunks of any size:
+ *
+ * ```ignore
+ * use oboe::{AudioStreamBuilder, PcmWriter};
+ *
+ * let stream = AudioStreamBuilder::default()
+ *     .set_i16()
+ *     .set_stereo()
+ *     .open_stream()?;
+ *
+ * let mut writer = PcmWriter::new(stream);
+ * writer.get_mut().start()?;
+ * std::io::copy(&mut decoder, &mut writer)?;
+ * ```
+ */
+pub struct PcmWriter<S> {
+    stream: S,
+    timeout: Timeout,
+    scratch: Scratch,
+    /// The bytes of the incomplete frame
+    partial: Vec<u8>,
+}
+
+impl<S: AudioOutputStreamSync> PcmWriter<S> {
+    /**
+     * Wrap the stream
+     *
+     * The writes block until all bytes are written by default.
+     */
+    pub fn new(mut stream: S) -> Self {
+        let scratch = Scratch::new(&mut stream);
+        Self {
+            stream,
+            timeout: Timeout::Infinite,
+            scratch,
+            partial: Vec::new(),
+        }
+    }
+
+    /**
+     * Set the timeout of the single write
+     *
+     * With [`Timeout::NonBlocking`] the writes fail with [`io::ErrorKind::WouldBlock`]
+     * when the stream can't accept any frame.
+     */
+    pub fn set_timeout(mut self, timeout: impl Into<Timeout>) -> Self {
+        self.timeout = timeout.into();
+        self
+    }
+
+    /**
+     * Get the timeout of the single write
+     */
+    pub fn get_timeout(&self) -> Timeout {
+        self.timeout
+    }
+
+    /**
+     * Get the number of bytes of the incomplete frame which weren't written yet
+     */
+    pub fn get_pending_bytes(&self) -> usize {
+        self.partial.len()
+    }
+}
+
+impl<S> PcmWriter<S> {
+    /**
+     * Get the reference to the stream
+     */
+    pub fn get_ref(&self) -> &S {
+        &self.stream
+    }
+
+    /**
+     * Get the mutable reference to the stream
+     */
+    pub fn get_mut(&mut self) -> &mut S {
+        &mut self.stream
+    }
+
+    /**
+     * Unwrap the stream
+     *
+     * The bytes of the incomplete frame are discarded.
+     */
+    pub fn into_inner(self) -> S {
+        self.stream
+    }
+}
+
+impl<S> fmt::Debug for PcmWriter<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PcmWriter")
+            .field("timeout", &self.timeout)
+            .field("pending_bytes", &self.partial.len())
+            .finish_non_exhaustive()
+    }
+}
+
+impl<S: AudioOutputStreamSync> Write for PcmWriter<S> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let bytes_per_frame = self.scratch.bytes_per_frame;
+        if buf.is_empty() || bytes_per_frame == 0 {
+            return Ok(0);
+        }
+
+        let partial = self.partial.len();
+        let frames = ((partial + buf.len()) / bytes_per_frame).min(self.scratch.frames);
+        if frames == 0 {
+            self.partial.extend_from_slice(buf);
+            return Ok(buf.len());
+        }
+
+        let taken = frames * bytes_per_frame - partial;
+        let bytes = self.scratch.bytes_mut(frames);
+        bytes[..partial].copy_from_slice(&self.partial);
+        bytes[partial..].copy_from_slice(&buf[..taken]);
+
+        // SAFETY: The scratch buffer is aligned and contains `frames` frames in the stream format.
+        let data = unsafe {
+            <<S::FrameType as IsFrameType>::Buffer<'_>>::_wrap_raw(
+                self.scratch.as_ptr(),
+                frames,
+                self.scratch.channels,
+                self.scratch.format,
+            )
+        }?;
+        let written = (self.stream.write(data, self.timeout.as_nanos())? as usize).min(frames);
+        if written == 0 {
+            return Err(no_progress(self.timeout));
+        }
+        self.partial.clear();
+
+        if written < frames {
+            return Ok(written * bytes_per_frame - partial);
+        }
+
+        // Keep the beginning of the next frame when the rest of bytes doesn't fit the scratch
+        let rest = &buf[taken..];
+        if rest.len() < bytes_per_frame {
+            self.partial.extend_from_slice(rest);
+            Ok(buf.len())
+        } else {
+            Ok(taken)
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/**
+ * The adapter which reads raw interleaved bytes in the format of the stream
+ *
+ * The rest of the frame is kept when the buffer is too small to hold the whole frame,
+ * so the data can be read in chunks of any size:
+ *
+ * ```ignore
+ * use oboe::{AudioStreamBuilder, PcmReader};
+ *
+ * let stream = AudioStreamBuilder::default()
+ *     .set_input()
+ *     .set_i16()
+ *     .set_mono()
+ *     .open_stream()?;
+ *
+ * let mut reader = PcmReader::new(stream);
+ * reader.get_mut().start()?;
+ * std::io::copy(&mut (&mut reader).take(bytes), &mut file)?;
+ * ```
+ */
+pub struct PcmReader<S> {
+    stream: S,
+    timeout: Timeout,
+    scratch: Scratch,
+    /// The bytes of the frame which weren't read yet
+    partial: Vec<u8>,
+}
+
+impl<S: AudioInputStreamSync> PcmReader<S> {
+    /**
+     * Wrap the stream
+     *
+     * The reads block until at least one frame is read by default.
+     */
+    pub fn new(mut stream: S) -> Self {
+        let scratch = Scratch::new(&mut stream);
+        Self {
+            stream,
+            timeout: Timeout::Infinite,
+            scratch,
+            partial: Vec::new(),
+        }
+    }
+
+    /**
+     * Set the timeout of the single read
+     *
+     * With [`Timeout::NonBlocking`] the reads fail with [`io::ErrorKind::WouldBlock`]
+     * when the stream has no frames available.
+     */
+    pub fn set_timeout(mut self, timeout: impl Into<Timeout>) -> Self {
+        self.timeout = timeout.into();
+        self
+    }
+
+    /**
+     * Get the timeout of the single read
+     */
+    pub fn get_timeout(&self) -> Timeout {
+        self.timeout
+    }
+}
+
+impl<S> PcmReader<S> {
+    /**
+     * Get the reference to the stream
+     */
+    pub fn get_ref(&self) -> &S {
+        &self.stream
+    }
+
+    /**
+     * Get the mutable reference to the stream
+     */
+    pub fn get_mut(&mut self) -> &mut S {
+        &mut self.stream
+    }
+
+    /**
+     * Unwrap the stream
+     *
+     * The bytes of the frame which weren't read yet are discarded.
+     */
+    pub fn into_inner(self) -> S {
+        self.stream
+    }
+}
+
+impl<S> fmt::Debug for PcmReader<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PcmReader")
+            .field("timeout", &self.timeout)
+            .field("pending_bytes", &self.partial.len())
+            .finish_non_exhaustive()
+    }
+}
+
+impl<S: AudioInputStreamSync> Read for PcmReader<S> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let bytes_per_frame = self.scratch.bytes_per_frame;
+        if buf.is_empty() || bytes_per_frame == 0 {
+            return Ok(0);
+        }
+
+        if !self.partial.is_empty() {
+            let count = self.partial.len().min(buf.len());
+            buf[..count].copy_from_slice(&self.partial[..count]);
+            self.partial.drain(..count);
+            return Ok(count);
+        }
+
+        let frames = (buf.len() / bytes_per_frame).clamp(1, self.scratch.frames);
+
+        // SAFETY: The scratch buffer is aligned and can hold `frames` frames in the stream format.
+        let data = unsafe {
+            <<S::FrameType as IsFrameType>::BufferMut<'_>>::_wrap_raw_mut(
+                self.scratch.as_mut_ptr(),
+                frames,
+                self.scratch.channels,
+                self.scratch.format,
+            )
+        }?;
+        let read = (self.stream.read(data, self.timeout.as_nanos())? as usize).min(frames);
+        if read == 0 {
+            return Err(no_progress(self.timeout));
+        }
+
+        let bytes = self.scratch.bytes_mut(read);
+        let count = bytes.len().min(buf.len());
+        buf[..count].copy_from_slice(&bytes[..count]);
+        self.partial.extend_from_slice(&bytes[count..]);
+        Ok(count)
+    }
+}
+
+/**
+ * The error when the stream didn't transfer any frame within the timeout
+ */
+fn no_progress(timeout: Timeout) -> io::Error {
+    if timeout.is_non_blocking() {
+        Error::WouldBlock.into()
+    } else {
+        Error::Timeout.into()
+    }
+}
+
+/**
+ * The aligned buffer of frames in the stream format
+ */
+struct Scratch {
+    /// The samples are at most 4 bytes, so the storage is aligned for any format
+    data: Vec<u32>,
+    frames: usize,
+    bytes_per_frame: usize,
+    channels: usize,
+    format: AudioFormat,
+}
+
+impl Scratch {
+    fn new(stream: &mut impl AudioStream) -> Self {
+        let bytes_per_frame = stream.get_bytes_per_frame().max(0) as usize;
+        let frames = stream.get_buffer_capacity_in_frames().max(1) as usize;
+        let words = (frames * bytes_per_frame).div_ceil(4);
+        Self {
+            data: vec![0; words],
+            frames,
+            bytes_per_frame,
+            channels: stream.get_channel_count().count() as usize,
+            format: stream.get_format(),
+        }
+    }
+
+    fn as_ptr(&self) -> *const c_void {
+        self.data.as_ptr() as *const c_void
+    }
+
+    fn as_mut_ptr(&mut self) -> *mut c_void {
+        self.data.as_mut_ptr() as *mut c_void
+    }
+
+    /// Get the bytes of the first `frames` frames
+    fn bytes_mut(&mut self, frames: usize) -> &mut [u8] {
+        let len = (frames * self.bytes_per_frame).min(self.data.len() * 4);
+        // SAFETY: The storage is initialized and has at least `len` bytes.
+        unsafe { from_raw_parts_mut(self.data.as_mut_ptr() as *mut u8, len) }
+    }
+}
+
+#[cfg(all(test, not(target_os = "android")))]
+mod tests {
+    use super::*;
+    use crate::{
+        fake_stream::{sync_stream, FakeStream},
+        AudioStreamSync, Direction, Input, Output, Stereo,
+    };
+    use std::{sync::Arc, time::Duration};
+
+    type Writer = PcmWriter<AudioStreamSync<Output, (i16, Stereo)>>;
+    type Reader = PcmReader<AudioStreamSync<Input, (i16, Stereo)>>;
+
+    /// The bytes of `frames` 16-bit stereo frames which count from one
+    fn frames(frames: usize) -> Vec<u8> {
+        (1..=frames as u8 * 4).collect()
+    }
+
+    fn writer() -> (Arc<FakeStream>, Writer) {
+        let fake = FakeStream::new(Direction::Output, AudioFormat::I16, 2);
+        let writer = PcmWriter::new(sync_stream(&fake));
+        (fake, writer)
+    }
+
+    fn reader() -> (Arc<FakeStream>, Reader) {
+        let fake = FakeStream::new(Direction::Input, AudioFormat::I16, 2);
+        let reader = PcmReader::new(sync_stream(&fake));
+        (fake, reader)
+    }
+
+    #[test]
+    fn writer_carries_incomplete_frame() {
+        let (fake, mut writer) = writer();
+        let data = frames(3);
+
+        assert_eq!(writer.write(&data[..3]).unwrap(), 3);
+        assert_eq!(writer.get_pending_bytes(), 3);
+        assert!(fake.lock().written.is_empty());
+
+        assert_eq!(writer.write(&data[3..9]).unwrap(), 6);
+        assert_eq!(writer.get_pending_bytes(), 1);
+        assert_eq!(fake.lock().written, data[..8]);
+
+        assert_eq!(writer.write(&data[9..]).unwrap(), 3);
+        assert_eq!(writer.get_pending_bytes(), 0);
+        assert_eq!(fake.lock().written, data);
+    }
+
+    #[test]
+    fn writer_continues_short_writes() {
+        let (fake, mut writer) = writer();
+        fake.update(|fake| fake.script.extend([Ok(1), Ok(1), Ok(100)]));
+        let data = frames(5);
+
+        assert_eq!(writer.write(&data[..2]).unwrap(), 2);
+        // The incomplete frame is written first, so one byte of the buffer is taken
+        assert_eq!(writer.write(&data[2..]).unwrap(), 2);
+        assert_eq!(writer.get_pending_bytes(), 0);
+
+        writer.write_all(&data[4..19]).unwrap();
+        assert_eq!(writer.get_pending_bytes(), 3);
+        assert_eq!(fake.lock().written, data[..16]);
+    }
+
+    #[test]
+    fn writer_is_limited_by_scratch() {
+        let fake = FakeStream::new(Direction::Output, AudioFormat::I16, 2);
+        fake.set_base(|base| base.mBufferCapacityInFrames = 2);
+        let mut writer: Writer = PcmWriter::new(sync_stream(&fake));
+        let data = frames(5);
+
+        assert_eq!(writer.write(&data[..1]).unwrap(), 1);
+        assert_eq!(writer.write(&data[1..]).unwrap(), 7);
+        writer.write_all(&data[8..]).unwrap();
+        assert_eq!(fake.lock().written, data);
+    }
+
+    #[test]
+    fn writer_reports_no_progress() {
+        let (fake, writer) = writer();
+        assert_eq!(writer.get_timeout(), Timeout::Infinite);
+        let mut writer = writer.set_timeout(Duration::from_millis(1));
+        fake.update(|fake| fake.script.push_back(Ok(0)));
+
+        let data = frames(2);
+        assert_eq!(writer.write(&data[..2]).unwrap(), 2);
+        let error = writer.write(&data[2..]).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::TimedOut);
+        assert_eq!(writer.get_pending_bytes(), 2);
+
+        let mut writer = writer.set_timeout(Timeout::NonBlocking);
+        fake.update(|fake| fake.script.push_back(Ok(0)));
+        let error = writer.write(&data[2..]).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::WouldBlock);
+
+        fake.update(|fake| fake.script.push_back(Err(Error::Disconnected)));
+        let error = writer.write(&data[2..]).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::BrokenPipe);
+
+        writer.write_all(&data[2..]).unwrap();
+        assert_eq!(fake.lock().written, data);
+    }
+
+    #[test]
+    fn reader_carries_rest_of_frame() {
+        let (fake, mut reader) = reader();
+        let data = frames(3);
+        fake.update(|fake| fake.captured.extend(&data));
+
+        let mut buf = [0; 3];
+        assert_eq!(reader.read(&mut buf).unwrap(), 3);
+        assert_eq!(buf, data[..3]);
+
+        let mut buf = [0; 16];
+        assert_eq!(reader.read(&mut buf).unwrap(), 1);
+        assert_eq!(buf[..1], data[3..4]);
+
+        let mut buf = [0; 6];
+        reader.read_exact(&mut buf).unwrap();
+        assert_eq!(buf, data[4..10]);
+        assert_eq!(fake.lock().frames_read, 3);
+
+        let mut buf = [0; 2];
+        reader.read_exact(&mut buf).unwrap();
+        assert_eq!(buf, data[10..]);
+    }
+
+    #[test]
+    fn reader_reports_no_progress() {
+        let (fake, reader) = reader();
+        let mut reader = reader.set_timeout(Timeout::NonBlocking);
+
+        let mut buf = [0; 4];
+        let error = reader.read(&mut buf).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::WouldBlock);
+
+        let mut reader = reader.set_timeout(Timeout::Infinite);
+        let error = reader.read(&mut buf).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::TimedOut);
+
+        fake.update(|fake| fake.script.push_back(Err(Error::Closed)));
+        let error = reader.read(&mut buf).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::BrokenPipe);
+    }
+}
@@ -1,4 +1,11 @@
 use oboe_sys as ffi;
+use std::{
+    ffi::c_void,
+    mem::size_of,
+    slice::{from_raw_parts, from_raw_parts_mut},
+};
+
+use super::{AudioFormat, Result};
 
 pub trait RawAudioStreamBase {
     fn _raw_base(&self) -> &ffi::oboe_AudioStreamBase;
@@ -8,6 +15,16 @@ pub trait RawAudioStreamBase {
 pub trait RawAudioStream {
     fn _raw_stream(&self) -> &ffi::oboe_AudioStream;
     fn _raw_stream_mut(&mut self) -> &mut ffi::oboe_AudioStream;
+
+    /// Call the native function which changes the state of stream
+    ///
+    /// The stream which is shared with the control handles calls it under their lock.
+    fn _change_state<R>(&mut self, change: impl FnOnce(*mut c_void) -> R) -> R
+    where
+        Self: Sized,
+    {
+        change(self._raw_stream_mut() as *mut _ as *mut c_void)
+    }
 }
 
 /// The raw marker for input stream
@@ -15,3 +32,101 @@ pub trait RawAudioInputStream {}
 
 /// The raw marker for output stream
 pub trait RawAudioOutputStream {}
+
+/// The raw access to buffer of frames for reading
+pub trait RawFrameBuffer: Sized {
+    /// Wraps raw interleaved frames
+    ///
+    /// The `channels` and `format` are actual values of stream.
+    /// Fails when the buffer can't represent the frames of stream.
+    unsafe fn _wrap_raw(
+        data: *const c_void,
+        frames: usize,
+        channels: usize,
+        format: AudioFormat,
+    ) -> Result<Self>;
+
+    /// Gets raw interleaved frames with number of frames
+    ///
+    /// Fails when the layout of buffer is not compatible with actual layout of stream.
+    fn _raw_frames(&self, channels: usize, format: AudioFormat) -> Result<(*const c_void, usize)>;
+
+    /// Gets the size of single frame of the buffer in bytes
+    ///
+    /// The `channels` is actual value of stream.
+    fn _bytes_per_frame(&self, channels: usize) -> usize;
+}
+
+/// The raw access to buffer of frames for writing
+pub trait RawFrameBufferMut: Sized {
+    /// Wraps raw interleaved frames
+    ///
+    /// The `channels` and `format` are actual values of stream.
+    /// Fails when the buffer can't represent the frames of stream.
+    unsafe fn _wrap_raw_mut(
+        data: *mut c_void,
+        frames: usize,
+        channels: usize,
+        format: AudioFormat,
+    ) -> Result<Self>;
+
+    /// Gets raw interleaved frames with number of frames
+    ///
+    /// Fails when the layout of buffer is not compatible with actual layout of stream.
+    fn _raw_frames_mut(
+        &mut self,
+        channels: usize,
+        format: AudioFormat,
+    ) -> Result<(*mut c_void, usize)>;
+
+    /// Gets the size of single frame of the buffer in bytes
+    ///
+    /// The `channels` is actual value of stream.
+    fn _bytes_per_frame(&self, channels: usize) -> usize;
+}
+
+impl<F> RawFrameBuffer for &[F] {
+    unsafe fn _wrap_raw(
+        data: *const c_void,
+        frames: usize,
+        _channels: usize,
+        _format: AudioFormat,
+    ) -> Result<Self> {
+        Ok(from_raw_parts(data as *const F, frames))
+    }
+
+    fn _raw_frames(
+        &self,
+        _channels: usize,
+        _format: AudioFormat,
+    ) -> Result<(*const c_void, usize)> {
+        Ok((self.as_ptr() as *const c_void, self.len()))
+    }
+
+    fn _bytes_per_frame(&self, _channels: usize) -> usize {
+        size_of::<F>()
+    }
+}
+
+impl<F> RawFrameBufferMut for &mut [F] {
+    unsafe fn _wrap_raw_mut(
+        data: *mut c_void,
+        frames: usize,
+        _channels: usize,
+        _format: AudioFormat,
+    ) -> Result<Self> {
+        Ok(from_raw_parts_mut(data as *mut F, frames))
+    }
+
+    fn _raw_frames_mut(
+        &mut self,
+        _channels: usize,
+        _format: AudioFormat,
+    ) -> Result<(*mut c_void, usize)> {
+        Ok((self.as_mut_ptr() as *mut c_void, self.len()))
+    }
+
+    fn _bytes_per_frame(&self, _channels: usize) -> usize {
+        size_of::<F>()
+    }
+}
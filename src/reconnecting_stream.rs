@@ -0,0 +1,597 @@
+use std::{
+    fmt,
+    ops::{Deref, DerefMut},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex, MutexGuard, Weak,
+    },
+    thread,
+    time::Duration,
+};
+
+use oboe_sys as ffi;
+
+use super::{
+    reset_error_callback, set_input_callback, set_output_callback, AudioInputCallback,
+    AudioInputStreamSafe, AudioOutputCallback, AudioOutputStreamSafe, AudioStream,
+    AudioStreamAsync, AudioStreamBuilderHandle, AudioStreamConfig, BuilderSettings, CallbackInfo,
+    CallbackPanic, CallbackSlot, DataCallbackResult, Error, Input, IsFrameType, OpenResult, Output,
+    StreamParameter,
+};
+
+/// The number of attempts to reopen the stream after disconnect
+const RECONNECT_ATTEMPTS: usize = 5;
+
+/// The delay between attempts to reopen the stream
+const RECONNECT_DELAY: Duration = Duration::from_millis(200);
+
+/**
+ * The event which is reported by the reconnecting stream
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReconnectEvent {
+    /**
+     * The stream was closed by Oboe because of an error, so it will be reopened.
+     */
+    Disconnected(Error),
+
+    /**
+     * The new stream was opened and started.
+     */
+    Reconnected,
+
+    /**
+     * The stream couldn't be reopened, so it stays closed.
+     */
+    Failed(Error),
+}
+
+type EventHandler = Box<dyn FnMut(ReconnectEvent) + Send>;
+
+/**
+ * The asynchronous (callback-driven) stream which is reopened automatically
+ * when the device is disconnected
+ *
+ * When the stream is closed with [`Error::Disconnected`] it is reopened with
+ * the same settings on the default device, the same callback object is passed to
+ * the new stream and the new stream is started.
+ *
+ * The current stream is accessed through [`lock`](Self::lock).
+ */
+pub struct ReconnectingStream<D, F> {
+    inner: Arc<Reconnector<D, F>>,
+}
+
+impl<D, F> ReconnectingStream<D, F> {
+    fn open(
+        builder: AudioStreamBuilderHandle,
+        settings: BuilderSettings,
+        requested: AudioStreamConfig,
+        callback: F,
+        set_callback: SetCallback<D, F>,
+    ) -> OpenResult<Self> {
+        let inner = Arc::new(Reconnector {
+            state: Mutex::new(State {
+                builder,
+                requested,
+                stream: None,
+                generation: 0,
+            }),
+            callback: CallbackSlot::new(callback),
+            panic: settings.panic,
+            strict: settings.strict,
+            handler: Mutex::new(None),
+            closed: AtomicBool::new(false),
+            set_callback,
+        });
+
+        inner.open_stream(&mut inner.lock_state(), false)?;
+
+        Ok(Self { inner })
+    }
+
+    /**
+     * Lock the current stream
+     *
+     * The reconnection waits until the stream is unlocked, so don't keep it locked for a long time.
+     */
+    pub fn lock(&self) -> ReconnectingStreamGuard<'_, D, F> {
+        ReconnectingStreamGuard(self.inner.lock_state())
+    }
+
+    /**
+     * Set the handler of the reconnection events
+     *
+     * The handler is called on a thread created by Oboe.
+     */
+    pub fn set_event_handler(&self, handler: impl FnMut(ReconnectEvent) + Send + 'static) {
+        *self
+            .inner
+            .handler
+            .lock()
+            .unwrap_or_else(|error| error.into_inner()) = Some(Box::new(handler));
+    }
+}
+
+impl<F: AudioInputCallback + Send> ReconnectingStream<Input, F> {
+    pub(crate) fn open_input(
+        builder: AudioStreamBuilderHandle,
+        settings: BuilderSettings,
+        requested: AudioStreamConfig,
+        callback: F,
+    ) -> OpenResult<Self> {
+        Self::open(builder, settings, requested, callback, set_input_callback)
+    }
+}
+
+impl<F: AudioOutputCallback + Send> ReconnectingStream<Output, F> {
+    pub(crate) fn open_output(
+        builder: AudioStreamBuilderHandle,
+        settings: BuilderSettings,
+        requested: AudioStreamConfig,
+        callback: F,
+    ) -> OpenResult<Self> {
+        Self::open(builder, settings, requested, callback, set_output_callback)
+    }
+}
+
+impl<D, F> fmt::Debug for ReconnectingStream<D, F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&*self.lock(), f)
+    }
+}
+
+impl<D, F> Drop for ReconnectingStream<D, F> {
+    fn drop(&mut self) {
+        self.inner.closed.store(true, Ordering::Release);
+        // Close the stream now, even when the reconnection keeps the state alive
+        drop(self.inner.lock_state().stream.take());
+    }
+}
+
+/**
+ * The locked current stream of [`ReconnectingStream`]
+ */
+pub struct ReconnectingStreamGuard<'a, D, F>(MutexGuard<'a, State<D, F>>);
+
+impl<'a, D, F> Deref for ReconnectingStreamGuard<'a, D, F> {
+    type Target = AudioStreamAsync<D, F>;
+
+    fn deref(&self) -> &Self::Target {
+        self.0.stream.as_ref().expect("The stream is opened")
+    }
+}
+
+impl<'a, D, F> DerefMut for ReconnectingStreamGuard<'a, D, F> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.0.stream.as_mut().expect("The stream is opened")
+    }
+}
+
+impl<'a, D, F> fmt::Debug for ReconnectingStreamGuard<'a, D, F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+type SetCallback<D, F> =
+    fn(&mut AudioStreamBuilderHandle, ReconnectCallback<D, F>, &Arc<CallbackPanic>);
+
+struct Reconnector<D, F> {
+    state: Mutex<State<D, F>>,
+    /// The callback object which is passed from each stream to the next one
+    callback: CallbackSlot<F>,
+    panic: Arc<CallbackPanic>,
+    strict: Vec<StreamParameter>,
+    handler: Mutex<Option<EventHandler>>,
+    closed: AtomicBool,
+    set_callback: SetCallback<D, F>,
+}
+
+struct State<D, F> {
+    builder: AudioStreamBuilderHandle,
+    /// The configuration of the builder, which is updated on reconnection
+    requested: AudioStreamConfig,
+    stream: Option<AudioStreamAsync<D, F>>,
+    /// The number of the current stream, so the errors of replaced streams are ignored
+    generation: u64,
+}
+
+// SAFETY: The builder and the stream are only used under the lock.
+unsafe impl<D, F: Send> Send for State<D, F> {}
+
+impl<D, F> Reconnector<D, F> {
+    fn lock_state(&self) -> MutexGuard<'_, State<D, F>> {
+        self.state.lock().unwrap_or_else(|error| error.into_inner())
+    }
+
+    /// Check that the stream of generation wasn't replaced or closed
+    fn is_current(&self, state: &State<D, F>, generation: u64) -> bool {
+        !self.closed.load(Ordering::Acquire) && state.generation == generation
+    }
+
+    fn notify(&self, event: ReconnectEvent) {
+        if let Some(handler) = &mut *self
+            .handler
+            .lock()
+            .unwrap_or_else(|error| error.into_inner())
+        {
+            handler(event);
+        }
+    }
+
+    fn open_stream(self: &Arc<Self>, state: &mut State<D, F>, start: bool) -> OpenResult<()> {
+        let generation = state.generation + 1;
+        let callback = ReconnectCallback {
+            callback: self.callback.clone(),
+            reconnector: Arc::downgrade(self),
+            generation,
+        };
+
+        // Each stream gets its own callback context, which also handles the errors
+        reset_error_callback(&mut state.builder);
+        (self.set_callback)(&mut state.builder, callback, &self.panic);
+
+        let stream = state.builder.open_stream()?;
+        // The callback is owned by the reconnecting stream itself
+        let mut stream = AudioStreamAsync::wrap_handle(
+            stream,
+            self.panic.clone(),
+            state.requested,
+            CallbackSlot::empty(),
+        );
+        stream.negotiation_report().check(&self.strict)?;
+        if start {
+            stream.start()?;
+        }
+
+        // The replaced stream is already closed by Oboe
+        state.stream = Some(stream);
+        state.generation = generation;
+
+        Ok(())
+    }
+
+    fn reconnect(self: &Arc<Self>, generation: u64, error: Error) {
+        if !self.is_current(&self.lock_state(), generation) {
+            return;
+        }
+
+        // The handler may lock the stream, so it is called without the lock
+        self.notify(ReconnectEvent::Disconnected(error));
+
+        let mut state = self.lock_state();
+        if !self.is_current(&state, generation) {
+            return;
+        }
+
+        // Reopen the stream on the default device (kUnspecified), so the device which was
+        // requested explicitly isn't expected from the new stream
+        // SAFETY: The builder is valid.
+        unsafe {
+            (*ffi::oboe_AudioStreamBuilder_getBase(&mut *state.builder)).mDeviceId = 0;
+        }
+        state.requested.device_id = 0;
+
+        let mut last_error = error;
+        for attempt in 0..RECONNECT_ATTEMPTS {
+            if attempt > 0 {
+                // Let the system settle on the new device without blocking the stream access
+                drop(state);
+                thread::sleep(RECONNECT_DELAY);
+                state = self.lock_state();
+                if self.closed.load(Ordering::Acquire) {
+                    return;
+                }
+            }
+
+            match self.open_stream(&mut state, true) {
+                Ok(()) => {
+                    drop(state);
+                    self.notify(ReconnectEvent::Reconnected);
+                    return;
+                }
+                Err(error) => last_error = error.into(),
+            }
+        }
+
+        drop(state);
+        self.notify(ReconnectEvent::Failed(last_error));
+    }
+}
+
+/**
+ * The callback which is set for each stream of the reconnecting stream
+ */
+struct ReconnectCallback<D, F> {
+    callback: CallbackSlot<F>,
+    reconnector: Weak<Reconnector<D, F>>,
+    generation: u64,
+}
+
+impl<D, F> ReconnectCallback<D, F> {
+    fn reconnect(&self, error: Error) {
+        if error != Error::Disconnected {
+            return;
+        }
+        if let Some(reconnector) = self.reconnector.upgrade() {
+            reconnector.reconnect(self.generation, error);
+        }
+    }
+}
+
+impl<F: AudioInputCallback> AudioInputCallback for ReconnectCallback<Input, F> {
+    type FrameType = F::FrameType;
+
+    fn wants_callback_info(&self) -> bool {
+        AudioInputCallback::wants_callback_info(&self.callback)
+    }
+
+    fn on_error_before_close(&mut self, audio_stream: &mut dyn AudioInputStreamSafe, error: Error) {
+        AudioInputCallback::on_error_before_close(&mut self.callback, audio_stream, error);
+    }
+
+    fn on_error_after_close(&mut self, audio_stream: &mut dyn AudioInputStreamSafe, error: Error) {
+        AudioInputCallback::on_error_after_close(&mut self.callback, audio_stream, error);
+        self.reconnect(error);
+    }
+
+    fn on_audio_ready(
+        &mut self,
+        audio_stream: &mut dyn AudioInputStreamSafe,
+        audio_data: <Self::FrameType as IsFrameType>::Buffer<'_>,
+    ) -> DataCallbackResult {
+        AudioInputCallback::on_audio_ready(&mut self.callback, audio_stream, audio_data)
+    }
+
+    fn on_audio_ready_with_info(
+        &mut self,
+        audio_stream: &mut dyn AudioInputStreamSafe,
+        info: &CallbackInfo,
+        audio_data: <Self::FrameType as IsFrameType>::Buffer<'_>,
+    ) -> DataCallbackResult {
+        AudioInputCallback::on_audio_ready_with_info(
+            &mut self.callback,
+            audio_stream,
+            info,
+            audio_data,
+        )
+    }
+}
+
+impl<F: AudioOutputCallback> AudioOutputCallback for ReconnectCallback<Output, F> {
+    type FrameType = F::FrameType;
+
+    fn wants_callback_info(&self) -> bool {
+        AudioOutputCallback::wants_callback_info(&self.callback)
+    }
+
+    fn on_error_before_close(
+        &mut self,
+        audio_stream: &mut dyn AudioOutputStreamSafe,
+        error: Error,
+    ) {
+        AudioOutputCallback::on_error_before_close(&mut self.callback, audio_stream, error);
+    }
+
+    fn on_error_after_close(&mut self, audio_stream: &mut dyn AudioOutputStreamSafe, error: Error) {
+        AudioOutputCallback::on_error_after_close(&mut self.callback, audio_stream, error);
+        self.reconnect(error);
+    }
+
+    fn on_audio_ready(
+        &mut self,
+        audio_stream: &mut dyn AudioOutputStreamSafe,
+        audio_data: <Self::FrameType as IsFrameType>::BufferMut<'_>,
+    ) -> DataCallbackResult {
+        AudioOutputCallback::on_audio_ready(&mut self.callback, audio_stream, audio_data)
+    }
+
+    fn on_audio_ready_with_info(
+        &mut self,
+        audio_stream: &mut dyn AudioOutputStreamSafe,
+        info: &CallbackInfo,
+        audio_data: <Self::FrameType as IsFrameType>::BufferMut<'_>,
+    ) -> DataCallbackResult {
+        AudioOutputCallback::on_audio_ready_with_info(
+            &mut self.callback,
+            audio_stream,
+            info,
+            audio_data,
+        )
+    }
+}
+
+#[cfg(all(test, not(target_os = "android")))]
+mod tests {
+    use super::*;
+    use crate::{
+        fake_stream::{on_open, FakeStream},
+        AudioStreamBase, AudioStreamBuilder, AudioStreamSafe, Mono, StreamState,
+    };
+
+    /// The callback which counts the calls, so its state can be checked across streams
+    #[derive(Default)]
+    struct Counter {
+        rendered: Arc<Mutex<usize>>,
+        errors: Arc<Mutex<Vec<Error>>>,
+    }
+
+    impl AudioOutputCallback for Counter {
+        type FrameType = (f32, Mono);
+
+        fn on_error_after_close(
+            &mut self,
+            _audio_stream: &mut dyn AudioOutputStreamSafe,
+            error: Error,
+        ) {
+            self.errors.lock().unwrap().push(error);
+        }
+
+        fn on_audio_ready(
+            &mut self,
+            _audio_stream: &mut dyn AudioOutputStreamSafe,
+            _audio_data: &mut [f32],
+        ) -> DataCallbackResult {
+            *self.rendered.lock().unwrap() += 1;
+            DataCallbackResult::Continue
+        }
+    }
+
+    /// Collect the fakes of the opened streams
+    fn record_opened() -> Arc<Mutex<Vec<Arc<FakeStream>>>> {
+        let opened = Arc::new(Mutex::new(Vec::new()));
+        let fakes = opened.clone();
+        on_open(move |fake| {
+            fakes.lock().unwrap().push(fake.clone());
+            Ok(())
+        });
+        opened
+    }
+
+    fn last(opened: &Mutex<Vec<Arc<FakeStream>>>) -> Arc<FakeStream> {
+        opened.lock().unwrap().last().unwrap().clone()
+    }
+
+    fn open(callback: Counter) -> ReconnectingStream<Output, Counter> {
+        AudioStreamBuilder::default()
+            .set_output()
+            .set_f32()
+            .set_mono()
+            .open_reconnecting_stream(callback)
+            .unwrap()
+    }
+
+    #[test]
+    fn repeated_disconnects_reopen_stream_with_same_callback() {
+        let opened = record_opened();
+        let callback = Counter::default();
+        let (rendered, errors) = (callback.rendered.clone(), callback.errors.clone());
+        let stream = open(callback);
+
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let log = events.clone();
+        stream.set_event_handler(move |event| log.lock().unwrap().push(event));
+
+        for storm in 1..=20 {
+            let fake = last(&opened);
+            assert_eq!(fake.render(&mut [0; 4 * 8]), DataCallbackResult::Continue);
+            fake.fail(Error::Disconnected);
+
+            assert_eq!(opened.lock().unwrap().len(), storm + 1);
+            assert_eq!(last(&opened).lock().state, StreamState::Started);
+            assert_eq!(stream.lock().get_state(), StreamState::Started);
+        }
+
+        assert_eq!(*rendered.lock().unwrap(), 20);
+        assert_eq!(*errors.lock().unwrap(), vec![Error::Disconnected; 20]);
+        let events = events.lock().unwrap();
+        assert_eq!(events.len(), 40);
+        for pair in events.chunks(2) {
+            assert_eq!(
+                pair,
+                [
+                    ReconnectEvent::Disconnected(Error::Disconnected),
+                    ReconnectEvent::Reconnected
+                ]
+            );
+        }
+    }
+
+    #[test]
+    fn errors_of_replaced_streams_are_ignored() {
+        let opened = record_opened();
+        let stream = open(Counter::default());
+
+        let first = last(&opened);
+        first.fail(Error::Disconnected);
+        assert_eq!(opened.lock().unwrap().len(), 2);
+
+        // The late errors of the replaced stream don't reopen the current one
+        for _ in 0..10 {
+            first.fail(Error::Disconnected);
+        }
+        assert_eq!(opened.lock().unwrap().len(), 2);
+        assert_eq!(stream.lock().get_state(), StreamState::Started);
+
+        // Only the disconnection is recovered
+        last(&opened).fail(Error::Internal);
+        assert_eq!(opened.lock().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn event_handler_can_lock_stream() {
+        let opened = record_opened();
+        let stream = Arc::new(open(Counter::default()));
+
+        let states = Arc::new(Mutex::new(Vec::new()));
+        let (weak, log) = (Arc::downgrade(&stream), states.clone());
+        stream.set_event_handler(move |event| {
+            let stream = weak.upgrade().unwrap();
+            log.lock().unwrap().push((event, stream.lock().get_state()));
+        });
+
+        for _ in 0..5 {
+            last(&opened).fail(Error::Disconnected);
+        }
+
+        let states = states.lock().unwrap();
+        assert_eq!(states.len(), 10);
+        for pair in states.chunks(2) {
+            assert_eq!(
+                pair,
+                [
+                    (
+                        ReconnectEvent::Disconnected(Error::Disconnected),
+                        StreamState::Closed
+                    ),
+                    (ReconnectEvent::Reconnected, StreamState::Started),
+                ]
+            );
+        }
+    }
+
+    #[test]
+    fn strict_device_is_not_expected_after_reconnection() {
+        let opened = Arc::new(Mutex::new(Vec::new()));
+        let fakes = opened.clone();
+        on_open(move |fake: &Arc<FakeStream>| {
+            // The requested device is granted, otherwise the default one
+            fake.set_base(|base| {
+                if base.mDeviceId == 0 {
+                    base.mDeviceId = 3;
+                }
+            });
+            fakes.lock().unwrap().push(fake.clone());
+            Ok(())
+        });
+
+        let stream = AudioStreamBuilder::default()
+            .set_output()
+            .set_f32()
+            .set_mono()
+            .set_device_id(7)
+            .set_strict_negotiation(&[StreamParameter::DeviceId])
+            .open_reconnecting_stream(Counter::default())
+            .unwrap();
+        assert_eq!(stream.lock().get_device_id(), 7);
+
+        for _ in 0..3 {
+            last(&opened).fail(Error::Disconnected);
+            assert_eq!(stream.lock().get_state(), StreamState::Started);
+            assert_eq!(stream.lock().get_device_id(), 3);
+        }
+        assert_eq!(opened.lock().unwrap().len(), 4);
+    }
+
+    #[test]
+    fn dropped_stream_isnt_reopened() {
+        let opened = record_opened();
+        let stream = open(Counter::default());
+        let fake = last(&opened);
+        drop(stream);
+
+        fake.fail(Error::Disconnected);
+        assert_eq!(opened.lock().unwrap().len(), 1);
+    }
+}
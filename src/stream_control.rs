@@ -0,0 +1,312 @@
+use oboe_sys as ffi;
+use std::{
+    fmt,
+    marker::PhantomData,
+    sync::{Arc, Mutex, MutexGuard},
+};
+
+use super::{
+    audio_stream_fmt, AudioInputStreamSafe, AudioOutputStream, AudioOutputStreamSafe, AudioStream,
+    AudioStreamHandle, AudioStreamSafe, Clock, FrameTimestamp, Input, Output, RawAudioInputStream,
+    RawAudioOutputStream, RawAudioStream, RawAudioStreamBase, Result, Status, StreamState,
+    Timestamp,
+};
+
+/**
+ * The thread-safe handle to control the asynchronous stream
+ *
+ * It can be obtained using [`AudioStreamAsync::control_handle`](crate::AudioStreamAsync::control_handle)
+ * and sent to another thread, for example to start and stop playback from the UI thread.
+ *
+ * Thread-safety guarantees:
+ *
+ * - All handles of the same stream share a single lock, so the calls through the handles
+ *   never run concurrently with each other or with dropping the stream by its owner.
+ * - The handle keeps the native stream object alive, but not open. When the owner drops
+ *   the stream or Oboe closes it because of an error, the state changes return
+ *   [`Error::Closed`](crate::Error::Closed) and [`get_state`](Self::get_state)
+ *   returns [`StreamState::Closed`].
+ * - The state changes through the owner itself, like [`AudioStream::start`] or
+ *   [`AudioStream::close`], take the same lock, so the stream is started, stopped or closed
+ *   by one side at a time. The queries of the owner aren't locked.
+ */
+pub struct StreamControl<D> {
+    stream: Arc<Mutex<ControlledStream<D>>>,
+}
+
+// The handle must be usable from any thread
+const _: fn() = || {
+    fn assert_thread_safe<T: Send + Sync + Clone + 'static>() {}
+    assert_thread_safe::<StreamControl<Input>>();
+    assert_thread_safe::<StreamControl<Output>>();
+};
+
+impl<D> Clone for StreamControl<D> {
+    fn clone(&self) -> Self {
+        Self {
+            stream: self.stream.clone(),
+        }
+    }
+}
+
+impl<D> fmt::Debug for StreamControl<D> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        audio_stream_fmt(&*self.lock(), f)
+    }
+}
+
+impl<D> StreamControl<D> {
+    pub(crate) fn new(raw: AudioStreamHandle) -> Self {
+        Self {
+            stream: Arc::new(Mutex::new(ControlledStream {
+                raw,
+                _phantom: PhantomData,
+            })),
+        }
+    }
+
+    /**
+     * Lock the stream, so no handle can use it until the guard is dropped
+     */
+    pub(crate) fn lock(&self) -> MutexGuard<'_, ControlledStream<D>> {
+        self.stream
+            .lock()
+            .unwrap_or_else(|error| error.into_inner())
+    }
+
+    /**
+     * Start the stream. This will block until the stream has been started, an error occurs
+     * or the default timeout has been reached.
+     */
+    pub fn start(&self) -> Status {
+        self.lock().start()
+    }
+
+    /**
+     * Stop the stream. This will block until the stream has been stopped, an error occurs
+     * or the default timeout has been reached.
+     */
+    pub fn stop(&self) -> Status {
+        self.lock().stop()
+    }
+
+    /**
+     * Start the stream asynchronously. Returns immediately (does not block).
+     */
+    pub fn request_start(&self) -> Status {
+        self.lock().request_start()
+    }
+
+    /**
+     * Stop the stream asynchronously. Returns immediately (does not block).
+     */
+    pub fn request_stop(&self) -> Status {
+        self.lock().request_stop()
+    }
+
+    /**
+     * Query the current state, eg. `StreamState::Pausing`
+     */
+    pub fn get_state(&self) -> StreamState {
+        self.lock().get_state()
+    }
+
+    /**
+     * Get the number of underruns or overruns.
+     *
+     * See [`AudioStreamSafe::get_xrun_count`].
+     */
+    pub fn get_xrun_count(&self) -> Result<i32> {
+        self.lock().get_xrun_count()
+    }
+
+    /**
+     * Returns true if XRun counts are supported on the stream
+     */
+    pub fn is_xrun_count_supported(&self) -> bool {
+        self.lock().is_xrun_count_supported()
+    }
+
+    /**
+     * Get the estimated time that the frame at `frame_position` entered or left the audio processing
+     * pipeline.
+     *
+     * See [`AudioStreamSafe::get_timestamp`].
+     */
+    pub fn get_timestamp(&self, clock_id: i32) -> Result<FrameTimestamp> {
+        self.lock().get_timestamp(clock_id)
+    }
+
+    /**
+     * Get the estimated time on the `clock` that the frame at the position entered or left
+     * the audio processing pipeline.
+     *
+     * See [`AudioStreamSafe::get_timestamp_for_clock`].
+     */
+    pub fn get_timestamp_for_clock(&self, clock: Clock) -> Result<Timestamp> {
+        self.lock().get_timestamp_for_clock(clock)
+    }
+}
+
+impl StreamControl<Input> {
+    /**
+     * The number of audio frames read from the stream.
+     */
+    pub fn get_frames_read(&self) -> i64 {
+        self.lock().get_frames_read()
+    }
+}
+
+impl StreamControl<Output> {
+    /**
+     * Pause the stream. This will block until the stream has been paused, an error occurs
+     * or the default timeout has been reached.
+     */
+    pub fn pause(&self) -> Status {
+        self.lock().pause()
+    }
+
+    /**
+     * Flush the stream. This will block until the stream has been flushed, an error occurs
+     * or the default timeout has been reached.
+     */
+    pub fn flush(&self) -> Status {
+        self.lock().flush()
+    }
+
+    /**
+     * Pause the stream asynchronously. Returns immediately (does not block).
+     */
+    pub fn request_pause(&self) -> Status {
+        self.lock().request_pause()
+    }
+
+    /**
+     * Flush the stream asynchronously. Returns immediately (does not block).
+     */
+    pub fn request_flush(&self) -> Status {
+        self.lock().request_flush()
+    }
+
+    /**
+     * The number of audio frames written into the stream.
+     */
+    pub fn get_frames_written(&self) -> i64 {
+        self.lock().get_frames_written()
+    }
+}
+
+/**
+ * The stream which is shared by control handles
+ */
+pub(crate) struct ControlledStream<D> {
+    raw: AudioStreamHandle,
+    _phantom: PhantomData<D>,
+}
+
+// SAFETY: The shared pointer can be moved between threads, and the stream is used under the lock only.
+unsafe impl<D> Send for ControlledStream<D> {}
+
+impl<D> RawAudioStreamBase for ControlledStream<D> {
+    fn _raw_base(&self) -> &ffi::oboe_AudioStreamBase {
+        unsafe { &*ffi::oboe_AudioStream_getBase(&*self.raw as *const _ as *mut _) }
+    }
+
+    fn _raw_base_mut(&mut self) -> &mut ffi::oboe_AudioStreamBase {
+        unsafe { &mut *ffi::oboe_AudioStream_getBase(&mut *self.raw as *mut _) }
+    }
+}
+
+impl<D> RawAudioStream for ControlledStream<D> {
+    fn _raw_stream(&self) -> &ffi::oboe_AudioStream {
+        &self.raw
+    }
+
+    fn _raw_stream_mut(&mut self) -> &mut ffi::oboe_AudioStream {
+        &mut self.raw
+    }
+}
+
+impl RawAudioInputStream for ControlledStream<Input> {}
+
+impl RawAudioOutputStream for ControlledStream<Output> {}
+
+#[cfg(all(test, not(target_os = "android")))]
+mod tests {
+    use super::*;
+    use crate::{
+        fake_stream::{async_stream, FakeStream},
+        AudioFormat, AudioStreamAsync, Direction, Error,
+    };
+    use std::{thread, time::Duration};
+
+    fn fake() -> (Arc<FakeStream>, AudioStreamAsync<Output, ()>) {
+        let fake = FakeStream::new(Direction::Output, AudioFormat::F32, 2);
+        // Make each native call long enough to overlap with the others
+        fake.update(|fake| fake.delay = Duration::from_millis(2));
+        let stream = async_stream(&fake, ());
+        (fake, stream)
+    }
+
+    #[test]
+    fn handles_and_owner_change_state_one_at_a_time() {
+        let (fake, mut stream) = fake();
+
+        let threads: Vec<_> = (0..4)
+            .map(|_| {
+                let control = stream.control_handle();
+                thread::spawn(move || {
+                    for _ in 0..10 {
+                        control.start().unwrap();
+                        control.pause().unwrap();
+                        control.request_stop().unwrap();
+                    }
+                })
+            })
+            .collect();
+        for _ in 0..10 {
+            stream.start().unwrap();
+            stream.request_pause().unwrap();
+            stream.stop().unwrap();
+        }
+        for thread in threads {
+            thread.join().unwrap();
+        }
+
+        assert_eq!(fake.max_active(), 1);
+        assert_eq!(fake.lock().calls.len(), 4 * 30 + 30);
+    }
+
+    #[test]
+    fn owner_closes_stream_between_handle_calls() {
+        let (fake, stream) = fake();
+        let control = stream.control_handle();
+
+        let starting = thread::spawn(move || {
+            for _ in 0..20 {
+                if control.start() == Err(Error::Closed) {
+                    return control;
+                }
+            }
+            control
+        });
+        thread::sleep(Duration::from_millis(5));
+        drop(stream);
+        let control = starting.join().unwrap();
+
+        assert_eq!(fake.max_active(), 1);
+        assert_eq!(control.get_state(), StreamState::Closed);
+        assert_eq!(control.stop(), Err(Error::Closed));
+    }
+
+    #[test]
+    fn handle_outlives_stream() {
+        let (fake, stream) = fake();
+        let control = stream.control_handle();
+        drop(stream);
+
+        assert_eq!(control.start(), Err(Error::Closed));
+        assert_eq!(control.get_state(), StreamState::Closed);
+        assert_eq!(fake.lock().state, StreamState::Closed);
+    }
+}
@@ -0,0 +1,792 @@
+use std::{
+    fmt,
+    future::Future,
+    mem,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Condvar, Mutex, MutexGuard,
+    },
+    task::{Context, Poll, Waker},
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+use super::{
+    AudioInputStreamSync, AudioOutputStreamSync, AudioStream, AudioStreamAsync, AudioStreamSafe,
+    AudioStreamSync, Error, Input, IsFrameType, Output, Result, StreamControl, StreamState,
+    NANOS_PER_MILLISECOND, NANOS_PER_SECOND,
+};
+
+impl<D: 'static, F> AudioStreamSync<D, F> {
+    /**
+     * Wait until the stream's current state no longer matches `input_state`
+     *
+     * It is the non-blocking counterpart of [`AudioStream::wait_for_state_change`]
+     * which resolves to the new state.
+     */
+    #[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "async")))]
+    pub fn state_changed(&self, input_state: StreamState) -> StateChanged {
+        StateChanged::spawn(self.helper(), self.control().clone(), input_state)
+    }
+
+    /**
+     * Wait until the stream has at least `num_frames` frames available in its buffer
+     *
+     * It is the non-blocking counterpart of [`AudioStream::wait_for_available_frames`]
+     * which resolves to the number of available frames.
+     */
+    #[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "async")))]
+    pub fn available_frames(&self, num_frames: i32) -> AvailableFrames {
+        AvailableFrames::spawn(self.helper(), self.control().clone(), num_frames)
+    }
+}
+
+impl<D: 'static, F> AudioStreamAsync<D, F> {
+    /**
+     * Wait until the stream's current state no longer matches `input_state`
+     *
+     * It is the non-blocking counterpart of [`AudioStream::wait_for_state_change`]
+     * which resolves to the new state.
+     */
+    #[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "async")))]
+    pub fn state_changed(&self, input_state: StreamState) -> StateChanged {
+        StateChanged::spawn(self.helper(), self.control().clone(), input_state)
+    }
+
+    /**
+     * Wait until the stream has at least `num_frames` frames available in its buffer
+     *
+     * It is the non-blocking counterpart of [`AudioStream::wait_for_available_frames`]
+     * which resolves to the number of available frames.
+     */
+    #[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "async")))]
+    pub fn available_frames(&self, num_frames: i32) -> AvailableFrames {
+        AvailableFrames::spawn(self.helper(), self.control().clone(), num_frames)
+    }
+}
+
+impl<F: IsFrameType> AudioStreamSync<Input, F> {
+    /**
+     * Read data into the supplied buffer from the started stream without blocking the thread
+     *
+     * The future resolves to the number of frames which were read, as soon as at least one
+     * frame is available, so it behaves like `AsyncRead::poll_read`.
+     *
+     * ```ignore
+     * stream.start()?;
+     * let frames = stream.read_async(&mut buffer).await?;
+     * ```
+     */
+    #[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "async")))]
+    pub fn read_async<'a>(&'a mut self, buffer: F::BufferMut<'a>) -> ReadFrames<'a, F> {
+        ReadFrames {
+            stream: self,
+            buffer: Some(buffer),
+            ready: None,
+        }
+    }
+}
+
+impl<F: IsFrameType> AudioStreamSync<Output, F> {
+    /**
+     * Write data from the supplied buffer into the started stream without blocking the thread
+     *
+     * The future resolves to the number of frames which were written, as soon as there is
+     * space for at least one frame, so it behaves like `AsyncWrite::poll_write`.
+     *
+     * ```ignore
+     * stream.start()?;
+     * let frames = stream.write_async(&buffer).await?;
+     * ```
+     */
+    #[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "async")))]
+    pub fn write_async<'a>(&'a mut self, buffer: F::Buffer<'a>) -> WriteFrames<'a, F> {
+        WriteFrames {
+            stream: self,
+            buffer: Some(buffer),
+            ready: None,
+        }
+    }
+}
+
+/**
+ * The future which resolves to the new state of the stream
+ *
+ * It is created by [`AudioStreamSync::state_changed`] or [`AudioStreamAsync::state_changed`].
+ */
+#[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "async")))]
+#[derive(Debug)]
+pub struct StateChanged {
+    wait: Wait<Result<StreamState>>,
+}
+
+impl StateChanged {
+    fn spawn<D: 'static>(
+        helper: &WaitHelper,
+        control: StreamControl<D>,
+        input_state: StreamState,
+    ) -> Self {
+        let interval = burst_duration(&mut *control.lock());
+        let wait = helper.spawn(Err(Error::Closed), interval, move || {
+            match control.lock().wait_for_state_change(input_state, 0) {
+                Ok(state) if state == input_state => None,
+                Err(Error::Timeout) => None,
+                result => Some(result),
+            }
+        });
+        Self { wait }
+    }
+}
+
+impl Future for StateChanged {
+    type Output = Result<StreamState>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        self.wait.poll(cx)
+    }
+}
+
+/**
+ * The future which resolves to the number of frames available in the buffer of the stream
+ *
+ * It is created by [`AudioStreamSync::available_frames`] or [`AudioStreamAsync::available_frames`].
+ */
+#[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "async")))]
+#[derive(Debug)]
+pub struct AvailableFrames {
+    wait: Wait<Result<i32>>,
+}
+
+impl AvailableFrames {
+    fn spawn<D: 'static>(helper: &WaitHelper, control: StreamControl<D>, num_frames: i32) -> Self {
+        let interval = burst_duration(&mut *control.lock());
+        let wait = helper.spawn(Err(Error::Closed), interval, move || {
+            match control.lock().wait_for_available_frames(num_frames, 0) {
+                Ok(frames) if frames < num_frames => None,
+                Err(Error::Timeout) => None,
+                result => Some(result),
+            }
+        });
+        Self { wait }
+    }
+}
+
+impl Future for AvailableFrames {
+    type Output = Result<i32>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        self.wait.poll(cx)
+    }
+}
+
+/**
+ * The future which resolves to the number of frames read from the stream
+ *
+ * It is created by [`AudioStreamSync::read_async`].
+ */
+#[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "async")))]
+pub struct ReadFrames<'a, F: IsFrameType + 'a> {
+    stream: &'a mut AudioStreamSync<Input, F>,
+    buffer: Option<F::BufferMut<'a>>,
+    ready: Option<Wait<()>>,
+}
+
+impl<'a, F: IsFrameType> fmt::Debug for ReadFrames<'a, F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ReadFrames").finish_non_exhaustive()
+    }
+}
+
+impl<'a, F: IsFrameType> Unpin for ReadFrames<'a, F> {}
+
+impl<'a, F: IsFrameType> Future for ReadFrames<'a, F> {
+    type Output = Result<i32>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = &mut *self;
+        if let Some(ready) = &mut this.ready {
+            if ready.poll(cx).is_pending() {
+                return Poll::Pending;
+            }
+            this.ready = None;
+        }
+
+        // Only the owner reads from the stream, so the available frames can't disappear
+        if is_active(&*this.stream) && this.stream.get_available_frames().unwrap_or(1) == 0 {
+            let control = this.stream.control().clone();
+            let burst = burst_duration(&mut *this.stream);
+            this.ready = Some(this.stream.helper().spawn((), burst, move || {
+                let mut stream = control.lock();
+                match stream.wait_for_available_frames(1, 0) {
+                    Ok(0) | Err(Error::Timeout) if is_active(&*stream) => None,
+                    _ => Some(()),
+                }
+            }));
+            return self.poll(cx);
+        }
+
+        let buffer = this
+            .buffer
+            .take()
+            .expect("ReadFrames polled after completion");
+        Poll::Ready(this.stream.read(buffer, 0))
+    }
+}
+
+/**
+ * The future which resolves to the number of frames written to the stream
+ *
+ * It is created by [`AudioStreamSync::write_async`].
+ */
+#[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "async")))]
+pub struct WriteFrames<'a, F: IsFrameType + 'a> {
+    stream: &'a mut AudioStreamSync<Output, F>,
+    buffer: Option<F::Buffer<'a>>,
+    ready: Option<Wait<()>>,
+}
+
+impl<'a, F: IsFrameType> fmt::Debug for WriteFrames<'a, F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WriteFrames").finish_non_exhaustive()
+    }
+}
+
+impl<'a, F: IsFrameType> Unpin for WriteFrames<'a, F> {}
+
+impl<'a, F: IsFrameType> Future for WriteFrames<'a, F> {
+    type Output = Result<i32>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = &mut *self;
+        if let Some(ready) = &mut this.ready {
+            if ready.poll(cx).is_pending() {
+                return Poll::Pending;
+            }
+            this.ready = None;
+        }
+
+        // Only the owner writes to the stream, so the free space can't disappear
+        if is_active(&*this.stream) && !has_space(&mut *this.stream) {
+            let control = this.stream.control().clone();
+            let burst = burst_duration(&mut *this.stream);
+            this.ready = Some(this.stream.helper().spawn((), burst, move || {
+                let mut stream = control.lock();
+                (!is_active(&*stream) || has_space(&mut *stream)).then_some(())
+            }));
+            return self.poll(cx);
+        }
+
+        let buffer = this
+            .buffer
+            .take()
+            .expect("WriteFrames polled after completion");
+        Poll::Ready(this.stream.write(buffer, 0))
+    }
+}
+
+/**
+ * The stream transfers the frames, so it is worth waiting for them
+ */
+fn is_active(stream: &dyn AudioStreamSafe) -> bool {
+    matches!(
+        stream.get_state(),
+        StreamState::Starting | StreamState::Started
+    )
+}
+
+/**
+ * The buffer of the output stream can accept at least one frame
+ */
+fn has_space(stream: &mut dyn AudioStreamSafe) -> bool {
+    match stream.get_available_frames() {
+        Ok(frames) => frames < stream.get_buffer_size_in_frames(),
+        Err(_) => true,
+    }
+}
+
+/**
+ * The time which the stream takes to consume the single burst of frames
+ *
+ * The state and the buffer of the stream change once per burst, so the waits check them
+ * at this interval.
+ */
+fn burst_duration(stream: &mut dyn AudioStreamSafe) -> Duration {
+    let (burst, sample_rate) = (stream.get_frames_per_burst(), stream.get_sample_rate());
+    if burst > 0 && sample_rate > 0 {
+        Duration::from_nanos((burst as i64 * NANOS_PER_SECOND / sample_rate as i64) as u64)
+    } else {
+        Duration::from_nanos(NANOS_PER_MILLISECOND as u64)
+    }
+}
+
+/**
+ * The helper thread which runs the waits of the futures of the stream
+ *
+ * The thread is spawned on the first wait and repeats the non-blocking step of each pending
+ * wait at the interval of the wait, so each stream has a single helper however many futures
+ * wait on it. The helper sleeps until the next step is due or another wait is added,
+ * and the steps take the lock of the stream just for the check.
+ * The stream joins the helper when it is dropped, after it is closed.
+ */
+#[derive(Default)]
+pub(crate) struct WaitHelper {
+    thread: Mutex<Option<Helper>>,
+}
+
+struct Helper {
+    jobs: Arc<Jobs>,
+    thread: JoinHandle<()>,
+}
+
+/**
+ * The step of the wait which gets whether the helper is stopped and returns whether
+ * the wait is over
+ */
+type Step = Box<dyn FnMut(bool) -> bool + Send>;
+
+struct Job {
+    step: Step,
+    interval: Duration,
+    due: Instant,
+}
+
+impl Job {
+    /**
+     * Run the step and schedule the next one unless the wait is over
+     */
+    fn run(&mut self, stopped: bool) -> bool {
+        let over = (self.step)(stopped);
+        self.due = Instant::now() + self.interval;
+        over
+    }
+}
+
+#[derive(Default)]
+struct Jobs {
+    queue: Mutex<JobQueue>,
+    added: Condvar,
+}
+
+#[derive(Default)]
+struct JobQueue {
+    jobs: Vec<Job>,
+    stopped: bool,
+}
+
+impl WaitHelper {
+    /**
+     * Repeat the step on the helper thread at the interval until it returns the result
+     * or the wait is dropped
+     *
+     * The step must not block, because the helper runs the steps of all waits.
+     * The wait resolves to `abandoned` when the stream is dropped before the step returns
+     * the result.
+     */
+    pub(crate) fn spawn<T: Send + 'static>(
+        &self,
+        abandoned: T,
+        interval: Duration,
+        mut step: impl FnMut() -> Option<T> + Send + 'static,
+    ) -> Wait<T> {
+        let shared = Arc::new(Shared {
+            state: Mutex::new(WaitState {
+                output: None,
+                waker: None,
+            }),
+            cancelled: AtomicBool::new(false),
+        });
+        let wait = shared.clone();
+        let mut abandoned = Some(abandoned);
+
+        let step: Step = Box::new(move |stopped| {
+            if wait.cancelled.load(Ordering::Acquire) {
+                return true;
+            }
+            match step().or_else(|| if stopped { abandoned.take() } else { None }) {
+                Some(output) => {
+                    wait.complete(output);
+                    true
+                }
+                None => false,
+            }
+        });
+
+        let mut helper = self
+            .thread
+            .lock()
+            .unwrap_or_else(|error| error.into_inner());
+        let helper = helper.get_or_insert_with(|| {
+            let jobs = Arc::new(Jobs::default());
+            let queue = jobs.clone();
+            let thread = thread::spawn(move || queue.run());
+            Helper { jobs, thread }
+        });
+        helper.jobs.lock().jobs.push(Job {
+            step,
+            interval,
+            due: Instant::now(),
+        });
+        helper.jobs.added.notify_one();
+
+        Wait { shared }
+    }
+}
+
+impl Drop for WaitHelper {
+    fn drop(&mut self) {
+        let helper = self
+            .thread
+            .get_mut()
+            .unwrap_or_else(|error| error.into_inner())
+            .take();
+        if let Some(Helper { jobs, thread }) = helper {
+            jobs.lock().stopped = true;
+            jobs.added.notify_one();
+            let _ = thread.join();
+        }
+    }
+}
+
+impl Jobs {
+    fn lock(&self) -> MutexGuard<'_, JobQueue> {
+        self.queue.lock().unwrap_or_else(|error| error.into_inner())
+    }
+
+    /**
+     * Run the due steps until the helper is stopped, then run the last step of each pending wait
+     */
+    fn run(&self) {
+        let mut queue = self.lock();
+        loop {
+            let now = Instant::now();
+            let stopped = queue.stopped;
+            let (mut due, pending): (Vec<_>, Vec<_>) = mem::take(&mut queue.jobs)
+                .into_iter()
+                .partition(|job| stopped || job.due <= now);
+            queue.jobs = pending;
+
+            if !due.is_empty() {
+                // The steps take the locks of the streams, so the waits can be added meanwhile
+                drop(queue);
+                due.retain_mut(|job| !job.run(stopped));
+                queue = self.lock();
+                queue.jobs.append(&mut due);
+                continue;
+            }
+
+            queue = match queue.jobs.iter().map(|job| job.due).min() {
+                None if stopped => return,
+                None => self
+                    .added
+                    .wait(queue)
+                    .unwrap_or_else(|error| error.into_inner()),
+                Some(next) => {
+                    self.added
+                        .wait_timeout(queue, next - now)
+                        .unwrap_or_else(|error| error.into_inner())
+                        .0
+                }
+            };
+        }
+    }
+}
+
+/**
+ * The result of the operation which runs on the helper thread
+ *
+ * The helper stops repeating the operation when the wait is dropped.
+ */
+pub(crate) struct Wait<T> {
+    shared: Arc<Shared<T>>,
+}
+
+struct Shared<T> {
+    state: Mutex<WaitState<T>>,
+    cancelled: AtomicBool,
+}
+
+struct WaitState<T> {
+    output: Option<T>,
+    waker: Option<Waker>,
+}
+
+impl<T> Wait<T> {
+    fn poll(&mut self, cx: &mut Context<'_>) -> Poll<T> {
+        let mut state = self.shared.lock();
+        match state.output.take() {
+            Some(output) => Poll::Ready(output),
+            None => {
+                state.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
+
+impl<T> Shared<T> {
+    fn lock(&self) -> MutexGuard<'_, WaitState<T>> {
+        self.state.lock().unwrap_or_else(|error| error.into_inner())
+    }
+
+    /**
+     * Store the output and wake the task which polled the wait, if any
+     *
+     * The output is stored under the same lock as the waker, so the wakeup can't be lost.
+     */
+    fn complete(&self, output: T) {
+        let waker = {
+            let mut state = self.lock();
+            state.output = Some(output);
+            state.waker.take()
+        };
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+    }
+}
+
+impl<T> fmt::Debug for Wait<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Wait")
+            .field("ready", &self.shared.lock().output.is_some())
+            .finish()
+    }
+}
+
+impl<T> Drop for Wait<T> {
+    fn drop(&mut self) {
+        self.shared.cancelled.store(true, Ordering::Release);
+    }
+}
+
+#[cfg(all(test, not(target_os = "android")))]
+mod tests {
+    use super::*;
+    use crate::{
+        fake_stream::{sync_stream, FakeStream},
+        AudioFormat, Direction,
+    };
+    use std::{
+        collections::HashSet,
+        sync::atomic::AtomicUsize,
+        task::Wake,
+        time::{Duration, Instant},
+    };
+
+    const STEP: Duration = Duration::from_millis(1);
+
+    /// The waker which counts the wakeups
+    #[derive(Default)]
+    struct Wakes(AtomicUsize);
+
+    impl Wake for Wakes {
+        fn wake(self: Arc<Self>) {
+            self.0.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    impl Wakes {
+        fn count(&self) -> usize {
+            self.0.load(Ordering::SeqCst)
+        }
+
+        fn poll<T>(self: &Arc<Self>, future: &mut (impl Future<Output = T> + Unpin)) -> Poll<T> {
+            let waker = Waker::from(self.clone());
+            Pin::new(future).poll(&mut Context::from_waker(&waker))
+        }
+    }
+
+    impl<T> Future for Wait<T> {
+        type Output = T;
+
+        fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<T> {
+            Wait::poll(&mut self, cx)
+        }
+    }
+
+    fn until(mut ready: impl FnMut() -> bool) {
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while !ready() {
+            assert!(
+                Instant::now() < deadline,
+                "The condition wasn't met in time"
+            );
+            thread::sleep(Duration::from_millis(1));
+        }
+    }
+
+    #[test]
+    fn wait_completes_after_wake() {
+        let helper = WaitHelper::default();
+        let release = Arc::new(AtomicBool::new(false));
+        let released = release.clone();
+        let mut wait = helper.spawn(0, STEP, move || {
+            released.load(Ordering::SeqCst).then_some(42)
+        });
+
+        let wakes = Arc::new(Wakes::default());
+        assert_eq!(wakes.poll(&mut wait), Poll::Pending);
+        thread::sleep(Duration::from_millis(10));
+        assert_eq!(wakes.count(), 0);
+
+        release.store(true, Ordering::SeqCst);
+        until(|| wakes.count() == 1);
+        assert_eq!(wakes.poll(&mut wait), Poll::Ready(42));
+    }
+
+    #[test]
+    fn output_before_first_poll_isnt_lost() {
+        let helper = WaitHelper::default();
+        let mut wait = helper.spawn(0, STEP, || Some(7));
+        until(|| wait.shared.lock().output.is_some());
+
+        let wakes = Arc::new(Wakes::default());
+        assert_eq!(wakes.poll(&mut wait), Poll::Ready(7));
+        assert_eq!(wakes.count(), 0);
+    }
+
+    #[test]
+    fn dropped_wait_isnt_repeated() {
+        let helper = WaitHelper::default();
+        let steps = Arc::new(AtomicUsize::new(0));
+        let counter = steps.clone();
+        let wait = helper.spawn((), STEP, move || {
+            counter.fetch_add(1, Ordering::SeqCst);
+            thread::sleep(Duration::from_millis(1));
+            None
+        });
+        until(|| steps.load(Ordering::SeqCst) > 0);
+
+        drop(wait);
+        thread::sleep(Duration::from_millis(10));
+        let count = steps.load(Ordering::SeqCst);
+        thread::sleep(Duration::from_millis(10));
+        assert_eq!(steps.load(Ordering::SeqCst), count);
+
+        // The helper has nothing to wait for, so it is joined at once
+        drop(helper);
+    }
+
+    #[test]
+    fn stopped_helper_abandons_pending_waits() {
+        let helper = WaitHelper::default();
+        let mut wait = helper.spawn(-1, STEP, || None);
+
+        let wakes = Arc::new(Wakes::default());
+        assert_eq!(wakes.poll(&mut wait), Poll::Pending);
+        drop(helper);
+        assert_eq!(wakes.count(), 1);
+        assert_eq!(wakes.poll(&mut wait), Poll::Ready(-1));
+    }
+
+    #[test]
+    fn waits_share_single_helper() {
+        let helper = WaitHelper::default();
+        let threads = Arc::new(Mutex::new(HashSet::new()));
+        let waits: Vec<_> = (0..4)
+            .map(|index| {
+                let threads = threads.clone();
+                let mut steps = 0;
+                helper.spawn(0, STEP, move || {
+                    threads.lock().unwrap().insert(thread::current().id());
+                    steps += 1;
+                    (steps > index).then_some(index)
+                })
+            })
+            .collect();
+
+        let wakes = Arc::new(Wakes::default());
+        for (index, mut wait) in waits.into_iter().enumerate() {
+            until(|| wakes.poll(&mut wait) == Poll::Ready(index));
+        }
+        assert_eq!(threads.lock().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn slow_wait_doesnt_delay_other_waits() {
+        let helper = WaitHelper::default();
+        let _slow = helper.spawn((), Duration::from_secs(3600), || None);
+        let mut steps = 0;
+        let mut fast = helper.spawn(0, STEP, move || {
+            steps += 1;
+            (steps == 3).then_some(steps)
+        });
+
+        let wakes = Arc::new(Wakes::default());
+        let started = Instant::now();
+        until(|| wakes.poll(&mut fast) == Poll::Ready(3));
+        assert!(started.elapsed() < Duration::from_secs(1));
+    }
+
+    fn fake() -> (Arc<FakeStream>, AudioStreamSync<Output, (f32, crate::Mono)>) {
+        let fake = FakeStream::new(Direction::Output, AudioFormat::F32, 1);
+        let stream = sync_stream(&fake);
+        (fake, stream)
+    }
+
+    #[test]
+    fn state_change_is_awaited() {
+        let (_fake, mut stream) = fake();
+        let mut changed = stream.state_changed(StreamState::Open);
+
+        let wakes = Arc::new(Wakes::default());
+        assert_eq!(wakes.poll(&mut changed), Poll::Pending);
+        stream.start().unwrap();
+        until(|| wakes.count() > 0);
+        assert_eq!(
+            wakes.poll(&mut changed),
+            Poll::Ready(Ok(StreamState::Started))
+        );
+    }
+
+    #[test]
+    fn stream_isnt_closed_while_awaited() {
+        let (fake, stream) = fake();
+        fake.update(|fake| fake.delay = Duration::from_millis(2));
+        let mut changed = stream.state_changed(StreamState::Open);
+        let mut available = stream.available_frames(1);
+
+        let wakes = Arc::new(Wakes::default());
+        assert_eq!(wakes.poll(&mut changed), Poll::Pending);
+        until(|| fake.lock().calls.contains(&"wait_for_available_frames"));
+        drop(stream);
+
+        assert_eq!(fake.max_active(), 1);
+        assert_eq!(
+            wakes.poll(&mut changed),
+            Poll::Ready(Ok(StreamState::Closed))
+        );
+        assert_eq!(wakes.poll(&mut available), Poll::Ready(Err(Error::Closed)));
+    }
+
+    #[test]
+    fn pending_wait_doesnt_hold_stream() {
+        let (fake, stream) = fake();
+        let mut changed = stream.state_changed(StreamState::Open);
+        let wakes = Arc::new(Wakes::default());
+        assert_eq!(wakes.poll(&mut changed), Poll::Pending);
+        until(|| fake.lock().calls.contains(&"wait_for_state_change"));
+
+        // The steps don't block, so the owner doesn't wait for them
+        let started = Instant::now();
+        for _ in 0..20 {
+            assert_eq!(stream.control().lock().get_state(), StreamState::Open);
+            thread::sleep(STEP);
+        }
+        assert!(started.elapsed() < Duration::from_millis(200));
+        assert_eq!(wakes.poll(&mut changed), Poll::Pending);
+    }
+
+    #[test]
+    fn dropped_stream_abandons_endless_wait() {
+        let (fake, stream) = fake();
+        fake.update(|fake| fake.state = StreamState::Closed);
+        let mut changed = stream.state_changed(StreamState::Closed);
+
+        let wakes = Arc::new(Wakes::default());
+        assert_eq!(wakes.poll(&mut changed), Poll::Pending);
+        drop(stream);
+        assert_eq!(wakes.poll(&mut changed), Poll::Ready(Err(Error::Closed)));
+    }
+}
@@ -0,0 +1,382 @@
+use std::{error, fmt, marker::PhantomData, ops::Deref, result};
+
+use super::{
+    AudioInputStreamSync, AudioOutputStream, AudioOutputStreamSync, AudioStream, AudioStreamSafe,
+    Error, IsFrameType, Result, Status, StreamState,
+};
+
+/**
+ * The lifecycle state marker of the stream which was opened but never started
+ */
+#[derive(Debug, Clone, Copy)]
+pub struct Open;
+
+/**
+ * The lifecycle state marker of the started stream
+ */
+#[derive(Debug, Clone, Copy)]
+pub struct Started;
+
+/**
+ * The lifecycle state marker of the paused output stream
+ */
+#[derive(Debug, Clone, Copy)]
+pub struct Paused;
+
+/**
+ * The lifecycle state marker of the stopped stream
+ */
+#[derive(Debug, Clone, Copy)]
+pub struct Stopped;
+
+/**
+ * The trait for lifecycle state marker types
+ */
+pub trait IsLifecycleState {
+    /**
+     * The state of the stream in the lifecycle state
+     */
+    const STATE: StreamState;
+}
+
+impl IsLifecycleState for Open {
+    const STATE: StreamState = StreamState::Open;
+}
+
+impl IsLifecycleState for Started {
+    const STATE: StreamState = StreamState::Started;
+}
+
+impl IsLifecycleState for Paused {
+    const STATE: StreamState = StreamState::Paused;
+}
+
+impl IsLifecycleState for Stopped {
+    const STATE: StreamState = StreamState::Stopped;
+}
+
+/**
+ * The stream which tracks its lifecycle state at compile time
+ *
+ * It wraps [`AudioStreamAsync`](crate::AudioStreamAsync) or
+ * [`AudioStreamSync`](crate::AudioStreamSync) and allows only the state transitions
+ * which are valid for the current state `L`.
+ * Each transition consumes the stream and returns it in the new state,
+ * or in the original state together with the error.
+ *
+ * The stream derefs to [`AudioStreamSafe`], so it can be queried, but its state
+ * can be changed by the transitions only.
+ *
+ * ```ignore
+ * use oboe::{AudioStreamBuilder, LifecycleStream};
+ *
+ * let stream = AudioStreamBuilder::default()
+ *     .set_output()
+ *     .set_f32()
+ *     .set_stereo()
+ *     .set_callback(player)
+ *     .open_stream()?;
+ *
+ * let stream = LifecycleStream::new(stream)?;
+ * let stream = stream.start()?;
+ * let mut stream = stream.pause()?;
+ * stream.flush()?;
+ * let stream = stream.stop()?;
+ * ```
+ *
+ * The stream which was already started, paused or stopped is tracked using
+ * [`try_from_state`](Self::try_from_state):
+ *
+ * ```ignore
+ * use oboe::{LifecycleStream, Started};
+ *
+ * let stream = LifecycleStream::<_, Started>::try_from_state(started_stream)?;
+ * let stream = stream.stop()?;
+ * ```
+ */
+pub struct LifecycleStream<S, L> {
+    stream: S,
+    _state: PhantomData<L>,
+}
+
+impl<S, L> LifecycleStream<S, L> {
+    fn wrap(stream: S) -> Self {
+        Self {
+            stream,
+            _state: PhantomData,
+        }
+    }
+
+    /**
+     * Unwrap the underlying stream, so its state isn't tracked anymore
+     */
+    pub fn into_inner(self) -> S {
+        self.stream
+    }
+}
+
+impl<S: AudioStreamSafe, L> LifecycleStream<S, L> {
+    /**
+     * Get the mutable access to the stream which doesn't allow changing its state
+     */
+    pub fn safe_mut(&mut self) -> &mut dyn AudioStreamSafe {
+        &mut self.stream
+    }
+}
+
+impl<S: AudioStreamSafe + 'static, L> Deref for LifecycleStream<S, L> {
+    type Target = dyn AudioStreamSafe;
+
+    fn deref(&self) -> &Self::Target {
+        &self.stream
+    }
+}
+
+impl<S: fmt::Debug, L> fmt::Debug for LifecycleStream<S, L> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&self.stream, f)
+    }
+}
+
+/**
+ * The result of the state transition of [`LifecycleStream`]
+ */
+pub type TransitionResult<S, Prev, Next> =
+    result::Result<LifecycleStream<S, Next>, TransitionError<LifecycleStream<S, Prev>>>;
+
+/**
+ * The failed state transition of [`LifecycleStream`] which hands back the original stream
+ */
+pub struct TransitionError<T> {
+    /**
+     * The error of the transition
+     */
+    pub error: Error,
+
+    /**
+     * The stream in its original state
+     */
+    pub stream: T,
+}
+
+impl<T> fmt::Debug for TransitionError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TransitionError")
+            .field("error", &self.error)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<T> fmt::Display for TransitionError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        "Stream state transition failed: ".fmt(f)?;
+        fmt::Display::fmt(&self.error, f)
+    }
+}
+
+impl<T> error::Error for TransitionError<T> {}
+
+impl<T> From<TransitionError<T>> for Error {
+    fn from(error: TransitionError<T>) -> Self {
+        error.error
+    }
+}
+
+impl<S: AudioStream, L> LifecycleStream<S, L> {
+    fn transit<Next>(
+        mut self,
+        change: impl FnOnce(&mut S) -> Status,
+    ) -> TransitionResult<S, L, Next> {
+        match change(&mut self.stream) {
+            Ok(()) => Ok(LifecycleStream::wrap(self.stream)),
+            Err(error) => Err(TransitionError {
+                error,
+                stream: self,
+            }),
+        }
+    }
+}
+
+impl<S: AudioStream, L: IsLifecycleState> LifecycleStream<S, L> {
+    /**
+     * Start tracking the state of the stream which is in the state `L`
+     *
+     * Fails with [`Error::InvalidState`] when the stream is in another state.
+     */
+    pub fn try_from_state(stream: S) -> result::Result<Self, TransitionError<S>> {
+        if stream.get_state() == L::STATE {
+            Ok(Self::wrap(stream))
+        } else {
+            Err(TransitionError {
+                error: Error::InvalidState,
+                stream,
+            })
+        }
+    }
+}
+
+impl<S: AudioStream> LifecycleStream<S, Open> {
+    /**
+     * Start tracking the state of the opened stream
+     *
+     * Fails with [`Error::InvalidState`] when the stream was started already.
+     */
+    pub fn new(stream: S) -> result::Result<Self, TransitionError<S>> {
+        Self::try_from_state(stream)
+    }
+
+    /**
+     * Start the stream. This will block until the stream has been started.
+     */
+    pub fn start(self) -> TransitionResult<S, Open, Started> {
+        self.transit(AudioStream::start)
+    }
+}
+
+impl<S: AudioStream> LifecycleStream<S, Started> {
+    /**
+     * Stop the stream. This will block until the stream has been stopped.
+     */
+    pub fn stop(self) -> TransitionResult<S, Started, Stopped> {
+        self.transit(AudioStream::stop)
+    }
+}
+
+impl<S: AudioOutputStream> LifecycleStream<S, Started> {
+    /**
+     * Pause the stream. This will block until the stream has been paused.
+     */
+    pub fn pause(self) -> TransitionResult<S, Started, Paused> {
+        self.transit(AudioOutputStream::pause)
+    }
+}
+
+impl<S: AudioInputStreamSync> LifecycleStream<S, Started> {
+    /**
+     * Read data into the supplied buffer from the stream.
+     *
+     * See [`AudioInputStreamSync::read`].
+     */
+    pub fn read(
+        &mut self,
+        buffer: <S::FrameType as IsFrameType>::BufferMut<'_>,
+        timeout_nanoseconds: i64,
+    ) -> Result<i32> {
+        self.stream.read(buffer, timeout_nanoseconds)
+    }
+}
+
+impl<S: AudioOutputStreamSync> LifecycleStream<S, Started> {
+    /**
+     * Write data from the supplied buffer into the stream.
+     *
+     * See [`AudioOutputStreamSync::write`].
+     */
+    pub fn write(
+        &mut self,
+        buffer: <S::FrameType as IsFrameType>::Buffer<'_>,
+        timeout_nanoseconds: i64,
+    ) -> Result<i32> {
+        self.stream.write(buffer, timeout_nanoseconds)
+    }
+}
+
+impl<S: AudioOutputStream> LifecycleStream<S, Paused> {
+    /**
+     * Resume the stream. This will block until the stream has been started.
+     */
+    pub fn start(self) -> TransitionResult<S, Paused, Started> {
+        self.transit(AudioStream::start)
+    }
+
+    /**
+     * Stop the stream. This will block until the stream has been stopped.
+     */
+    pub fn stop(self) -> TransitionResult<S, Paused, Stopped> {
+        self.transit(AudioStream::stop)
+    }
+
+    /**
+     * Discard the data which was written but not played yet.
+     * This will block until the stream has been flushed.
+     *
+     * The flushed stream stays paused.
+     */
+    pub fn flush(&mut self) -> Status {
+        self.stream.flush()
+    }
+}
+
+impl<S: AudioStream> LifecycleStream<S, Stopped> {
+    /**
+     * Start the stream again. This will block until the stream has been started.
+     */
+    pub fn start(self) -> TransitionResult<S, Stopped, Started> {
+        self.transit(AudioStream::start)
+    }
+}
+
+#[cfg(all(test, not(target_os = "android")))]
+mod tests {
+    use super::*;
+    use crate::{
+        fake_stream::{sync_stream, FakeStream},
+        AudioFormat, AudioStreamSync, Direction, Mono, Output,
+    };
+    use std::sync::Arc;
+
+    type Stream = AudioStreamSync<Output, (f32, Mono)>;
+
+    fn fake_in(state: StreamState) -> (Arc<FakeStream>, Stream) {
+        let fake = FakeStream::new(Direction::Output, AudioFormat::F32, 1);
+        fake.update(|fake| fake.state = state);
+        let stream = sync_stream(&fake);
+        (fake, stream)
+    }
+
+    #[test]
+    fn new_accepts_opened_stream_only() {
+        let (_fake, stream) = fake_in(StreamState::Open);
+        assert!(LifecycleStream::new(stream).is_ok());
+
+        let (_fake, stream) = fake_in(StreamState::Started);
+        let error = LifecycleStream::new(stream).unwrap_err();
+        assert_eq!(error.error, Error::InvalidState);
+        assert_eq!(error.stream.get_state(), StreamState::Started);
+    }
+
+    #[test]
+    fn started_stream_can_be_stopped() {
+        let (fake, stream) = fake_in(StreamState::Started);
+        let stream = LifecycleStream::<_, Started>::try_from_state(stream).unwrap();
+        let stream = stream.stop().unwrap();
+        assert_eq!(stream.get_state(), StreamState::Stopped);
+        assert_eq!(fake.lock().state, StreamState::Stopped);
+    }
+
+    #[test]
+    fn paused_stream_can_be_resumed() {
+        let (_fake, stream) = fake_in(StreamState::Paused);
+        let stream = LifecycleStream::<_, Paused>::try_from_state(stream).unwrap();
+        let stream = stream.start().unwrap();
+        assert_eq!(stream.get_state(), StreamState::Started);
+    }
+
+    #[test]
+    fn stopped_stream_can_be_restarted() {
+        let (_fake, stream) = fake_in(StreamState::Stopped);
+        let stream = LifecycleStream::<_, Stopped>::try_from_state(stream).unwrap();
+        let stream = stream.start().unwrap();
+        assert_eq!(stream.get_state(), StreamState::Started);
+    }
+
+    #[test]
+    fn stream_in_other_state_is_handed_back() {
+        for state in [StreamState::Open, StreamState::Paused, StreamState::Closed] {
+            let (_fake, stream) = fake_in(state);
+            let error = LifecycleStream::<_, Stopped>::try_from_state(stream).unwrap_err();
+            assert_eq!(error.error, Error::InvalidState);
+            assert_eq!(error.stream.get_state(), state);
+        }
+    }
+}
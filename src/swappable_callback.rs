@@ -0,0 +1,404 @@
+use std::{
+    fmt,
+    marker::PhantomData,
+    ptr::null_mut,
+    sync::{
+        atomic::{AtomicPtr, Ordering},
+        Arc,
+    },
+};
+
+use super::{
+    AudioInputCallback, AudioInputStreamSafe, AudioOutputCallback, AudioOutputStreamSafe,
+    CallbackInfo, DataCallbackResult, Error, IsFrameType,
+};
+
+/**
+ * The callback adapter which allows replacing the processor without reopening the stream
+ *
+ * The new processor is installed through [`SwapHandle`] and picked up by the audio thread
+ * at the start of the next callback. Picking up is wait-free and never allocates or frees
+ * memory, because the replaced processor is handed back to the control thread,
+ * which takes it using [`SwapHandle::take_retired`] and drops it there.
+ *
+ * The [`CallbackInfo`] is computed when the initial processor wants it,
+ * because it is checked once when the callback is set.
+ *
+ * Use boxed trait objects to switch between processors of different types:
+ *
+ * ```ignore
+ * use oboe::{AudioOutputCallback, AudioStreamBuilder, Stereo, SwappableCallback};
+ *
+ * type Processor = Box<dyn AudioOutputCallback<FrameType = (f32, Stereo)> + Send>;
+ *
+ * let (callback, swap) = SwappableCallback::<Processor>::new(Box::new(dry_chain));
+ *
+ * let stream = AudioStreamBuilder::default()
+ *     .set_performance_mode(PerformanceMode::LowLatency)
+ *     .set_f32()
+ *     .set_stereo()
+ *     .set_callback(callback)
+ *     .open_stream()?;
+ *
+ * // Later on the control thread
+ * swap.install(Box::new(reverb_chain));
+ * // ...
+ * drop(swap.take_retired());
+ * ```
+ */
+pub struct SwappableCallback<F> {
+    current: Box<F>,
+    exchange: Arc<Exchange<F>>,
+}
+
+impl<F> SwappableCallback<F> {
+    /**
+     * Create the adapter with the initial processor and the handle to replace it
+     */
+    pub fn new(processor: F) -> (Self, SwapHandle<F>) {
+        let exchange = Arc::new(Exchange {
+            incoming: AtomicPtr::new(null_mut()),
+            retired: AtomicPtr::new(null_mut()),
+            _phantom: PhantomData,
+        });
+        let callback = Self {
+            current: Box::new(processor),
+            exchange: exchange.clone(),
+        };
+        (callback, SwapHandle { exchange })
+    }
+
+    /// Pick up the installed processor, when the previous one can be retired
+    fn update(&mut self) {
+        let exchange = &*self.exchange;
+        // Only the audio thread sets the retired processor, so it stays empty until it is set below
+        if exchange.retired.load(Ordering::Acquire).is_null() {
+            let incoming = exchange.incoming.swap(null_mut(), Ordering::AcqRel);
+            if !incoming.is_null() {
+                // SAFETY: The pointer was created by `Box::into_raw` and it is owned exclusively now.
+                let previous =
+                    std::mem::replace(&mut self.current, unsafe { Box::from_raw(incoming) });
+                exchange
+                    .retired
+                    .store(Box::into_raw(previous), Ordering::Release);
+            }
+        }
+    }
+}
+
+impl<F> fmt::Debug for SwappableCallback<F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SwappableCallback").finish_non_exhaustive()
+    }
+}
+
+impl<F: AudioInputCallback> AudioInputCallback for SwappableCallback<F> {
+    type FrameType = F::FrameType;
+
+    fn wants_callback_info(&self) -> bool {
+        self.current.wants_callback_info()
+    }
+
+    fn on_error_before_close(&mut self, audio_stream: &mut dyn AudioInputStreamSafe, error: Error) {
+        self.current.on_error_before_close(audio_stream, error);
+    }
+
+    fn on_error_after_close(&mut self, audio_stream: &mut dyn AudioInputStreamSafe, error: Error) {
+        self.current.on_error_after_close(audio_stream, error);
+    }
+
+    fn on_audio_ready(
+        &mut self,
+        audio_stream: &mut dyn AudioInputStreamSafe,
+        audio_data: <Self::FrameType as IsFrameType>::Buffer<'_>,
+    ) -> DataCallbackResult {
+        self.update();
+        self.current.on_audio_ready(audio_stream, audio_data)
+    }
+
+    fn on_audio_ready_with_info(
+        &mut self,
+        audio_stream: &mut dyn AudioInputStreamSafe,
+        info: &CallbackInfo,
+        audio_data: <Self::FrameType as IsFrameType>::Buffer<'_>,
+    ) -> DataCallbackResult {
+        self.update();
+        self.current
+            .on_audio_ready_with_info(audio_stream, info, audio_data)
+    }
+}
+
+impl<F: AudioOutputCallback> AudioOutputCallback for SwappableCallback<F> {
+    type FrameType = F::FrameType;
+
+    fn wants_callback_info(&self) -> bool {
+        self.current.wants_callback_info()
+    }
+
+    fn on_error_before_close(
+        &mut self,
+        audio_stream: &mut dyn AudioOutputStreamSafe,
+        error: Error,
+    ) {
+        self.current.on_error_before_close(audio_stream, error);
+    }
+
+    fn on_error_after_close(&mut self, audio_stream: &mut dyn AudioOutputStreamSafe, error: Error) {
+        self.current.on_error_after_close(audio_stream, error);
+    }
+
+    fn on_audio_ready(
+        &mut self,
+        audio_stream: &mut dyn AudioOutputStreamSafe,
+        audio_data: <Self::FrameType as IsFrameType>::BufferMut<'_>,
+    ) -> DataCallbackResult {
+        self.update();
+        self.current.on_audio_ready(audio_stream, audio_data)
+    }
+
+    fn on_audio_ready_with_info(
+        &mut self,
+        audio_stream: &mut dyn AudioOutputStreamSafe,
+        info: &CallbackInfo,
+        audio_data: <Self::FrameType as IsFrameType>::BufferMut<'_>,
+    ) -> DataCallbackResult {
+        self.update();
+        self.current
+            .on_audio_ready_with_info(audio_stream, info, audio_data)
+    }
+}
+
+/**
+ * The control-side handle of [`SwappableCallback`]
+ *
+ * It can be cloned and sent to another thread, when the processor is [`Send`].
+ */
+pub struct SwapHandle<F> {
+    exchange: Arc<Exchange<F>>,
+}
+
+impl<F> Clone for SwapHandle<F> {
+    fn clone(&self) -> Self {
+        Self {
+            exchange: self.exchange.clone(),
+        }
+    }
+}
+
+impl<F> fmt::Debug for SwapHandle<F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SwapHandle")
+            .field("pending", &self.is_pending())
+            .finish()
+    }
+}
+
+impl<F> SwapHandle<F> {
+    /**
+     * Install the processor which will be used from the next callback
+     *
+     * Returns the processor which was installed earlier but wasn't picked up yet,
+     * so it was never used.
+     *
+     * The audio thread picks up the new processor only after the previously replaced one
+     * was taken using [`take_retired`](Self::take_retired).
+     */
+    pub fn install(&self, processor: F) -> Option<F> {
+        let incoming = Box::into_raw(Box::new(processor));
+        let previous = self.exchange.incoming.swap(incoming, Ordering::AcqRel);
+        // SAFETY: The pointer was created by `Box::into_raw` and it is owned exclusively now.
+        (!previous.is_null()).then(|| *unsafe { Box::from_raw(previous) })
+    }
+
+    /**
+     * Take the processor which was replaced by the audio thread, so it is dropped
+     * outside of the callback
+     */
+    pub fn take_retired(&self) -> Option<F> {
+        let retired = self.exchange.retired.swap(null_mut(), Ordering::AcqRel);
+        // SAFETY: The pointer was created by `Box::into_raw` and it is owned exclusively now.
+        (!retired.is_null()).then(|| *unsafe { Box::from_raw(retired) })
+    }
+
+    /**
+     * Check that the installed processor wasn't picked up by the audio thread yet
+     */
+    pub fn is_pending(&self) -> bool {
+        !self.exchange.incoming.load(Ordering::Acquire).is_null()
+    }
+}
+
+/**
+ * The processors which are passed between the control thread and the audio thread
+ */
+struct Exchange<F> {
+    /// The installed processor which wasn't picked up yet
+    incoming: AtomicPtr<F>,
+    /// The replaced processor which wasn't taken yet
+    retired: AtomicPtr<F>,
+    /// The processors are owned, so the exchange isn't thread-safe by itself
+    _phantom: PhantomData<*mut F>,
+}
+
+// SAFETY: The processors are moved between threads through the atomic pointers only.
+unsafe impl<F: Send> Send for Exchange<F> {}
+unsafe impl<F: Send> Sync for Exchange<F> {}
+
+impl<F> Drop for Exchange<F> {
+    fn drop(&mut self) {
+        for processor in [self.incoming.get_mut(), self.retired.get_mut()] {
+            if !processor.is_null() {
+                // SAFETY: The pointer was created by `Box::into_raw` and nobody uses it anymore.
+                drop(unsafe { Box::from_raw(*processor) });
+            }
+        }
+    }
+}
+
+#[cfg(all(test, not(target_os = "android")))]
+mod tests {
+    use super::*;
+    use crate::{
+        fake_stream::{sync_stream, FakeStream},
+        AudioFormat, AudioStreamSync, Direction, Mono, Output,
+    };
+    use std::sync::Mutex;
+
+    /// The processor which fills the frames with its identifier and records its drop
+    struct Processor {
+        id: usize,
+        dropped: Arc<Mutex<Vec<usize>>>,
+    }
+
+    impl Drop for Processor {
+        fn drop(&mut self) {
+            self.dropped.lock().unwrap().push(self.id);
+        }
+    }
+
+    impl AudioOutputCallback for Processor {
+        type FrameType = (f32, Mono);
+
+        fn on_audio_ready(
+            &mut self,
+            _audio_stream: &mut dyn AudioOutputStreamSafe,
+            audio_data: &mut [f32],
+        ) -> DataCallbackResult {
+            audio_data.fill(self.id as f32);
+            DataCallbackResult::Continue
+        }
+    }
+
+    struct Stub {
+        stream: AudioStreamSync<Output, (f32, Mono)>,
+        dropped: Arc<Mutex<Vec<usize>>>,
+    }
+
+    impl Stub {
+        fn new() -> Self {
+            let fake = FakeStream::new(Direction::Output, AudioFormat::F32, 1);
+            Self {
+                stream: sync_stream(&fake),
+                dropped: Arc::default(),
+            }
+        }
+
+        fn processor(&self, id: usize) -> Processor {
+            Processor {
+                id,
+                dropped: self.dropped.clone(),
+            }
+        }
+
+        /// Run the callback and check that no processor was dropped inside
+        fn render(&mut self, callback: &mut SwappableCallback<Processor>) -> f32 {
+            let dropped = self.dropped();
+            let mut frames = [0.0; 4];
+            assert_eq!(
+                callback.on_audio_ready(&mut self.stream, &mut frames),
+                DataCallbackResult::Continue
+            );
+            assert_eq!(self.dropped(), dropped, "Freed in the callback");
+            frames[0]
+        }
+
+        fn dropped(&self) -> Vec<usize> {
+            self.dropped.lock().unwrap().clone()
+        }
+    }
+
+    #[test]
+    fn installed_processor_is_picked_up_and_retired() {
+        let mut stub = Stub::new();
+        let (mut callback, swap) = SwappableCallback::new(stub.processor(1));
+        assert_eq!(stub.render(&mut callback), 1.0);
+
+        assert!(swap.install(stub.processor(2)).is_none());
+        assert!(swap.is_pending());
+        assert!(swap.take_retired().is_none());
+
+        assert_eq!(stub.render(&mut callback), 2.0);
+        assert!(!swap.is_pending());
+        assert_eq!(stub.render(&mut callback), 2.0);
+
+        let retired = swap.take_retired().unwrap();
+        assert_eq!(retired.id, 1);
+        assert!(stub.dropped().is_empty());
+        drop(retired);
+        assert_eq!(stub.dropped(), [1]);
+        assert!(swap.take_retired().is_none());
+    }
+
+    #[test]
+    fn pending_processor_waits_until_retired_one_is_taken() {
+        let mut stub = Stub::new();
+        let (mut callback, swap) = SwappableCallback::new(stub.processor(1));
+
+        swap.install(stub.processor(2));
+        assert_eq!(stub.render(&mut callback), 2.0);
+
+        // The previous processor wasn't taken yet, so the next one stays pending
+        swap.install(stub.processor(3));
+        for _ in 0..3 {
+            assert_eq!(stub.render(&mut callback), 2.0);
+            assert!(swap.is_pending());
+        }
+
+        assert_eq!(swap.take_retired().unwrap().id, 1);
+        assert_eq!(stub.render(&mut callback), 3.0);
+        assert_eq!(swap.take_retired().unwrap().id, 2);
+        assert_eq!(stub.dropped(), [1, 2]);
+    }
+
+    #[test]
+    fn reinstalling_returns_processor_which_was_never_picked_up() {
+        let mut stub = Stub::new();
+        let (mut callback, swap) = SwappableCallback::new(stub.processor(1));
+
+        swap.install(stub.processor(2));
+        let unused = swap.install(stub.processor(3)).unwrap();
+        assert_eq!(unused.id, 2);
+        drop(unused);
+
+        assert_eq!(stub.render(&mut callback), 3.0);
+        assert_eq!(swap.take_retired().unwrap().id, 1);
+        assert_eq!(stub.dropped(), [2, 1]);
+    }
+
+    #[test]
+    fn exchange_drops_processors_which_werent_taken() {
+        let mut stub = Stub::new();
+        let (mut callback, swap) = SwappableCallback::new(stub.processor(1));
+        swap.install(stub.processor(2));
+        stub.render(&mut callback);
+        swap.install(stub.processor(3));
+
+        drop(callback);
+        assert_eq!(stub.dropped(), [2]);
+        drop(swap);
+
+        let mut dropped = stub.dropped();
+        dropped.sort();
+        assert_eq!(dropped, [1, 2, 3]);
+    }
+}
@@ -0,0 +1,113 @@
+use std::time::Duration;
+
+use super::DEFAULT_TIMEOUT_NANOS;
+
+/**
+ * The longest timeout which is passed to Oboe
+ *
+ * Oboe adds the timeout to the current time, so it should be far from `i64::MAX`.
+ */
+const MAX_TIMEOUT_NANOS: i64 = i64::MAX / 2;
+
+/**
+ * The timeout of the blocking stream operation
+ *
+ * It can be created from [`Duration`]:
+ *
+ * ```ignore
+ * stream.start_within(Duration::from_millis(500))?;
+ * stream.write_within(&frames, Timeout::NonBlocking)?;
+ * ```
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Timeout {
+    /**
+     * Don't wait at all
+     */
+    NonBlocking,
+
+    /**
+     * Wait until the duration has elapsed
+     */
+    Duration(Duration),
+
+    /**
+     * Wait as long as needed
+     */
+    Infinite,
+}
+
+impl Timeout {
+    /**
+     * The default timeout of the state change operations, such as `start` and `stop`
+     *
+     * See [`DEFAULT_TIMEOUT_NANOS`].
+     */
+    pub const DEFAULT: Timeout =
+        Timeout::Duration(Duration::from_nanos(DEFAULT_TIMEOUT_NANOS as u64));
+
+    /**
+     * Get the number of nanoseconds to pass to Oboe
+     *
+     * The infinite and very long timeouts are clamped to `i64::MAX / 2`,
+     * which is about 146 years, so Oboe can add them to the current time without overflow.
+     */
+    pub fn as_nanos(self) -> i64 {
+        match self {
+            Timeout::NonBlocking => 0,
+            Timeout::Duration(duration) => {
+                duration.as_nanos().min(MAX_TIMEOUT_NANOS as u128) as i64
+            }
+            Timeout::Infinite => MAX_TIMEOUT_NANOS,
+        }
+    }
+
+    /**
+     * Check that the operation doesn't wait
+     */
+    pub fn is_non_blocking(self) -> bool {
+        self.as_nanos() == 0
+    }
+}
+
+impl Default for Timeout {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+impl From<Duration> for Timeout {
+    fn from(duration: Duration) -> Self {
+        Timeout::Duration(duration)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn nanos_dont_overflow_deadline() {
+        let now = i64::MAX / 4;
+        for timeout in [
+            Timeout::Infinite,
+            Timeout::Duration(Duration::MAX),
+            Timeout::Duration(Duration::from_nanos(u64::MAX)),
+        ] {
+            assert_eq!(timeout.as_nanos(), MAX_TIMEOUT_NANOS);
+            assert!(now.checked_add(timeout.as_nanos()).is_some());
+        }
+    }
+
+    #[test]
+    fn nanos_of_short_timeouts() {
+        assert_eq!(Timeout::NonBlocking.as_nanos(), 0);
+        assert_eq!(
+            Timeout::from(Duration::from_millis(3)).as_nanos(),
+            3_000_000
+        );
+        assert_eq!(Timeout::default().as_nanos(), DEFAULT_TIMEOUT_NANOS);
+        assert!(Timeout::from(Duration::ZERO).is_non_blocking());
+        assert!(!Timeout::Infinite.is_non_blocking());
+    }
+}
@@ -0,0 +1,252 @@
+use std::time::{Duration, Instant, SystemTime};
+
+use super::{AudioApi, FrameTimestamp, NANOS_PER_SECOND};
+
+/**
+ * The system clock which is used for the stream timestamps
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Clock {
+    /**
+     * The monotonic time since boot, which doesn't count the time in suspend (`CLOCK_MONOTONIC`)
+     *
+     * It is the clock of [`Instant`]. Default.
+     */
+    #[default]
+    Monotonic,
+
+    /**
+     * The monotonic time since boot, which includes the time in suspend (`CLOCK_BOOTTIME`)
+     */
+    Boottime,
+
+    /**
+     * The wall-clock time since the Unix epoch (`CLOCK_REALTIME`)
+     *
+     * It is the clock of [`SystemTime`].
+     */
+    Realtime,
+
+    /**
+     * The monotonic time which isn't adjusted by NTP (`CLOCK_MONOTONIC_RAW`)
+     */
+    MonotonicRaw,
+}
+
+impl Clock {
+    /**
+     * Get the raw `clockid_t` value
+     */
+    pub fn clock_id(self) -> i32 {
+        match self {
+            Clock::Realtime => libc::CLOCK_REALTIME,
+            Clock::Monotonic => libc::CLOCK_MONOTONIC,
+            Clock::MonotonicRaw => libc::CLOCK_MONOTONIC_RAW,
+            Clock::Boottime => libc::CLOCK_BOOTTIME,
+        }
+    }
+
+    /**
+     * Check that the stream timestamps of the audio API can be obtained on this clock
+     *
+     * AAudio supports `Monotonic` and `Boottime` clocks only.
+     * OpenSL ES doesn't support timestamps at all.
+     */
+    pub fn is_supported_by(self, audio_api: AudioApi) -> bool {
+        match audio_api {
+            AudioApi::AAudio => matches!(self, Clock::Monotonic | Clock::Boottime),
+            _ => false,
+        }
+    }
+
+    /**
+     * Get the current time of the clock
+     */
+    pub fn now(self) -> Duration {
+        let mut time = libc::timespec {
+            tv_sec: 0,
+            tv_nsec: 0,
+        };
+        // SAFETY: The clock identifiers are valid and `time` is a valid pointer.
+        unsafe { libc::clock_gettime(self.clock_id(), &mut time) };
+        Duration::new(time.tv_sec as u64, time.tv_nsec as u32)
+    }
+}
+
+/**
+ * The position of the stream and the time on the clock when the frame at the position
+ * entered or left the audio processing pipeline
+ *
+ * It can be obtained using
+ * [`AudioStreamSafe::get_timestamp_for_clock`](crate::AudioStreamSafe::get_timestamp_for_clock).
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Timestamp {
+    /**
+     * The position in number of frames
+     */
+    pub position: i64,
+
+    /**
+     * The time since the start of the clock
+     */
+    pub time: Duration,
+
+    /**
+     * The clock of the time
+     */
+    pub clock: Clock,
+}
+
+impl Timestamp {
+    pub(crate) fn new(timestamp: FrameTimestamp, clock: Clock) -> Self {
+        Self {
+            position: timestamp.position,
+            time: Duration::from_nanos(timestamp.timestamp.max(0) as u64),
+            clock,
+        }
+    }
+
+    /**
+     * Estimate the time on the clock of the frame at `frame_position`
+     *
+     * Returns the time of timestamp when the sample rate is unknown.
+     */
+    pub fn time_of_frame(&self, frame_position: i64, sample_rate: i32) -> Duration {
+        if sample_rate <= 0 {
+            return self.time;
+        }
+        let frames = frame_position as i128 - self.position as i128;
+        let nanos =
+            self.time.as_nanos() as i128 + frames * NANOS_PER_SECOND as i128 / sample_rate as i128;
+        Duration::from_nanos(nanos.clamp(0, u64::MAX as i128) as u64)
+    }
+
+    /**
+     * Estimate the position of the frame at the time on the clock
+     *
+     * Returns the position of timestamp when the sample rate is unknown.
+     */
+    pub fn frame_at_time(&self, time: Duration, sample_rate: i32) -> i64 {
+        if sample_rate <= 0 {
+            return self.position;
+        }
+        let nanos = time.as_nanos() as i128 - self.time.as_nanos() as i128;
+        let position =
+            self.position as i128 + nanos * sample_rate as i128 / NANOS_PER_SECOND as i128;
+        position.clamp(i64::MIN as i128, i64::MAX as i128) as i64
+    }
+
+    /**
+     * Get the time as [`Instant`]
+     *
+     * Returns `None` when the clock isn't [`Clock::Monotonic`].
+     */
+    pub fn to_instant(&self) -> Option<Instant> {
+        if self.clock != Clock::Monotonic {
+            return None;
+        }
+        let (now, clock_now) = (Instant::now(), self.clock.now());
+        if self.time <= clock_now {
+            now.checked_sub(clock_now - self.time)
+        } else {
+            now.checked_add(self.time - clock_now)
+        }
+    }
+
+    /**
+     * Get the time as [`SystemTime`]
+     *
+     * Returns `None` when the clock isn't [`Clock::Realtime`].
+     */
+    pub fn to_system_time(&self) -> Option<SystemTime> {
+        if self.clock != Clock::Realtime {
+            return None;
+        }
+        SystemTime::UNIX_EPOCH.checked_add(self.time)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn timestamp(position: i64, time: Duration) -> Timestamp {
+        Timestamp {
+            position,
+            time,
+            clock: Clock::Monotonic,
+        }
+    }
+
+    #[test]
+    fn frame_time_round_trips() {
+        let stamp = timestamp(48000, Duration::from_secs(10));
+        for position in [0, 1, 480, 48000, 48001, 96000, 1 << 40] {
+            for sample_rate in [8000, 44100, 48000, 192000] {
+                let time = stamp.time_of_frame(position, sample_rate);
+                let frame = stamp.frame_at_time(time, sample_rate);
+                // The time is rounded to whole nanoseconds
+                assert!(
+                    (position - 1..=position + 1).contains(&frame),
+                    "{position} at {sample_rate}: {frame}"
+                );
+            }
+        }
+        assert_eq!(stamp.time_of_frame(96000, 48000), Duration::from_secs(11));
+        assert_eq!(stamp.frame_at_time(Duration::from_secs(9), 48000), 0);
+    }
+
+    #[test]
+    fn unknown_sample_rate_keeps_timestamp() {
+        let stamp = timestamp(480, Duration::from_secs(1));
+        for sample_rate in [0, -1, i32::MIN] {
+            assert_eq!(stamp.time_of_frame(960, sample_rate), stamp.time);
+            assert_eq!(
+                stamp.frame_at_time(Duration::from_secs(2), sample_rate),
+                480
+            );
+        }
+    }
+
+    #[test]
+    fn out_of_range_estimates_saturate() {
+        let stamp = timestamp(0, Duration::from_secs(1));
+        assert_eq!(stamp.time_of_frame(i64::MIN, 48000), Duration::ZERO);
+        assert_eq!(stamp.frame_at_time(Duration::MAX, i32::MAX), i64::MAX);
+
+        let stamp = timestamp(i64::MIN, Duration::MAX);
+        assert_eq!(stamp.frame_at_time(Duration::ZERO, i32::MAX), i64::MIN);
+    }
+
+    #[test]
+    fn extreme_positions_dont_overflow() {
+        let stamp = timestamp(i64::MIN, Duration::from_secs(1));
+        assert_eq!(
+            stamp.time_of_frame(i64::MAX, 48000),
+            Duration::from_nanos(u64::MAX)
+        );
+
+        let stamp = timestamp(i64::MAX, Duration::from_secs(10));
+        assert_eq!(stamp.time_of_frame(i64::MIN, 48000), Duration::ZERO);
+        assert_eq!(
+            stamp.time_of_frame(i64::MAX - 48000, 48000),
+            Duration::from_secs(9)
+        );
+    }
+
+    #[test]
+    fn clock_is_monotonic() {
+        for clock in [Clock::Monotonic, Clock::Boottime, Clock::MonotonicRaw] {
+            let before = clock.now();
+            assert!(clock.now() >= before);
+        }
+        let realtime = Clock::Realtime.now();
+        let system = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap();
+        assert!(system.abs_diff(realtime) < Duration::from_secs(1));
+    }
+}
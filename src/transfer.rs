@@ -0,0 +1,294 @@
+use std::{error, fmt, result, time::Instant};
+
+use super::{Error, Result, Timeout};
+
+/**
+ * The result of [`write_all`](crate::AudioOutputStreamSync::write_all)
+ * and [`read_exact`](crate::AudioInputStreamSync::read_exact)
+ */
+pub type TransferResult = result::Result<(), TransferError>;
+
+/**
+ * The failed transfer of frames which reports how many frames were moved before the failure
+ *
+ * The error is [`Error::Timeout`] when the deadline has been reached
+ * and [`Error::WouldBlock`] when the non-blocking transfer couldn't move all frames at once.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TransferError {
+    /**
+     * The number of frames which were moved before the failure
+     */
+    pub frames: usize,
+
+    /**
+     * The error of the transfer
+     */
+    pub error: Error,
+}
+
+impl fmt::Display for TransferError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Transfer failed after {} frames: ", self.frames)?;
+        fmt::Display::fmt(&self.error, f)
+    }
+}
+
+impl error::Error for TransferError {}
+
+impl From<TransferError> for Error {
+    fn from(error: TransferError) -> Self {
+        error.error
+    }
+}
+
+/**
+ * Move `frames` frames until the deadline using `step`, which gets the offset of the rest
+ * of frames and the timeout in nanoseconds and returns the number of moved frames
+ */
+pub(crate) fn transfer(
+    frames: usize,
+    deadline: Timeout,
+    mut step: impl FnMut(usize, i64) -> Result<i32>,
+) -> TransferResult {
+    let deadline_at = match deadline {
+        Timeout::Duration(duration) => Instant::now().checked_add(duration),
+        _ => None,
+    };
+    let mut moved = 0;
+
+    while moved < frames {
+        let timeout = match (deadline, deadline_at) {
+            (Timeout::Duration(_), Some(deadline_at)) => {
+                Timeout::Duration(deadline_at.saturating_duration_since(Instant::now()))
+            }
+            // The deadline is too far to be represented
+            (Timeout::Duration(_), None) => Timeout::Infinite,
+            (timeout, _) => timeout,
+        };
+
+        match step(moved, timeout.as_nanos()) {
+            // The stream never moves more frames than requested, but don't trust it
+            Ok(count) if count > 0 => moved = (moved + count as usize).min(frames),
+            // The blocking step moves nothing only when its timeout has elapsed
+            Ok(_) => {
+                let error = if deadline.is_non_blocking() {
+                    Error::WouldBlock
+                } else {
+                    Error::Timeout
+                };
+                return Err(TransferError {
+                    frames: moved,
+                    error,
+                });
+            }
+            Err(error) => {
+                return Err(TransferError {
+                    frames: moved,
+                    error,
+                })
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(all(test, not(target_os = "android")))]
+mod tests {
+    use super::*;
+    use crate::{
+        fake_stream::{sync_stream, FakeStream},
+        AudioFormat, AudioInputStreamSync, AudioOutputStreamSync, AudioStreamSync, Direction,
+        Input, InterleavedBufferMut, Output, Stereo, Unspecified,
+    };
+    use std::{sync::Arc, time::Duration};
+
+    #[test]
+    fn short_steps_are_continued() {
+        let mut offsets = Vec::new();
+        let result = transfer(10, Timeout::Infinite, |offset, _| {
+            offsets.push(offset);
+            Ok((10 - offset).min(3) as i32)
+        });
+        assert_eq!(result, Ok(()));
+        assert_eq!(offsets, [0, 3, 6, 9]);
+    }
+
+    #[test]
+    fn excess_progress_is_clamped() {
+        let mut steps = 0;
+        let result = transfer(4, Timeout::Infinite, |_, _| {
+            steps += 1;
+            Ok(100)
+        });
+        assert_eq!(result, Ok(()));
+        assert_eq!(steps, 1);
+        assert_eq!(
+            transfer(0, Timeout::NonBlocking, |_, _| unreachable!()),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn no_progress_until_deadline_times_out() {
+        let mut timeouts = Vec::new();
+        let result = transfer(8, Duration::from_secs(60).into(), |offset, timeout| {
+            timeouts.push(timeout);
+            Ok(if offset == 0 { 5 } else { 0 })
+        });
+        assert_eq!(
+            result,
+            Err(TransferError {
+                frames: 5,
+                error: Error::Timeout,
+            })
+        );
+        // The rest of time is passed to each step
+        assert_eq!(timeouts.len(), 2);
+        assert!(timeouts[1] <= timeouts[0] && timeouts[1] > 0);
+    }
+
+    #[test]
+    fn no_progress_of_infinite_step_times_out() {
+        let mut timeouts = Vec::new();
+        let result = transfer(8, Timeout::Infinite, |_, timeout| {
+            timeouts.push(timeout);
+            Ok(0)
+        });
+        assert_eq!(result.unwrap_err().error, Error::Timeout);
+        assert_eq!(timeouts, [Timeout::Infinite.as_nanos()]);
+    }
+
+    #[test]
+    fn non_blocking_transfer_would_block() {
+        let mut timeouts = Vec::new();
+        let result = transfer(8, Timeout::NonBlocking, |offset, timeout| {
+            timeouts.push(timeout);
+            Ok(if offset == 0 { 3 } else { 0 })
+        });
+        assert_eq!(
+            result,
+            Err(TransferError {
+                frames: 3,
+                error: Error::WouldBlock,
+            })
+        );
+        assert_eq!(timeouts, [0, 0]);
+    }
+
+    #[test]
+    fn error_reports_moved_frames() {
+        let mut steps = [Ok(2), Ok(4), Err(Error::Disconnected)].into_iter();
+        let result = transfer(10, Timeout::Infinite, |_, _| steps.next().unwrap());
+        assert_eq!(
+            result,
+            Err(TransferError {
+                frames: 6,
+                error: Error::Disconnected,
+            })
+        );
+        assert_eq!(Error::from(result.unwrap_err()), Error::Disconnected);
+    }
+
+    fn bytes(samples: &[i16]) -> Vec<u8> {
+        samples
+            .iter()
+            .flat_map(|sample| sample.to_ne_bytes())
+            .collect()
+    }
+
+    #[test]
+    fn write_all_continues_short_writes() {
+        let fake = FakeStream::new(Direction::Output, AudioFormat::I16, 2);
+        let mut stream: AudioStreamSync<Output, (i16, Stereo)> = sync_stream(&fake);
+        fake.update(|fake| fake.script.extend([Ok(1), Ok(2)]));
+
+        let frames = [(1, 2), (3, 4), (5, 6), (7, 8), (9, 10)];
+        assert_eq!(stream.write_all(&frames, Timeout::Infinite), Ok(()));
+
+        let fake = fake.lock();
+        assert_eq!(fake.written, bytes(&[1, 2, 3, 4, 5, 6, 7, 8, 9, 10]));
+        assert_eq!(
+            fake.calls.iter().filter(|call| **call == "write").count(),
+            3
+        );
+    }
+
+    #[test]
+    fn write_all_reports_written_frames_on_disconnect() {
+        let fake = FakeStream::new(Direction::Output, AudioFormat::I16, 2);
+        let mut stream: AudioStreamSync<Output, (i16, Stereo)> = sync_stream(&fake);
+        fake.update(|fake| fake.script.extend([Ok(3), Ok(1), Err(Error::Disconnected)]));
+
+        let frames = [(0, 0); 8];
+        assert_eq!(
+            stream.write_all(&frames, Timeout::Infinite),
+            Err(TransferError {
+                frames: 4,
+                error: Error::Disconnected,
+            })
+        );
+        assert_eq!(fake.lock().written.len(), 4 * 4);
+    }
+
+    fn input() -> (Arc<FakeStream>, AudioStreamSync<Input, (i16, Unspecified)>) {
+        let fake = FakeStream::new(Direction::Input, AudioFormat::I16, 3);
+        let stream = sync_stream(&fake);
+        (fake, stream)
+    }
+
+    #[test]
+    fn read_exact_continues_short_reads() {
+        let (fake, mut stream) = input();
+        fake.update(|fake| {
+            fake.captured.extend(bytes(&[1, 2, 3, 4, 5, 6, 7, 8, 9]));
+            fake.script.extend([Ok(1), Ok(1)]);
+        });
+
+        let mut samples = [0; 9];
+        let buffer = InterleavedBufferMut::new(&mut samples, 3);
+        assert_eq!(stream.read_exact(buffer, Timeout::Infinite), Ok(()));
+        assert_eq!(samples, [1, 2, 3, 4, 5, 6, 7, 8, 9]);
+    }
+
+    #[test]
+    fn read_exact_times_out_without_input() {
+        let (fake, mut stream) = input();
+        fake.update(|fake| fake.captured.extend(bytes(&[1, 2, 3])));
+
+        let mut samples = [0; 9];
+        let buffer = InterleavedBufferMut::new(&mut samples, 3);
+        assert_eq!(
+            stream.read_exact(buffer, Timeout::DEFAULT),
+            Err(TransferError {
+                frames: 1,
+                error: Error::Timeout,
+            })
+        );
+
+        let buffer = InterleavedBufferMut::new(&mut samples, 3);
+        assert_eq!(
+            stream.read_exact(buffer, Timeout::NonBlocking),
+            Err(TransferError {
+                frames: 0,
+                error: Error::WouldBlock,
+            })
+        );
+        assert_eq!(samples[..3], [1, 2, 3]);
+    }
+
+    #[test]
+    fn buffer_of_other_layout_is_refused() {
+        let (_fake, mut stream) = input();
+        let mut samples = [0; 8];
+        let buffer = InterleavedBufferMut::new(&mut samples, 2);
+        assert_eq!(
+            stream.read_exact(buffer, Timeout::Infinite),
+            Err(TransferError {
+                frames: 0,
+                error: Error::IllegalArgument,
+            })
+        );
+    }
+}
@@ -1,4 +1,7 @@
-use super::{AudioFormat, ChannelCount, Direction};
+use super::{
+    AudioBuffer, AudioBufferMut, AudioFormat, ChannelCount, Direction, InterleavedBuffer,
+    InterleavedBufferMut, RawFrameBuffer, RawFrameBufferMut,
+};
 
 /**
  * Unspecified marker type for use everywhere
@@ -45,6 +48,97 @@ impl IsFormat for i16 {
     const FORMAT: AudioFormat = AudioFormat::I16;
 }
 
+/**
+ * The packed signed 24-bit integer sample (little-endian)
+ */
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct I24([u8; 3]);
+
+impl I24 {
+    /**
+     * The minimum value of sample
+     */
+    pub const MIN: I24 = I24::from_i32(-(1 << 23));
+
+    /**
+     * The maximum value of sample
+     */
+    pub const MAX: I24 = I24::from_i32((1 << 23) - 1);
+
+    /**
+     * Create sample from integer value
+     *
+     * The values out of range will be clipped.
+     */
+    pub const fn from_i32(value: i32) -> Self {
+        let value = if value < -(1 << 23) {
+            -(1 << 23)
+        } else if value > (1 << 23) - 1 {
+            (1 << 23) - 1
+        } else {
+            value
+        };
+        let [b0, b1, b2, _] = value.to_le_bytes();
+        Self([b0, b1, b2])
+    }
+
+    /**
+     * Get integer value of sample
+     */
+    pub const fn to_i32(self) -> i32 {
+        let [b0, b1, b2] = self.0;
+        // Shift back to extend the sign
+        i32::from_le_bytes([0, b0, b1, b2]) >> 8
+    }
+
+    /**
+     * Create sample from floating point value in range `-1.0..1.0`
+     *
+     * The values out of range will be clipped.
+     */
+    pub fn from_f32(value: f32) -> Self {
+        Self::from_i32((value.clamp(-1.0, 1.0) * 8388607.0) as i32)
+    }
+
+    /**
+     * Get floating point value of sample in range `-1.0..1.0`
+     */
+    pub fn to_f32(self) -> f32 {
+        self.to_i32() as f32 / 8388608.0
+    }
+
+    /**
+     * Create sample from packed little-endian bytes
+     */
+    pub const fn from_le_bytes(bytes: [u8; 3]) -> Self {
+        Self(bytes)
+    }
+
+    /**
+     * Get packed little-endian bytes of sample
+     */
+    pub const fn to_le_bytes(self) -> [u8; 3] {
+        self.0
+    }
+}
+
+impl From<I24> for i32 {
+    fn from(sample: I24) -> Self {
+        sample.to_i32()
+    }
+}
+
+impl From<I24> for f32 {
+    fn from(sample: I24) -> Self {
+        sample.to_f32()
+    }
+}
+
+impl IsFormat for I24 {
+    const FORMAT: AudioFormat = AudioFormat::I24;
+}
+
 impl IsFormat for i32 {
     const FORMAT: AudioFormat = AudioFormat::I32;
 }
@@ -82,34 +176,185 @@ impl IsChannelCount for Stereo {
     const CHANNEL_COUNT: ChannelCount = ChannelCount::Stereo;
 }
 
-pub enum AltFrame<T: IsFormat> {
-    Mono(T),
-    Stereo(T, T),
+/**
+ * The multichannel configuration marker for the `N` channels
+ *
+ * The frames of such streams are represented as `[T; N]`.
+ */
+pub struct Channels<const N: usize>;
+
+impl<const N: usize> IsChannelCount for Channels<N> {
+    const CHANNEL_COUNT: ChannelCount = {
+        assert!(
+            N > 0 && N <= i32::MAX as usize,
+            "Invalid number of channels"
+        );
+        ChannelCount::from_count(N as i32)
+    };
 }
 
 /**
  * The trait for frame type marker types
  */
 pub trait IsFrameType {
-    type Type;
+    /**
+     * The type of single frame
+     */
+    type Type: ?Sized;
     type Format: IsFormat;
     type ChannelCount: IsChannelCount;
+
+    /**
+     * The buffer of frames for reading
+     */
+    type Buffer<'a>: RawFrameBuffer
+    where
+        Self: 'a;
+
+    /**
+     * The buffer of frames for writing
+     */
+    type BufferMut<'a>: RawFrameBufferMut
+    where
+        Self: 'a;
 }
 
-impl<T: IsFormat> IsFrameType for (T, Unspecified) {
-    type Type = AltFrame<T>;
-    type Format = T;
+/**
+ * The frames with unspecified channel count are represented using interleaved buffers,
+ * so the number of channels is known at runtime only.
+ */
+macro_rules! interleaved_frame_type {
+    ($($type:ty),*) => {
+        $(
+            impl IsFrameType for ($type, Unspecified) {
+                type Type = [$type];
+                type Format = $type;
+                type ChannelCount = Unspecified;
+                type Buffer<'a> = InterleavedBuffer<'a, $type>;
+                type BufferMut<'a> = InterleavedBufferMut<'a, $type>;
+            }
+        )*
+    };
+}
+
+interleaved_frame_type!(i16, I24, i32, f32);
+
+/**
+ * The frame type for streams which format and channel count are known at runtime only
+ *
+ * The frames are represented using [`AudioBuffer`] and [`AudioBufferMut`].
+ */
+pub type Dynamic = (Unspecified, Unspecified);
+
+impl IsFrameType for Dynamic {
+    type Type = [u8];
+    type Format = Unspecified;
     type ChannelCount = Unspecified;
+    type Buffer<'a> = AudioBuffer<'a>;
+    type BufferMut<'a> = AudioBufferMut<'a>;
 }
 
 impl<T: IsFormat> IsFrameType for (T, Mono) {
     type Type = T;
     type Format = T;
     type ChannelCount = Mono;
+    type Buffer<'a>
+        = &'a [T]
+    where
+        T: 'a;
+    type BufferMut<'a>
+        = &'a mut [T]
+    where
+        T: 'a;
 }
 
 impl<T: IsFormat> IsFrameType for (T, Stereo) {
     type Type = (T, T);
     type Format = T;
     type ChannelCount = Stereo;
+    type Buffer<'a>
+        = &'a [(T, T)]
+    where
+        T: 'a;
+    type BufferMut<'a>
+        = &'a mut [(T, T)]
+    where
+        T: 'a;
+}
+
+impl<T: IsFormat, const N: usize> IsFrameType for (T, Channels<N>) {
+    type Type = [T; N];
+    type Format = T;
+    type ChannelCount = Channels<N>;
+    type Buffer<'a>
+        = &'a [[T; N]]
+    where
+        T: 'a;
+    type BufferMut<'a>
+        = &'a mut [[T; N]]
+    where
+        T: 'a;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn i24_extends_sign() {
+        assert_eq!(I24::from_le_bytes([0xff, 0xff, 0xff]).to_i32(), -1);
+        assert_eq!(I24::from_le_bytes([0x00, 0x00, 0x80]).to_i32(), -(1 << 23));
+        assert_eq!(
+            I24::from_le_bytes([0xff, 0xff, 0x7f]).to_i32(),
+            (1 << 23) - 1
+        );
+        assert_eq!(I24::from_le_bytes([0x00, 0x00, 0x00]).to_i32(), 0);
+        assert_eq!(I24::from_le_bytes([0x01, 0x00, 0x00]).to_i32(), 1);
+        assert_eq!(I24::from_le_bytes([0x34, 0x12, 0xfe]).to_i32(), -0x01edcc);
+    }
+
+    #[test]
+    fn i24_has_little_endian_byte_order() {
+        assert_eq!(I24::from_i32(0x123456).to_le_bytes(), [0x56, 0x34, 0x12]);
+        assert_eq!(I24::from_i32(-2).to_le_bytes(), [0xfe, 0xff, 0xff]);
+        assert_eq!(std::mem::size_of::<I24>(), 3);
+        assert_eq!(std::mem::align_of::<I24>(), 1);
+    }
+
+    #[test]
+    fn i24_clips_out_of_range_values() {
+        assert_eq!(I24::MIN.to_i32(), -8388608);
+        assert_eq!(I24::MAX.to_i32(), 8388607);
+        assert_eq!(I24::from_i32(i32::MIN), I24::MIN);
+        assert_eq!(I24::from_i32(-8388609), I24::MIN);
+        assert_eq!(I24::from_i32(i32::MAX), I24::MAX);
+        assert_eq!(I24::from_i32(8388608), I24::MAX);
+    }
+
+    #[test]
+    fn i24_round_trips() {
+        for value in [
+            -8388608, -8388607, -65536, -256, -1, 0, 1, 255, 256, 65535, 8388607,
+        ] {
+            let sample = I24::from_i32(value);
+            assert_eq!(sample.to_i32(), value);
+            assert_eq!(i32::from(sample), value);
+            assert_eq!(I24::from_le_bytes(sample.to_le_bytes()), sample);
+        }
+    }
+
+    #[test]
+    fn i24_converts_to_f32() {
+        assert_eq!(I24::MIN.to_f32(), -1.0);
+        assert_eq!(I24::default().to_f32(), 0.0);
+        assert_eq!(I24::from_i32(1 << 22).to_f32(), 0.5);
+        assert!(f32::from(I24::MAX) < 1.0);
+
+        assert_eq!(I24::from_f32(0.0), I24::default());
+        assert_eq!(I24::from_f32(1.0), I24::MAX);
+        assert_eq!(I24::from_f32(-1.0).to_i32(), -8388607);
+        assert_eq!(I24::from_f32(0.5).to_i32(), 4194303);
+        assert_eq!(I24::from_f32(4.0), I24::MAX);
+        assert_eq!(I24::from_f32(-4.0).to_i32(), -8388607);
+    }
 }
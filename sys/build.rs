@@ -12,6 +12,13 @@ fn main() {
         return;
     }
 
+    // Skip build of the native library for the host tests, which use the fake stream
+    if env::var_os("CARGO_FEATURE_TEST").is_some()
+        && !matches!(env::var("CARGO_CFG_TARGET_OS"), Ok(s) if s == "android")
+    {
+        return;
+    }
+
     let out_dir = PathBuf::from(env::var("OUT_DIR").expect("OUT_DIR is set by cargo."));
     let src_dir = Path::new("oboe");
     let ext_dir = Path::new("oboe-ext");
@@ -150,6 +157,7 @@ impl Builder {
                     .to_string(),
             )
             .allowlist_type("oboe::ChannelCount")
+            .allowlist_type("oboe::ChannelMask")
             .allowlist_type("oboe::AudioStreamBase")
             .allowlist_type("oboe::AudioStream")
             .allowlist_type("oboe::AudioStreamBuilder")
@@ -162,6 +170,7 @@ impl Builder {
             .allowlist_function("oboe::AudioStream_.+")
             .allowlist_function("oboe::AudioStreamShared_.*")
             .allowlist_function("oboe::AudioStreamCallbackWrapper_.+")
+            .allowlist_function("oboe::LatencyTunerWrapper_.+")
             .allowlist_function("oboe::getSdkVersion")
             .blocklist_type("std::.*_ptr.*")
             .blocklist_type("oboe::ManagedStream")
@@ -272,6 +281,7 @@ impl Builder {
             "AudioStreamWrapper.cpp",
             "AudioStreamBuilderWrapper.cpp",
             "AudioStreamCallbackWrapper.cpp",
+            "LatencyTunerWrapper.cpp",
         ];
 
         if env::var(format!("CXX_{}", self.target)).is_err() {
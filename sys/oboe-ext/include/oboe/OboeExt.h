@@ -17,8 +17,12 @@ namespace oboe {
                                     AudioStream *oboeStream,
                                     Result error);
 
+  typedef bool (*ErrorHandler)(void *context,
+                               AudioStream *oboeStream,
+                               Result error);
+
   class AudioStreamCallbackWrapper
-      : public AudioStreamDataCallback, public AudioStreamErrorCallback {
+      : public AudioStreamCallback {
   public:
     AudioStreamCallbackWrapper(void *context,
                                const DropContextHandler drop_context,
@@ -46,6 +50,34 @@ namespace oboe {
     const ErrorCloseHandler _after_close;
   };
 
+  class AudioStreamErrorCallbackWrapper
+      : public AudioStreamErrorCallback {
+  public:
+    AudioStreamErrorCallbackWrapper(void *context,
+                                    const DropContextHandler drop_context,
+                                    const ErrorHandler error,
+                                    const ErrorCloseHandler before_close,
+                                    const ErrorCloseHandler after_close);
+
+    ~AudioStreamErrorCallbackWrapper();
+
+    bool onError(AudioStream *oboeStream,
+                 Result error);
+
+    void onErrorBeforeClose(AudioStream *oboeStream,
+                            Result error);
+
+    void onErrorAfterClose(AudioStream *oboeStream,
+                           Result error);
+
+  private:
+    void *_context;
+    const DropContextHandler _drop_context;
+    const ErrorHandler _error;
+    const ErrorCloseHandler _before_close;
+    const ErrorCloseHandler _after_close;
+  };
+
   void AudioStreamBuilder_create(AudioStreamBuilder *builder);
   void AudioStreamBuilder_delete(AudioStreamBuilder *builder);
   void AudioStreamBuilder_setCallback(AudioStreamBuilder *builder,
@@ -54,6 +86,19 @@ namespace oboe {
                                       const AudioReadyHandler audio_ready,
                                       const ErrorCloseHandler before_close,
                                       const ErrorCloseHandler after_close);
+  void AudioStreamBuilder_setStabilizedCallback(AudioStreamBuilder *builder,
+                                                void *context,
+                                                const DropContextHandler drop_context,
+                                                const AudioReadyHandler audio_ready,
+                                                const ErrorCloseHandler before_close,
+                                                const ErrorCloseHandler after_close);
+  void AudioStreamBuilder_setErrorCallback(AudioStreamBuilder *builder,
+                                           void *context,
+                                           const DropContextHandler drop_context,
+                                           const ErrorHandler error,
+                                           const ErrorCloseHandler before_close,
+                                           const ErrorCloseHandler after_close);
+  void AudioStreamBuilder_resetErrorCallback(AudioStreamBuilder *builder);
 
   AudioApi AudioStreamBuilder_getAudioApi(const AudioStreamBuilder *builder);
   void AudioStreamBuilder_setAudioApi(AudioStreamBuilder *builder, AudioApi api);
@@ -97,6 +142,18 @@ namespace oboe {
                                              int64_t timeoutNanoseconds);
 
   AudioStreamBase* AudioStream_getBase(AudioStream *oboeStream);
+
+  class LatencyTunerWrapper;
+
+  LatencyTunerWrapper *LatencyTunerWrapper_create(AudioStream *oboeStream,
+                                                  int32_t maximumBufferSize);
+  void LatencyTunerWrapper_delete(LatencyTunerWrapper *tuner);
+  Result LatencyTunerWrapper_tune(LatencyTunerWrapper *tuner);
+  void LatencyTunerWrapper_requestReset(LatencyTunerWrapper *tuner);
+  bool LatencyTunerWrapper_isAtMaximumBufferSize(LatencyTunerWrapper *tuner);
+  void LatencyTunerWrapper_setMinimumBufferSize(LatencyTunerWrapper *tuner,
+                                                int32_t bufferSize);
+  int32_t LatencyTunerWrapper_getMinimumBufferSize(const LatencyTunerWrapper *tuner);
 }
 
 #endif
@@ -1,6 +1,17 @@
 #include "oboe/OboeExt.h"
 
 namespace oboe {
+  /// Stabilized callback which owns the wrapped callback.
+  class StabilizedCallbackWrapper: public StabilizedCallback {
+  public:
+    StabilizedCallbackWrapper(std::shared_ptr<AudioStreamCallbackWrapper> callback):
+      StabilizedCallback(callback.get()),
+      _callback(callback) {}
+
+  private:
+    std::shared_ptr<AudioStreamCallbackWrapper> _callback;
+  };
+
   /*void AudioStreamBuilder_init(AudioStreamBuilder *builder) {
     new (builder) AudioStreamBuilder();
   }
@@ -40,9 +51,55 @@ namespace oboe {
         after_close);
 
     builder->setDataCallback(s);
+    // Keep the separately registered error callback
+    if (builder->getErrorCallback() == nullptr) {
+      builder->setErrorCallback(s);
+    }
+  }
+
+  /// Takes ownership of context (drop_context will be called to free it).
+  void AudioStreamBuilder_setStabilizedCallback(AudioStreamBuilder *builder,
+                                                void *context,
+                                                const DropContextHandler drop_context,
+                                                const AudioReadyHandler audio_ready,
+                                                const ErrorCloseHandler before_close,
+                                                const ErrorCloseHandler after_close) {
+    auto s = std::make_shared<StabilizedCallbackWrapper>(
+        std::make_shared<AudioStreamCallbackWrapper>(
+            context,
+            drop_context,
+            audio_ready,
+            before_close,
+            after_close));
+
+    builder->setDataCallback(s);
+    // Keep the separately registered error callback
+    if (builder->getErrorCallback() == nullptr) {
+      builder->setErrorCallback(s);
+    }
+  }
+
+  /// Takes ownership of context (drop_context will be called to free it).
+  void AudioStreamBuilder_setErrorCallback(AudioStreamBuilder *builder,
+                                           void *context,
+                                           const DropContextHandler drop_context,
+                                           const ErrorHandler error,
+                                           const ErrorCloseHandler before_close,
+                                           const ErrorCloseHandler after_close) {
+    auto s = std::make_shared<AudioStreamErrorCallbackWrapper>(
+        context,
+        drop_context,
+        error,
+        before_close,
+        after_close);
+
     builder->setErrorCallback(s);
   }
 
+  void AudioStreamBuilder_resetErrorCallback(AudioStreamBuilder *builder) {
+    builder->setErrorCallback(std::shared_ptr<AudioStreamErrorCallback>());
+  }
+
   AudioStreamBase* AudioStreamBuilder_getBase(AudioStreamBuilder *builder) {
     return static_cast<AudioStreamBase*>(builder);
   }
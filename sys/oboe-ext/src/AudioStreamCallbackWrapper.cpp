@@ -38,4 +38,39 @@ namespace oboe {
                     Result error) {
     _after_close(_context, oboeStream, error);
   }
+
+  AudioStreamErrorCallbackWrapper::
+  AudioStreamErrorCallbackWrapper(void *context,
+                                  const DropContextHandler drop_context,
+                                  const ErrorHandler error,
+                                  const ErrorCloseHandler before_close,
+                                  const ErrorCloseHandler after_close):
+    _context(context),
+    _drop_context(drop_context),
+    _error(error),
+    _before_close(before_close),
+    _after_close(after_close) {}
+
+  AudioStreamErrorCallbackWrapper
+   ::~AudioStreamErrorCallbackWrapper() {
+     _drop_context(_context);
+  }
+
+  bool AudioStreamErrorCallbackWrapper::
+  onError(AudioStream *oboeStream,
+          Result error) {
+    return _error(_context, oboeStream, error);
+  }
+
+  void AudioStreamErrorCallbackWrapper::
+  onErrorBeforeClose(AudioStream *oboeStream,
+                     Result error) {
+    _before_close(_context, oboeStream, error);
+  }
+
+  void AudioStreamErrorCallbackWrapper::
+  onErrorAfterClose(AudioStream *oboeStream,
+                    Result error) {
+    _after_close(_context, oboeStream, error);
+  }
 }
@@ -0,0 +1,80 @@
+#include "oboe/OboeExt.h"
+
+namespace oboe {
+  /// Latency tuner which does not keep the stream alive.
+  ///
+  /// The tuner is usually owned by the data callback which is owned by the stream,
+  /// so holding a strong reference here would create a cycle.
+  class LatencyTunerWrapper {
+  public:
+    LatencyTunerWrapper(AudioStream *oboeStream,
+                        int32_t maximumBufferSize):
+      _stream(oboeStream->lockWeakThis()),
+      _tuner(*oboeStream,
+             maximumBufferSize > 0
+             ? maximumBufferSize
+             : oboeStream->getBufferCapacityInFrames()) {}
+
+    Result tune() {
+      auto stream = _stream.lock(); // keep stream alive while tuning
+      if (!stream) {
+        return Result::ErrorClosed;
+      }
+      return _tuner.tune();
+    }
+
+    void requestReset() {
+      _tuner.requestReset();
+    }
+
+    bool isAtMaximumBufferSize() {
+      auto stream = _stream.lock(); // keep stream alive while querying
+      if (!stream) {
+        return false;
+      }
+      return _tuner.isAtMaximumBufferSize();
+    }
+
+    void setMinimumBufferSize(int32_t bufferSize) {
+      _tuner.setMinimumBufferSize(bufferSize);
+    }
+
+    int32_t getMinimumBufferSize() const {
+      return _tuner.getMinimumBufferSize();
+    }
+
+  private:
+    std::weak_ptr<AudioStream> _stream;
+    LatencyTuner _tuner;
+  };
+
+  LatencyTunerWrapper *LatencyTunerWrapper_create(AudioStream *oboeStream,
+                                                  int32_t maximumBufferSize) {
+    return new LatencyTunerWrapper(oboeStream, maximumBufferSize);
+  }
+
+  void LatencyTunerWrapper_delete(LatencyTunerWrapper *tuner) {
+    delete tuner;
+  }
+
+  Result LatencyTunerWrapper_tune(LatencyTunerWrapper *tuner) {
+    return tuner->tune();
+  }
+
+  void LatencyTunerWrapper_requestReset(LatencyTunerWrapper *tuner) {
+    tuner->requestReset();
+  }
+
+  bool LatencyTunerWrapper_isAtMaximumBufferSize(LatencyTunerWrapper *tuner) {
+    return tuner->isAtMaximumBufferSize();
+  }
+
+  void LatencyTunerWrapper_setMinimumBufferSize(LatencyTunerWrapper *tuner,
+                                                int32_t bufferSize) {
+    tuner->setMinimumBufferSize(bufferSize);
+  }
+
+  int32_t LatencyTunerWrapper_getMinimumBufferSize(const LatencyTunerWrapper *tuner) {
+    return tuner->getMinimumBufferSize();
+  }
+}
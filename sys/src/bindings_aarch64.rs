@@ -1265,11 +1265,17 @@ pub type oboe_ErrorCloseHandler = ::std::option::Option<
         error: oboe_Result,
     ),
 >;
+pub type oboe_ErrorHandler = ::std::option::Option<
+    unsafe extern "C" fn(
+        context: *mut ::std::os::raw::c_void,
+        oboeStream: *mut oboe_AudioStream,
+        error: oboe_Result,
+    ) -> bool,
+>;
 #[repr(C)]
 #[derive(Debug)]
 pub struct oboe_AudioStreamCallbackWrapper {
-    pub _base: oboe_AudioStreamDataCallback,
-    pub _base_1: oboe_AudioStreamErrorCallback,
+    pub _base: oboe_AudioStreamCallback,
     pub _context: *mut ::std::os::raw::c_void,
     pub _drop_context: oboe_DropContextHandler,
     pub _audio_ready: oboe_AudioReadyHandler,
@@ -1424,6 +1430,32 @@ extern "C" {
         after_close: oboe_ErrorCloseHandler,
     );
 }
+extern "C" {
+    #[link_name = "\u{1}_ZN4oboe40AudioStreamBuilder_setStabilizedCallbackEPNS_18AudioStreamBuilderEPvPFvS2_EPFNS_18DataCallbackResultES2_PNS_11AudioStreamES2_iEPFvS2_S7_NS_6ResultEESC_"]
+    pub fn oboe_AudioStreamBuilder_setStabilizedCallback(
+        builder: *mut oboe_AudioStreamBuilder,
+        context: *mut ::std::os::raw::c_void,
+        drop_context: oboe_DropContextHandler,
+        audio_ready: oboe_AudioReadyHandler,
+        before_close: oboe_ErrorCloseHandler,
+        after_close: oboe_ErrorCloseHandler,
+    );
+}
+extern "C" {
+    #[link_name = "\u{1}_ZN4oboe35AudioStreamBuilder_setErrorCallbackEPNS_18AudioStreamBuilderEPvPFvS2_EPFbS2_PNS_11AudioStreamENS_6ResultEEPFvS2_S6_S7_ESB_"]
+    pub fn oboe_AudioStreamBuilder_setErrorCallback(
+        builder: *mut oboe_AudioStreamBuilder,
+        context: *mut ::std::os::raw::c_void,
+        drop_context: oboe_DropContextHandler,
+        error: oboe_ErrorHandler,
+        before_close: oboe_ErrorCloseHandler,
+        after_close: oboe_ErrorCloseHandler,
+    );
+}
+extern "C" {
+    #[link_name = "\u{1}_ZN4oboe37AudioStreamBuilder_resetErrorCallbackEPNS_18AudioStreamBuilderE"]
+    pub fn oboe_AudioStreamBuilder_resetErrorCallback(builder: *mut oboe_AudioStreamBuilder);
+}
 extern "C" {
     #[link_name = "\u{1}_ZN4oboe30AudioStreamBuilder_getAudioApiEPKNS_18AudioStreamBuilderE"]
     pub fn oboe_AudioStreamBuilder_getAudioApi(
@@ -1558,6 +1590,49 @@ extern "C" {
     pub fn oboe_AudioStream_getBase(oboeStream: *mut oboe_AudioStream)
         -> *mut oboe_AudioStreamBase;
 }
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct oboe_LatencyTunerWrapper {
+    _unused: [u8; 0],
+}
+extern "C" {
+    #[link_name = "\u{1}_ZN4oboe26LatencyTunerWrapper_createEPNS_11AudioStreamEi"]
+    pub fn oboe_LatencyTunerWrapper_create(
+        oboeStream: *mut oboe_AudioStream,
+        maximumBufferSize: i32,
+    ) -> *mut oboe_LatencyTunerWrapper;
+}
+extern "C" {
+    #[link_name = "\u{1}_ZN4oboe26LatencyTunerWrapper_deleteEPNS_19LatencyTunerWrapperE"]
+    pub fn oboe_LatencyTunerWrapper_delete(tuner: *mut oboe_LatencyTunerWrapper);
+}
+extern "C" {
+    #[link_name = "\u{1}_ZN4oboe24LatencyTunerWrapper_tuneEPNS_19LatencyTunerWrapperE"]
+    pub fn oboe_LatencyTunerWrapper_tune(tuner: *mut oboe_LatencyTunerWrapper) -> oboe_Result;
+}
+extern "C" {
+    #[link_name = "\u{1}_ZN4oboe32LatencyTunerWrapper_requestResetEPNS_19LatencyTunerWrapperE"]
+    pub fn oboe_LatencyTunerWrapper_requestReset(tuner: *mut oboe_LatencyTunerWrapper);
+}
+extern "C" {
+    #[link_name = "\u{1}_ZN4oboe41LatencyTunerWrapper_isAtMaximumBufferSizeEPNS_19LatencyTunerWrapperE"]
+    pub fn oboe_LatencyTunerWrapper_isAtMaximumBufferSize(
+        tuner: *mut oboe_LatencyTunerWrapper,
+    ) -> bool;
+}
+extern "C" {
+    #[link_name = "\u{1}_ZN4oboe40LatencyTunerWrapper_setMinimumBufferSizeEPNS_19LatencyTunerWrapperEi"]
+    pub fn oboe_LatencyTunerWrapper_setMinimumBufferSize(
+        tuner: *mut oboe_LatencyTunerWrapper,
+        bufferSize: i32,
+    );
+}
+extern "C" {
+    #[link_name = "\u{1}_ZN4oboe40LatencyTunerWrapper_getMinimumBufferSizeEPKNS_19LatencyTunerWrapperE"]
+    pub fn oboe_LatencyTunerWrapper_getMinimumBufferSize(
+        tuner: *const oboe_LatencyTunerWrapper,
+    ) -> i32;
+}
 pub type __kernel_clockid_t = ::std::os::raw::c_int;
 pub type __clockid_t = __kernel_clockid_t;
 pub type clockid_t = __clockid_t;
@@ -16,28 +16,28 @@ include!(concat!(env!("OUT_DIR"), "/bindings.rs"));
 
 #[cfg(all(
     not(feature = "generate-bindings"),
-    any(target_os = "android", test),
+    any(target_os = "android", feature = "test"),
     target_arch = "arm"
 ))]
 include!("bindings_armv7.rs");
 
 #[cfg(all(
     not(feature = "generate-bindings"),
-    any(target_os = "android", test),
+    any(target_os = "android", feature = "test"),
     target_arch = "aarch64"
 ))]
 include!("bindings_aarch64.rs");
 
 #[cfg(all(
     not(feature = "generate-bindings"),
-    any(target_os = "android", test),
+    any(target_os = "android", feature = "test"),
     target_arch = "x86"
 ))]
 include!("bindings_i686.rs");
 
 #[cfg(all(
     not(feature = "generate-bindings"),
-    any(target_os = "android", test),
+    any(target_os = "android", feature = "test"),
     target_arch = "x86_64"
 ))]
 include!("bindings_x86_64.rs");